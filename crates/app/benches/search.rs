@@ -0,0 +1,85 @@
+//! Compares the throughput of the search query AST with the compiled predicate tree.
+//!
+//! ```bash
+//! cargo bench -p yozefu-app --bench search
+//! ```
+
+use std::{hint::black_box, path::Path};
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use lib::{DataType, KafkaRecord};
+use yozefu_app::search::{Search, SearchContext, ValidSearchQuery};
+
+const QUERIES: [(&str, &str); 4] = [
+    ("json-pointer", r#"value.album.title == "Virtue""#),
+    (
+        "cheap-check-last",
+        r#"value contains "release" && key starts with "order-" && partition == 2"#,
+    ),
+    (
+        "timestamp",
+        r#"timestamp between "2024-11-23T12:00:00.000+01:00" and "2024-11-23T15:00:00.000+01:00""#,
+    ),
+    (
+        "mixed",
+        r#"(partition == 1 || partition == 4) and offset >= 3_460 and value.album.tracks[2].name != "Exit""#,
+    ),
+];
+
+fn records() -> Vec<KafkaRecord> {
+    (0..1_000)
+        .map(|i| {
+            let value = serde_json::json!({
+                "album": {
+                    "title": if i % 10 == 0 { "Virtue" } else { "Lost Highway" },
+                    "tracks": [{"name": "Intro"}, {"name": "Wires"}, {"name": format!("Track {}", i)}]
+                },
+                "release": { "year": 2000 + i % 25 }
+            });
+            KafkaRecord {
+                topic: "albums".to_string(),
+                timestamp: Some(1_732_356_000_000 + i * 60_000),
+                partition: (i % 6) as i32,
+                offset: i,
+                size: 256,
+                key: DataType::String(format!("order-{}", i)),
+                value: DataType::Json(value),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+fn bench_search(c: &mut Criterion) {
+    let records = records();
+    let filters_directory = Path::new(".");
+    let mut group = c.benchmark_group("search");
+    group.throughput(Throughput::Elements(records.len() as u64));
+    for (name, input) in QUERIES {
+        let query = ValidSearchQuery::from(input, filters_directory).unwrap();
+        group.bench_function(format!("{}/ast", name), |b| {
+            b.iter(|| {
+                records
+                    .iter()
+                    .filter(|r| {
+                        query
+                            .query()
+                            .matches(&SearchContext::new(black_box(r), filters_directory))
+                    })
+                    .count()
+            })
+        });
+        group.bench_function(format!("{}/compiled", name), |b| {
+            b.iter(|| {
+                records
+                    .iter()
+                    .filter(|r| query.matches(&SearchContext::new(black_box(r), filters_directory)))
+                    .count()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_search);
+criterion_main!(benches);
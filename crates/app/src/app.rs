@@ -1,22 +1,32 @@
 //! This app is both a kafka consumer and a kafka admin client.
 use lib::{
     ConsumerGroupDetail, Error, ExportedKafkaRecord, KafkaRecord, TopicDetail,
-    kafka::SchemaRegistryClient, search::offset::FromOffset,
+    kafka::SchemaRegistryClient,
+    search::{offset::FromOffset, sample::Sample},
 };
 use log::{info, warn};
 use rdkafka::{
     Offset, TopicPartitionList,
     consumer::{BaseConsumer, Consumer, StreamConsumer},
+    metadata::MetadataTopic,
 };
 use thousands::Separable;
 
-use std::{collections::HashSet, fs, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    time::Duration,
+};
 
 use itertools::Itertools;
 
 use crate::{
     configuration::{Configuration, InternalConfig, YozefuConfig},
-    search::{Search, ValidSearchQuery},
+    search::{
+        Search, ValidSearchQuery,
+        committed::{GroupOffsets, clear_committed_offsets, set_committed_offsets},
+        sample::PartitionSampler,
+    },
 };
 
 /// Struct exposing different functions for consuming kafka records.
@@ -51,8 +61,30 @@ impl App {
     }
 
     /// Create a kafka consumer
+    /// The offsets committed by the consumer groups of the search query are fetched beforehand.
     pub fn create_consumer(&self, topics: &Vec<String>) -> Result<StreamConsumer, Error> {
-        let offset = self.search_query.offset().unwrap_or(FromOffset::End);
+        clear_committed_offsets();
+        let mut committed = HashMap::new();
+        for group in self.search_query.consumer_groups() {
+            let group_committed = self.committed_offsets(&group, topics)?;
+            let offsets: GroupOffsets = group_committed
+                .elements()
+                .iter()
+                .filter_map(|e| match e.offset() {
+                    Offset::Offset(o) => Some(((e.topic().to_string(), e.partition()), o)),
+                    _ => None,
+                })
+                .collect();
+            set_committed_offsets(&group, offsets);
+            committed.insert(group, group_committed);
+        }
+
+        // `sample <n> per partition` reads the records already in the partitions
+        let default_offset = match self.search_query.query().sample {
+            Some(Sample::PerPartition(_)) => FromOffset::Beginning,
+            _ => FromOffset::End,
+        };
+        let offset = self.search_query.offset().unwrap_or(default_offset);
         match offset {
             FromOffset::Beginning => self.assign_partitions(topics, Offset::Beginning),
             FromOffset::End => self.assign_partitions(topics, Offset::End),
@@ -64,8 +96,8 @@ impl App {
                 for t in topics {
                     let metadata = consumer.fetch_metadata(Some(t), Duration::from_secs(10))?;
                     for m in metadata.topics() {
-                        for p in m.partitions() {
-                            tp.add_partition(m.name(), p.id());
+                        for p in self.partitions_to_consume(m) {
+                            tp.add_partition(m.name(), p);
                         }
                     }
                 }
@@ -74,7 +106,98 @@ impl App {
                 consumer.assign(&tt)?;
                 Ok(consumer)
             }
+            FromOffset::Group(group) => {
+                let consumer = self.create_assigned_consumer()?;
+                let mut assignments = TopicPartitionList::new();
+                // Partitions without committed offset are consumed from the end, like a new member of the group would
+                // The group of the `from` clause is one of the consumer groups fetched above
+                let group_committed = match committed.remove(&group) {
+                    Some(group_committed) => group_committed,
+                    None => self.committed_offsets(&group, topics)?,
+                };
+                for e in group_committed.elements() {
+                    let offset = match e.offset() {
+                        Offset::Offset(o) => Offset::Offset(o),
+                        _ => Offset::End,
+                    };
+                    assignments.add_partition_offset(e.topic(), e.partition(), offset)?;
+                }
+                consumer.assign(&assignments)?;
+                info!(
+                    "New Consumer created, about to consume {:?} from the offsets committed by '{}'",
+                    topics, group
+                );
+                Ok(consumer)
+            }
+        }
+    }
+
+    /// With `sample <n> per partition`, computes the positions to read between the assigned offsets and the end of each partition,
+    /// and moves the consumer to the first position of each partition.
+    pub fn create_sampler(
+        &self,
+        consumer: &StreamConsumer,
+    ) -> Result<Option<PartitionSampler>, Error> {
+        let Some(Sample::PerPartition(count)) = self.search_query.query().sample else {
+            return Ok(None);
+        };
+        let mut partitions = vec![];
+        for e in consumer.assignment()?.elements() {
+            let (low, high) =
+                consumer.fetch_watermarks(e.topic(), e.partition(), Duration::from_secs(10))?;
+            let start = match e.offset() {
+                Offset::Offset(o) => o.clamp(low, high),
+                Offset::OffsetTail(o) => (high - o).max(low),
+                Offset::End => high,
+                _ => low,
+            };
+            partitions.push((e.topic().to_string(), e.partition(), start, high));
+        }
+        let sampler = PartitionSampler::new(&partitions, count);
+        consumer.assign(&sampler.assignments())?;
+        info!(
+            "{} records are sampled on the following topic partitions: [{}]",
+            sampler.len().separate_with_underscores(),
+            partitions
+                .iter()
+                .map(|(topic, partition, _, _)| format!("{}-{}", topic, partition))
+                .join(", ")
+        );
+        Ok(Some(sampler))
+    }
+
+    /// Fetches the offsets committed by a consumer group on the partitions of the topics.
+    /// The consumer neither joins the group nor commits offsets.
+    fn committed_offsets(
+        &self,
+        group: &str,
+        topics: &[String],
+    ) -> Result<TopicPartitionList, Error> {
+        let mut properties = self.config.kafka_config_map();
+        properties.insert("group.id".to_string(), group.to_string());
+        properties.insert("enable.auto.commit".to_string(), "false".to_string());
+        let consumer: BaseConsumer =
+            InternalConfig::kafka_client_config_from_properties(properties).create()?;
+        let mut partitions = TopicPartitionList::new();
+        for topic in topics {
+            let metadata = consumer.fetch_metadata(Some(topic), Duration::from_secs(10))?;
+            for t in metadata.topics() {
+                for p in self.partitions_to_consume(t) {
+                    partitions.add_partition(topic, p);
+                }
+            }
         }
+        let committed = consumer.committed_offsets(partitions, Duration::from_secs(10))?;
+        info!(
+            "Offsets committed by consumer group '{}': [{}]",
+            group,
+            committed
+                .elements()
+                .iter()
+                .map(|e| format!("{}-{}: {:?}", e.topic(), e.partition(), e.offset()))
+                .join(", ")
+        );
+        Ok(committed)
     }
 
     /// Exports a given kafka record to a file.
@@ -173,8 +296,8 @@ impl App {
         for topic in topics {
             let metadata = consumer.fetch_metadata(Some(topic), Duration::from_secs(10))?;
             for t in metadata.topics() {
-                for p in t.partitions() {
-                    assignments.add_partition_offset(topic, p.id(), offset)?;
+                for p in self.partitions_to_consume(t) {
+                    assignments.add_partition_offset(topic, p, offset)?;
                 }
             }
         }
@@ -183,6 +306,32 @@ impl App {
         Ok(consumer)
     }
 
+    /// Returns the partitions of the topic that need to be consumed.
+    /// When the search query pins the key (`key == "my-key"`) and a partitioner is configured for the topic,
+    /// only the partition the key belongs to is returned. An empty key is never pinned.
+    fn partitions_to_consume(&self, topic: &MetadataTopic) -> Vec<i32> {
+        let partitions = topic.partitions().iter().map(|p| p.id()).collect_vec();
+        let (Some(key), Some(partitioner)) = (
+            self.search_query.pinned_key(),
+            self.config.partitioner_of(topic.name()),
+        ) else {
+            return partitions;
+        };
+        match partitioner.pinned_partition(&key, partitions.len()) {
+            Some(partition) => {
+                info!(
+                    "Key '{}' belongs to partition {} of topic '{}' according to the '{}' partitioner",
+                    key,
+                    partition,
+                    topic.name(),
+                    partitioner
+                );
+                vec![partition]
+            }
+            None => partitions,
+        }
+    }
+
     /// Returns the topics details for a given list topics
     /// This function is not ready yet
     pub fn topic_details(&self, topics: HashSet<String>) -> Result<Vec<TopicDetail>, Error> {
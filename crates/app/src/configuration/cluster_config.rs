@@ -6,6 +6,8 @@ use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, path::PathBuf};
 use url::Url;
 
+use crate::partitioner::Partitioner;
+
 use super::Configuration;
 
 /// List of kafka properties that are a file location.
@@ -24,6 +26,8 @@ impl Default for ClusterConfig {
             url_template: Some(default_url_template()),
             schema_registry: None,
             kafka: Default::default(),
+            partitioner: None,
+            topic_partitioners: Default::default(),
         }
     }
 }
@@ -38,6 +42,13 @@ pub struct ClusterConfig {
     pub schema_registry: Option<SchemaRegistryConfig>,
     // Kafka consumer properties for this cluster, see <https://github.com/confluentinc/librdkafka/blob/master/CONFIGURATION.md> for more details
     pub kafka: IndexMap<String, String>,
+    /// Partitioner used by the producers of this cluster.
+    /// When set, a search query pinning the key (`key == "my-key"`) only consumes the partition the key belongs to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partitioner: Option<Partitioner>,
+    /// Partitioner of specific topics, it takes precedence over `partitioner`
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub topic_partitioners: IndexMap<String, Partitioner>,
 }
 
 impl ClusterConfig {
@@ -57,6 +68,14 @@ impl ClusterConfig {
         }
         cloned
     }
+
+    /// Returns the partitioner of the given topic, if any.
+    pub fn partitioner_of(&self, topic: &str) -> Option<Partitioner> {
+        self.topic_partitioners
+            .get(topic)
+            .copied()
+            .or(self.partitioner)
+    }
 }
 
 /// Schema registry configuration of a given cluster
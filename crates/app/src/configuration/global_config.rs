@@ -12,9 +12,15 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use crate::APPLICATION_NAME;
+use crate::{
+    APPLICATION_NAME,
+    search::filter::{FilterErrorPolicy, FilterLimits},
+};
 
-use super::cluster_config::{ClusterConfig, SchemaRegistryConfig};
+use super::{
+    cluster_config::{ClusterConfig, SchemaRegistryConfig},
+    saved_query::{SavedQuery, resolve_query},
+};
 
 const EXAMPLE_PROMPTS: &[&str] = &[
     r#"timestamp between "2 hours ago" and "1 hour ago" limit 100 from beginning"#,
@@ -47,11 +53,20 @@ pub struct GlobalConfig {
     pub default_kafka_config: IndexMap<String, String>,
     /// History of past search queries
     pub history: Vec<String>,
+    /// Named search queries, invoked with `@name(parameter=value)`
+    #[serde(default)]
+    pub queries: IndexMap<String, SavedQuery>,
     /// Show shortcuts
     #[serde(default = "default_show_shortcuts")]
     pub show_shortcuts: bool,
     #[serde(default = "default_export_directory")]
     pub export_directory: PathBuf,
+    /// What a search filter returns when the wasm module fails: `match`, `no-match` or `abort`
+    #[serde(default)]
+    pub filter_error_policy: FilterErrorPolicy,
+    /// Timeout and memory limits of the search filters
+    #[serde(default)]
+    pub filter_limits: FilterLimits,
 }
 
 fn default_url_template() -> String {
@@ -84,12 +99,15 @@ impl TryFrom<&PathBuf> for GlobalConfig {
             logs: None,
             default_url_template: default_url_template(),
             history: EXAMPLE_PROMPTS.iter().map(|e| e.to_string()).collect_vec(),
+            queries: IndexMap::default(),
             initial_query: "from end - 10".to_string(),
             clusters: IndexMap::default(),
             default_kafka_config: IndexMap::default(),
             theme: default_theme(),
             show_shortcuts: true,
             export_directory: default_export_directory(),
+            filter_error_policy: FilterErrorPolicy::default(),
+            filter_limits: FilterLimits::default(),
         })
     }
 }
@@ -168,6 +186,11 @@ impl GlobalConfig {
             .unwrap_or(self.default_url_template.clone())
     }
 
+    /// Resolves `@name(parameter=value)` and `@file` inputs to a search query.
+    pub fn resolve_query(&self, input: &str) -> Result<String, Error> {
+        resolve_query(input, &self.queries)
+    }
+
     /// Returns the schema registry configuration for the given cluster.
     pub fn schema_registry_config_of(&self, cluster: &str) -> Option<SchemaRegistryConfig> {
         self.clusters
@@ -4,7 +4,10 @@ use std::{collections::HashMap, path::PathBuf};
 
 use chrono::Local;
 
-use crate::configuration::{GlobalConfig, SchemaRegistryConfig};
+use crate::{
+    configuration::{GlobalConfig, SchemaRegistryConfig},
+    partitioner::Partitioner,
+};
 
 use super::{Configuration, yozefu_config::YozefuConfig};
 
@@ -60,6 +63,11 @@ impl InternalConfig {
         }
     }
 
+    /// Returns the partitioner of the given topic, if any.
+    pub fn partitioner_of(&self, topic: &str) -> Option<Partitioner> {
+        self.specific.partitioner_of(topic)
+    }
+
     /// Returns the output file path for exported kafka records.
     pub fn output_file(&self) -> &PathBuf {
         &self.output_file
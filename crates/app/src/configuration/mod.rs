@@ -6,6 +6,7 @@ use rdkafka::{ClientConfig, config::FromClientConfig};
 mod cluster_config;
 mod global_config;
 mod internal_config;
+mod saved_query;
 mod yozefu_config;
 
 pub use cluster_config::ClusterConfig;
@@ -13,6 +14,7 @@ pub use cluster_config::KAFKA_PROPERTIES_WITH_LOCATIONS;
 pub use cluster_config::SchemaRegistryConfig;
 pub use global_config::GlobalConfig;
 pub use internal_config::InternalConfig;
+pub use saved_query::{SavedQuery, resolve_query};
 pub use yozefu_config::YozefuConfig;
 
 pub trait Configuration {
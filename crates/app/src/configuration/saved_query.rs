@@ -0,0 +1,310 @@
+//! Saved queries are search queries stored in the configuration file under a name.
+//!
+//! ```json
+//! "queries": {
+//!   "customer-orders": {
+//!     "description": "Orders of a customer",
+//!     "query": "value.customer == $customer and timestamp >= now - $since from end - 10000",
+//!     "parameters": { "since": "1h" }
+//!   }
+//! }
+//! ```
+//!
+//! They are invoked with `@customer-orders(customer="C-42", since=24h)`.
+//! `@path/to/query.sql` reads the query from a file instead, the file can span several lines and contain `--` comments.
+//! Arguments are inserted as written, a string must be quoted.
+//! Placeholders are only replaced in saved queries and never inside string literals:
+//! `"$5"` and the content of a file are kept as they are.
+
+use std::{fs, path::Path};
+
+use indexmap::IndexMap;
+use itertools::Itertools;
+use lib::Error;
+use serde::{Deserialize, Serialize};
+
+/// A search query stored in the configuration.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Default)]
+pub struct SavedQuery {
+    /// The search query, `$name` or `${name}` are replaced by the arguments of the invocation.
+    pub query: String,
+    /// A short description displayed by the TUI
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Default values of the parameters
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub parameters: IndexMap<String, String>,
+}
+
+impl SavedQuery {
+    /// Returns the names of the parameters used in the query, in order of appearance.
+    pub fn parameter_names(&self) -> Vec<String> {
+        placeholders(&strip_comments(&self.query))
+            .into_iter()
+            .unique()
+            .collect()
+    }
+
+    /// Returns the invocation of this query, with the parameters without default values left empty.
+    pub fn invocation(&self, name: &str) -> String {
+        let missing = self
+            .parameter_names()
+            .into_iter()
+            .filter(|p| !self.parameters.contains_key(p))
+            .map(|p| format!("{}=", p))
+            .collect_vec();
+        match missing.is_empty() {
+            true => format!("@{}", name),
+            false => format!("@{}({})", name, missing.join(", ")),
+        }
+    }
+}
+
+/// Returns the search query the input refers to.
+/// Inputs that don't start with `@` are returned as they are.
+pub fn resolve_query(input: &str, queries: &IndexMap<String, SavedQuery>) -> Result<String, Error> {
+    let input = input.trim();
+    let Some(invocation) = input.strip_prefix('@') else {
+        return Ok(input.to_string());
+    };
+    let (name, arguments) = parse_invocation(invocation)?;
+
+    let Some(saved) = queries.get(name) else {
+        let file = Path::new(name);
+        let content = fs::read_to_string(file).map_err(|e| {
+            Error::Error(format!(
+                "'{}' is neither a saved query nor a readable file {:?}: {}",
+                name,
+                file.display(),
+                e
+            ))
+        })?;
+        if !arguments.is_empty() {
+            return Err(Error::Error(format!(
+                "'@{}' is a file, it doesn't accept parameters",
+                name
+            )));
+        }
+        return Ok(strip_comments(&content));
+    };
+
+    let template = strip_comments(&saved.query);
+    let names = placeholders(&template);
+    if let Some(unknown) = arguments.keys().find(|a| !names.contains(a)) {
+        return Err(Error::Error(format!(
+            "Unknown parameter '{}' for '@{}', expected one of: {}",
+            unknown,
+            name,
+            names.iter().unique().join(", ")
+        )));
+    }
+    let mut values = saved.parameters.clone();
+    values.extend(arguments);
+    substitute(&template, &values).map_err(|parameter| {
+        Error::Error(format!(
+            "Missing value for parameter '{}' of '@{}'",
+            parameter, name
+        ))
+    })
+}
+
+/// Splits `name(a=1, b="x")` into the name and the arguments.
+fn parse_invocation(invocation: &str) -> Result<(&str, IndexMap<String, String>), Error> {
+    let Some((name, arguments)) = invocation.strip_suffix(')').and_then(|i| i.split_once('('))
+    else {
+        return Ok((invocation, IndexMap::default()));
+    };
+    let mut parsed = IndexMap::default();
+    for argument in split_outside_quotes(arguments, ',') {
+        if argument.trim().is_empty() {
+            continue;
+        }
+        let Some((key, value)) = argument.split_once('=') else {
+            return Err(Error::Error(format!(
+                "Invalid argument '{}', expected 'name=value'",
+                argument.trim()
+            )));
+        };
+        parsed.insert(key.trim().to_string(), value.trim().to_string());
+    }
+    Ok((name.trim(), parsed))
+}
+
+/// Splits the input on the separator, ignoring separators inside quoted strings.
+fn split_outside_quotes(input: &str, separator: char) -> Vec<String> {
+    let mut parts = vec![];
+    let mut current = String::new();
+    let mut quote = None;
+    for c in input.chars() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, c) if c == separator => {
+                parts.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => (),
+        }
+        current.push(c);
+    }
+    parts.push(current);
+    parts
+}
+
+/// Removes `--` comments outside of quoted strings and joins the lines.
+fn strip_comments(query: &str) -> String {
+    query
+        .lines()
+        .map(|line| {
+            let mut quote = None;
+            let mut previous = None;
+            for (index, c) in line.char_indices() {
+                match (quote, c) {
+                    (None, '"' | '\'') => quote = Some(c),
+                    (Some(q), _) if q == c => quote = None,
+                    (None, '-') if previous == Some('-') => return line[..index - 1].trim(),
+                    _ => (),
+                }
+                previous = Some(c);
+            }
+            line.trim()
+        })
+        .filter(|line| !line.is_empty())
+        .join(" ")
+}
+
+/// Returns the names of the `$name` and `${name}` placeholders outside of string literals.
+fn placeholders(template: &str) -> Vec<String> {
+    let mut names = vec![];
+    let _ = substitute_with(template, |name| {
+        names.push(name.to_string());
+        Some(String::new())
+    });
+    names
+}
+
+/// Replaces the placeholders with their value. `$$` is a literal `$`.
+/// A name starts with a letter, string literals are left untouched.
+/// Returns the name of the first parameter without value.
+fn substitute(template: &str, values: &IndexMap<String, String>) -> Result<String, String> {
+    substitute_with(template, |name| values.get(name).cloned())
+}
+
+fn substitute_with(
+    template: &str,
+    mut value_of: impl FnMut(&str) -> Option<String>,
+) -> Result<String, String> {
+    let mut result = String::new();
+    let mut chars = template.chars().peekable();
+    let mut quote = None;
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            _ => (),
+        }
+        if c != '$' || quote.is_some() {
+            result.push(c);
+            continue;
+        }
+        let braced = chars.next_if_eq(&'{').is_some();
+        if !braced && chars.next_if_eq(&'$').is_some() {
+            result.push('$');
+            continue;
+        }
+        let mut name = String::new();
+        if let Some(c) = chars.next_if(|c| c.is_ascii_alphabetic()) {
+            name.push(c);
+            while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                name.push(c);
+            }
+        }
+        let closed = !braced || chars.next_if_eq(&'}').is_some();
+        if name.is_empty() || !closed {
+            result.push_str(if braced { "${" } else { "$" });
+            result.push_str(&name);
+            continue;
+        }
+        result.push_str(&value_of(&name).ok_or(name)?);
+    }
+    Ok(result)
+}
+
+#[test]
+fn test_resolve_saved_query() {
+    let queries = IndexMap::from([(
+        "customer-orders".to_string(),
+        SavedQuery {
+            query:
+                "value.customer == $customer -- the customer ID\n and timestamp >= now - ${since}"
+                    .to_string(),
+            description: None,
+            parameters: IndexMap::from([("since".to_string(), "1h".to_string())]),
+        },
+    )]);
+    assert_eq!(
+        resolve_query(r#"@customer-orders(customer="C-42")"#, &queries).unwrap(),
+        r#"value.customer == "C-42" and timestamp >= now - 1h"#
+    );
+    assert_eq!(
+        resolve_query(r#"@customer-orders(customer='a, b', since=2d)"#, &queries).unwrap(),
+        r#"value.customer == 'a, b' and timestamp >= now - 2d"#
+    );
+    assert!(resolve_query("@customer-orders", &queries).is_err());
+    assert!(resolve_query("@customer-orders(customer=1, limit=3)", &queries).is_err());
+    assert!(resolve_query("@unknown-query", &queries).is_err());
+    assert_eq!(
+        resolve_query(r#"value contains "$5""#, &queries).unwrap(),
+        r#"value contains "$5""#
+    );
+}
+
+#[test]
+fn test_strip_comments() {
+    assert_eq!(
+        strip_comments("-- header\nkey == \"a--b\" -- the key\n\n  from end - 10\n"),
+        r#"key == "a--b" from end - 10"#
+    );
+}
+
+#[test]
+fn test_invocation() {
+    let saved = SavedQuery {
+        query: "key == $key and offset > $offset and key != $key".to_string(),
+        description: None,
+        parameters: IndexMap::from([("offset".to_string(), "0".to_string())]),
+    };
+    assert_eq!(saved.parameter_names(), vec!["key", "offset"]);
+    assert_eq!(saved.invocation("by-key"), "@by-key(key=)");
+    assert_eq!(
+        substitute(
+            "price $$5 ${a}b ${c $",
+            &IndexMap::from([("a".to_string(), "1".to_string())])
+        ),
+        Ok("price $5 1b ${c $".to_string())
+    );
+    assert_eq!(
+        substitute(
+            r#"value == "$a" and price > $5 and key == '${a}' and offset > $a"#,
+            &IndexMap::from([("a".to_string(), "1".to_string())])
+        ),
+        Ok(r#"value == "$a" and price > $5 and key == '${a}' and offset > 1"#.to_string())
+    );
+}
+
+#[test]
+fn test_resolve_file_query() {
+    let directory = tempfile::tempdir().unwrap();
+    let file = directory.path().join("price.sql");
+    fs::write(
+        &file,
+        "-- a literal dollar\nvalue.price == \"$5\" and key == $key",
+    )
+    .unwrap();
+    let invocation = format!("@{}", file.display());
+    assert_eq!(
+        resolve_query(&invocation, &IndexMap::default()).unwrap(),
+        r#"value.price == "$5" and key == $key"#
+    );
+    assert!(resolve_query(&format!("{}(key=1)", invocation), &IndexMap::default()).is_err());
+}
@@ -1,7 +1,7 @@
 //! module defining the configuration of the yozefu application
 
 use super::{Configuration, SchemaRegistryConfig};
-use crate::{APPLICATION_NAME, configuration::ClusterConfig};
+use crate::{APPLICATION_NAME, configuration::ClusterConfig, partitioner::Partitioner};
 use std::{collections::HashMap, path::PathBuf};
 
 /// composed of kafka properties and
@@ -30,6 +30,10 @@ impl YozefuConfig {
         self.cluster_config.schema_registry.clone()
     }
 
+    pub fn partitioner_of(&self, topic: &str) -> Option<Partitioner> {
+        self.cluster_config.partitioner_of(topic)
+    }
+
     pub fn with_exported_directory(self, exported_directory: PathBuf) -> Self {
         Self {
             cluster_config: self.cluster_config,
@@ -59,6 +63,8 @@ impl YozefuConfig {
                 url_template: None,
                 schema_registry: None,
                 kafka: indexmap::IndexMap::from_iter(kafka_properties),
+                partitioner: None,
+                topic_partitioners: Default::default(),
             },
             logs_file: self.logs_file,
             export_directory: self.export_directory,
@@ -4,6 +4,7 @@
 //! - Consume records.
 mod app;
 pub mod configuration;
+pub mod partitioner;
 pub mod search;
 
 pub use app::App;
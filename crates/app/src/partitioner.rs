@@ -0,0 +1,121 @@
+//! Partitioners compute the partition a kafka record is written to, based on its key.
+//!
+//! When the search query pins the key (`key == "my-key"`), yozefu can use the partitioner
+//! of the producers to only consume the partition the key belongs to,
+//! instead of reading every partition of the topic.
+//!
+//! The key is hashed as a UTF-8 string. Keys serialized with a schema registry (Avro, Protobuf, JSON schema)
+//! are hashed by the producers with the magic byte and the schema id, so they don't end up on the computed partition.
+//!
+//! The implementations follow the ones of the Java client and [librdkafka](https://github.com/confluentinc/librdkafka/blob/master/CONFIGURATION.md#topic-configuration-properties).
+
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString};
+
+/// Partitioners supported by yozefu.
+/// The names are the ones of the librdkafka `partitioner` property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, Display, EnumString)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Partitioner {
+    /// Default partitioner of the Java client: `murmur2(key) & 0x7fffffff % partitions`.
+    Murmur2,
+    /// Same as `murmur2` for non-empty keys.
+    Murmur2Random,
+    /// Default partitioner of librdkafka based clients: `crc32(key) % partitions`.
+    Consistent,
+    /// Same as `consistent` for non-empty keys.
+    ConsistentRandom,
+}
+
+impl Partitioner {
+    /// Returns the partition the given key belongs to.
+    pub fn partition(&self, key: &[u8], number_of_partitions: usize) -> i32 {
+        let number_of_partitions = number_of_partitions.max(1) as u32;
+        let partition = match self {
+            Partitioner::Murmur2 | Partitioner::Murmur2Random => {
+                (murmur2(key) & 0x7fffffff) % number_of_partitions
+            }
+            Partitioner::Consistent | Partitioner::ConsistentRandom => {
+                crc32fast::hash(key) % number_of_partitions
+            }
+        };
+        partition as i32
+    }
+
+    /// Returns the only partition that can contain records with the key pinned by the search query.
+    /// An empty key is never pinned: null keys are decoded as empty strings,
+    /// and the `*_random` partitioners send empty and null keys to a random partition.
+    pub fn pinned_partition(&self, key: &str, number_of_partitions: usize) -> Option<i32> {
+        match key.is_empty() || number_of_partitions == 0 {
+            true => None,
+            false => Some(self.partition(key.as_bytes(), number_of_partitions)),
+        }
+    }
+}
+
+/// Murmur2 hash as implemented by the Kafka Java client.
+fn murmur2(data: &[u8]) -> u32 {
+    const SEED: u32 = 0x9747b28c;
+    const M: u32 = 0x5bd1e995;
+    const R: u32 = 24;
+
+    let length = data.len();
+    let mut h: u32 = SEED ^ (length as u32);
+
+    let mut chunks = data.chunks_exact(4);
+    for chunk in chunks.by_ref() {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h = h.wrapping_mul(M);
+        h ^= k;
+    }
+
+    let remainder = chunks.remainder();
+    if remainder.len() >= 3 {
+        h ^= (remainder[2] as u32) << 16;
+    }
+    if remainder.len() >= 2 {
+        h ^= (remainder[1] as u32) << 8;
+    }
+    if !remainder.is_empty() {
+        h ^= remainder[0] as u32;
+        h = h.wrapping_mul(M);
+    }
+
+    h ^= h >> 13;
+    h = h.wrapping_mul(M);
+    h ^= h >> 15;
+    h
+}
+
+#[test]
+fn test_murmur2() {
+    // Test vectors of the Kafka Java client
+    assert_eq!(murmur2(b"21") as i32, -973932308);
+    assert_eq!(murmur2(b"foobar") as i32, -790332482);
+    assert_eq!(murmur2(b"a-little-bit-long-string") as i32, -985981536);
+    assert_eq!(murmur2(b"a-little-bit-longer-string") as i32, -1486304829);
+    assert_eq!(
+        murmur2(b"lkjh234lh9fiuh90y23oiuhsafujhadof229phr9h19h89h8") as i32,
+        -58897971
+    );
+    assert_eq!(murmur2(b"abc") as i32, 479470107);
+}
+
+#[test]
+fn test_partition() {
+    assert_eq!(Partitioner::Murmur2.partition(b"21", 6), 0);
+    assert_eq!(Partitioner::Consistent.partition(b"123456789", 7), 5);
+    assert_eq!(Partitioner::ConsistentRandom.partition(b"123456789", 1), 0);
+}
+
+#[test]
+fn test_pinned_partition() {
+    assert_eq!(Partitioner::Murmur2.pinned_partition("21", 6), Some(0));
+    assert_eq!(Partitioner::Murmur2Random.pinned_partition("", 6), None);
+    assert_eq!(Partitioner::Consistent.pinned_partition("", 6), None);
+    assert_eq!(Partitioner::Consistent.pinned_partition("21", 0), None);
+}
@@ -0,0 +1,290 @@
+//! Incremental evaluation of the `group by` clause.
+//!
+//! Matching records are pushed one by one into an [`Aggregator`],
+//! the aggregated rows can be read at any time to display a live table.
+//! At most [`MAX_GROUPS`] groups are kept, the records of the next groups are counted in [`OTHER_GROUPS`].
+
+use std::{collections::HashMap, sync::OnceLock};
+
+use itertools::Itertools;
+use lib::{
+    DataType, KafkaRecord,
+    search::{
+        group_by::{Aggregate, GroupBy, symbol_to_query},
+        symbol::Symbol,
+    },
+};
+use serde::Serialize;
+
+use super::compiled::{json_pointer, json_scalar};
+
+/// Maximum number of groups of an aggregation.
+pub const MAX_GROUPS: usize = 10_000;
+
+/// Group of the records whose group doesn't fit in the aggregation anymore.
+pub const OTHER_GROUPS: &str = "(other groups)";
+
+/// A group and its aggregated metrics.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AggregatedRow {
+    pub group: String,
+    pub count: usize,
+    /// Result of `min` or `max`, `None` for `count` or when no record had a numeric value.
+    pub value: Option<f64>,
+}
+
+impl AggregatedRow {
+    /// Returns the cells of the row, in the same order as [`Aggregator::columns`].
+    pub fn cells(&self, group_by: &GroupBy) -> Vec<String> {
+        let mut cells = vec![self.group.clone(), self.count.to_string()];
+        if group_by.aggregate != Aggregate::Count {
+            cells.push(self.value.map(format_number).unwrap_or_default());
+        }
+        cells
+    }
+}
+
+/// Aggregates kafka records according to a `group by` clause.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aggregator {
+    group_by: GroupBy,
+    groups: HashMap<String, AggregatedRow>,
+    /// Sorted rows, computed again once a record is pushed
+    rows: OnceLock<Vec<AggregatedRow>>,
+}
+
+impl Aggregator {
+    pub fn new(group_by: GroupBy) -> Self {
+        Self {
+            group_by,
+            groups: HashMap::new(),
+            rows: OnceLock::new(),
+        }
+    }
+
+    pub fn group_by(&self) -> &GroupBy {
+        &self.group_by
+    }
+
+    pub fn reset(&mut self) {
+        self.groups.clear();
+        self.rows.take();
+    }
+
+    /// Names of the columns of the aggregated table.
+    pub fn columns(&self) -> Vec<String> {
+        let mut columns = vec![symbol_to_query(&self.group_by.key), "count".to_string()];
+        if self.group_by.aggregate != Aggregate::Count {
+            columns.push(self.group_by.aggregate.to_string());
+        }
+        columns
+    }
+
+    /// Adds a matching record to its group.
+    pub fn push(&mut self, record: &KafkaRecord) {
+        self.rows.take();
+        let mut group = group_of(&self.group_by.key, record);
+        if self.groups.len() >= MAX_GROUPS && !self.groups.contains_key(&group) {
+            group = OTHER_GROUPS.to_string();
+        }
+        let row = self
+            .groups
+            .entry(group.clone())
+            .or_insert_with(|| AggregatedRow {
+                group,
+                count: 0,
+                value: None,
+            });
+        row.count += 1;
+        let (symbol, keep_lowest) = match &self.group_by.aggregate {
+            Aggregate::Count => return,
+            Aggregate::Min(symbol) => (symbol, true),
+            Aggregate::Max(symbol) => (symbol, false),
+        };
+        if let Some(number) = number_of(symbol, record) {
+            row.value = match row.value {
+                Some(v) if keep_lowest => Some(v.min(number)),
+                Some(v) => Some(v.max(number)),
+                None => Some(number),
+            };
+        }
+    }
+
+    /// Returns the aggregated rows, the biggest groups first, limited by the `top` keyword.
+    /// The rows are sorted once, until the next record is pushed.
+    pub fn rows(&self) -> &[AggregatedRow] {
+        self.rows.get_or_init(|| {
+            let rows = self.groups.values().sorted_by(|a, b| {
+                let ordering = match &self.group_by.aggregate {
+                    Aggregate::Count => b.count.cmp(&a.count),
+                    Aggregate::Min(_) => compare_values(a.value, b.value),
+                    Aggregate::Max(_) => compare_values(a.value.map(|v| -v), b.value.map(|v| -v)),
+                };
+                ordering.then_with(|| a.group.cmp(&b.group))
+            });
+            match self.group_by.top {
+                Some(top) => rows.take(top).cloned().collect(),
+                None => rows.cloned().collect(),
+            }
+        })
+    }
+
+    /// Serializes the aggregated rows to a JSON array of objects.
+    pub fn to_json(&self) -> serde_json::Value {
+        let columns = self.columns();
+        let rows = self.rows();
+        serde_json::Value::Array(
+            rows.iter()
+                .map(|row| {
+                    let mut object = serde_json::Map::new();
+                    object.insert(columns[0].clone(), row.group.clone().into());
+                    object.insert(columns[1].clone(), row.count.into());
+                    if let Some(column) = columns.get(2) {
+                        object.insert(column.clone(), row.value.into());
+                    }
+                    serde_json::Value::Object(object)
+                })
+                .collect(),
+        )
+    }
+
+    /// Serializes the aggregated rows to CSV, with a header line.
+    pub fn to_csv(&self) -> String {
+        let mut lines = vec![self.columns().iter().map(|c| csv_field(c)).join(",")];
+        for row in self.rows() {
+            lines.push(
+                row.cells(&self.group_by)
+                    .iter()
+                    .map(|c| csv_field(c))
+                    .join(","),
+            );
+        }
+        lines.join("\n")
+    }
+}
+
+/// Rows without a value are listed last.
+fn compare_values(a: Option<f64>, b: Option<f64>) -> std::cmp::Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    }
+}
+
+fn format_number(number: f64) -> String {
+    match number.fract() == 0.0 && number.abs() < 1e15 {
+        true => format!("{}", number as i64),
+        false => number.to_string(),
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    match field.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+fn value_at(data: &DataType, path: &Option<String>) -> Option<String> {
+    match (data, path) {
+        (data, None) => Some(data.raw()),
+        (DataType::Json(value), Some(path)) => {
+            value
+                .pointer(&json_pointer(path))
+                .map(|v| match json_scalar(v) {
+                    Some(s) => s.to_string(),
+                    None => v.to_string(),
+                })
+        }
+        (DataType::String(_), Some(_)) => None,
+    }
+}
+
+/// Returns the group the record belongs to. Missing values are grouped under `null`.
+fn group_of(symbol: &Symbol, record: &KafkaRecord) -> String {
+    let group = match symbol {
+        Symbol::Topic => Some(record.topic.clone()),
+        Symbol::Partition => Some(record.partition.to_string()),
+        Symbol::Offset => Some(record.offset.to_string()),
+        Symbol::Size => Some(record.size.to_string()),
+        Symbol::Timestamp => record.timestamp.map(|t| t.to_string()),
+        Symbol::Key => Some(record.key.raw()),
+        Symbol::Value(path) => value_at(&record.value, path),
+        Symbol::Header(name) => record.headers.get(name).cloned(),
+        Symbol::OffsetTail => None,
+    };
+    group.unwrap_or("null".to_string())
+}
+
+/// Returns the numeric value of the symbol, `None` if it's not a number.
+fn number_of(symbol: &Symbol, record: &KafkaRecord) -> Option<f64> {
+    match symbol {
+        Symbol::Partition => Some(record.partition as f64),
+        Symbol::Offset => Some(record.offset as f64),
+        Symbol::Size => Some(record.size as f64),
+        Symbol::Timestamp => record.timestamp.map(|t| t as f64),
+        Symbol::Value(path) => value_at(&record.value, path).and_then(|v| v.parse().ok()),
+        Symbol::Key => record.key.raw().parse().ok(),
+        Symbol::Header(name) => record.headers.get(name).and_then(|v| v.parse().ok()),
+        Symbol::Topic | Symbol::OffsetTail => None,
+    }
+}
+
+#[cfg(test)]
+fn record(key: &str, value: serde_json::Value, size: usize) -> KafkaRecord {
+    KafkaRecord {
+        key: DataType::String(key.to_string()),
+        value: DataType::Json(value),
+        size,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+fn aggregator(input: &str) -> Aggregator {
+    Aggregator::new(lib::parse_search_query(input).unwrap().1.group_by.unwrap())
+}
+
+#[test]
+fn test_count_by() {
+    let mut aggregator = aggregator("count by value.type top 2");
+    for (t, size) in [("click", 10), ("view", 20), ("click", 30), ("buy", 40)] {
+        aggregator.push(&record("k", serde_json::json!({"type": t}), size));
+    }
+    aggregator.push(&record("k", serde_json::json!({}), 1));
+    assert_eq!(
+        aggregator.to_csv(),
+        "value.type,count\nclick,2\nbuy,1".to_string()
+    );
+}
+
+#[test]
+fn test_max_by() {
+    let mut aggregator = aggregator("group by key max value.price");
+    aggregator.push(&record("a", serde_json::json!({"price": 10}), 0));
+    aggregator.push(&record("b", serde_json::json!({"price": "12.5"}), 0));
+    aggregator.push(&record("a", serde_json::json!({"price": 30}), 0));
+    aggregator.push(&record("c", serde_json::json!({"price": [1]}), 0));
+    assert_eq!(
+        aggregator.to_json(),
+        serde_json::json!([
+            {"key": "a", "count": 2, "max value.price": 30.0},
+            {"key": "b", "count": 1, "max value.price": 12.5},
+            {"key": "c", "count": 1, "max value.price": null},
+        ])
+    );
+}
+
+#[test]
+fn test_max_groups() {
+    let mut aggregator = aggregator("count by key");
+    for key in 0..MAX_GROUPS + 2 {
+        aggregator.push(&record(&key.to_string(), serde_json::json!({}), 0));
+    }
+    let rows = aggregator.rows();
+    assert_eq!(rows.len(), MAX_GROUPS + 1);
+    assert_eq!(rows[0].group, OTHER_GROUPS);
+    assert_eq!(rows[0].count, 2);
+}
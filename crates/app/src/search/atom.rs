@@ -12,6 +12,14 @@ impl Search for Atom {
         }
     }
 
+    fn pinned_key(&self) -> Option<String> {
+        match self {
+            Atom::Compare(c) => c.pinned_key(),
+            Atom::Parenthesis(c) => c.pinned_key(),
+            _ => None,
+        }
+    }
+
     fn matches(&self, context: &SearchContext) -> bool {
         match self {
             Atom::Symbol(_) => false,
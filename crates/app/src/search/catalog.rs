@@ -0,0 +1,142 @@
+//! Search filters installed in the filters directory.
+//!
+//! A search filter is a `<name>.wasm` file. The optional `describe` function of the wasm module
+//! returns a [`FilterDescription`]: what the search filter does, its version and its parameters.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use extism::{Manifest, Wasm, convert::Json};
+use itertools::Itertools;
+use lib::FilterDescription;
+
+use super::{filter::filter_limits, plugin_pool::PluginPool};
+
+pub const DESCRIBE_FUNCTION_NAME: &str = "describe";
+
+/// What is known about an installed search filter.
+#[derive(Debug, Clone)]
+pub struct FilterInfo {
+    pub name: String,
+    pub path: PathBuf,
+    /// Size of the wasm module, in bytes
+    pub size: u64,
+    /// Functions of the search filter API exported by the wasm module
+    pub exports: Vec<&'static str>,
+    /// `true` when the search filter uses the key/value store
+    pub stateful: bool,
+    /// What `describe` returns, `None` when the wasm module doesn't export it
+    pub description: Option<FilterDescription>,
+}
+
+impl FilterInfo {
+    /// Loads the wasm module of the search filter and calls `describe`.
+    pub fn load(filters_directory: &Path, name: &str) -> Result<Self, lib::Error> {
+        validate_filter_name(name)?;
+        let path = filter_path(filters_directory, name);
+        let size = fs::metadata(&path)
+            .map_err(|e| lib::Error::Error(format!("{}: {}", path.display(), e)))?
+            .len();
+        let pool = PluginPool::new(Manifest::new([Wasm::file(&path)]), filter_limits().of(name))
+            .map_err(|e| lib::Error::Error(format!("{}: {}", path.display(), e)))?;
+        let description = match pool.exports(DESCRIBE_FUNCTION_NAME) {
+            true => Some(
+                pool.call::<&str, Json<FilterDescription>>(DESCRIBE_FUNCTION_NAME, "")
+                    .map_err(|e| {
+                        lib::Error::Error(format!(
+                            "Error when calling '{}' from wasm module '{}': {}",
+                            DESCRIBE_FUNCTION_NAME, name, e
+                        ))
+                    })?
+                    .0,
+            ),
+            false => None,
+        };
+        Ok(Self {
+            name: name.to_string(),
+            path,
+            size,
+            exports: pool.exported().to_vec(),
+            stateful: pool.stateful(),
+            description,
+        })
+    }
+
+    /// Returns how the search filter is called, `key-ends-with(suffix: string)`.
+    pub fn signature(&self) -> String {
+        match &self.description {
+            Some(description) => description.signature(&self.name),
+            None => format!("{}(..)", self.name),
+        }
+    }
+}
+
+/// Checks the name of a search filter: letters, digits, `-` or `_`, so it can't point outside of the filters directory.
+pub fn validate_filter_name(name: &str) -> Result<(), lib::Error> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(lib::Error::Error(format!(
+            "'{}' is not a valid name for a search filter. Use letters, digits, '-' or '_'",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the path of the wasm module of the search filter.
+pub fn filter_path(filters_directory: &Path, name: &str) -> PathBuf {
+    filters_directory.join(format!("{}.wasm", name))
+}
+
+/// Returns the names of the installed search filters, sorted.
+pub fn installed_filters(filters_directory: &Path) -> Vec<String> {
+    fs::read_dir(filters_directory)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|e| e == "wasm"))
+                .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+                .sorted()
+                .collect_vec()
+        })
+        .unwrap_or_default()
+}
+
+#[test]
+fn test_installed_filters() {
+    let directory = tempfile::tempdir().unwrap();
+    for file in ["key-ends-with.wasm", "dedupe.wasm", "notes.txt"] {
+        fs::write(directory.path().join(file), "").unwrap();
+    }
+    assert_eq!(
+        installed_filters(directory.path()),
+        vec!["dedupe", "key-ends-with"]
+    );
+    assert!(installed_filters(&directory.path().join("missing")).is_empty());
+}
+
+#[test]
+fn test_validate_filter_name() {
+    assert!(validate_filter_name("key-ends_with2").is_ok());
+    assert!(validate_filter_name("").is_err());
+    assert!(validate_filter_name("../../x").is_err());
+    assert!(validate_filter_name("/etc/passwd").is_err());
+}
+
+#[test]
+fn test_signature() {
+    let description: FilterDescription = serde_json::from_str(
+        r#"{"description": "Matches records whose key ends with the suffix",
+            "parameters": [{"name": "suffix", "type": "string"}, {"name": "ignore_case", "optional": true}]}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        description.signature("key-ends-with"),
+        "key-ends-with(suffix: string, ignore_case?)"
+    );
+}
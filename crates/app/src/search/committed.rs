@@ -0,0 +1,40 @@
+//! Offsets committed by consumer groups.
+//!
+//! `from group "billing-service"` and `offset < committed("billing-service")` need the offsets of the group.
+//! They are fetched when the consumer is created, see [`crate::App::create_consumer`],
+//! and kept here so the predicates can look them up for the topic and the partition of each record.
+
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, RwLock},
+};
+
+/// Committed offsets of a consumer group, per topic and partition.
+pub type GroupOffsets = HashMap<(String, i32), i64>;
+
+/// Committed offsets of the consumer groups used by the current search query.
+pub static COMMITTED_OFFSETS: LazyLock<RwLock<HashMap<String, GroupOffsets>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Replaces the committed offsets of a consumer group.
+pub fn set_committed_offsets(group: &str, offsets: GroupOffsets) {
+    COMMITTED_OFFSETS
+        .write()
+        .unwrap()
+        .insert(group.to_string(), offsets);
+}
+
+/// Forgets the committed offsets of the previous search query.
+pub fn clear_committed_offsets() {
+    COMMITTED_OFFSETS.write().unwrap().clear();
+}
+
+/// Returns the offset committed by the consumer group on the partition, if any.
+pub fn committed_offset(group: &str, topic: &str, partition: i32) -> Option<i64> {
+    COMMITTED_OFFSETS
+        .read()
+        .unwrap()
+        .get(group)
+        .and_then(|offsets| offsets.get(&(topic.to_string(), partition)))
+        .copied()
+}
@@ -10,6 +10,9 @@ use lib::{
 use crate::search::Search;
 
 use super::SearchContext;
+use super::committed::committed_offset;
+use super::compiled::{StringMatcher, compare_numbers, date_millis, json_pointer};
+use super::function::CompiledCall;
 
 impl Search for CompareExpression {
     fn offset(&self) -> Option<FromOffset> {
@@ -22,12 +25,22 @@ impl Search for CompareExpression {
                 Some(FromOffset::Offset(*e + 1))
             }
             CompareExpression::OffsetTail(e) => Some(FromOffset::OffsetTail(*e)),
+            CompareExpression::OffsetCommitted(
+                NumberOperator::Equal
+                | NumberOperator::GreaterOrEqual
+                | NumberOperator::GreaterThan,
+                group,
+            ) => Some(FromOffset::Group(group.clone())),
             CompareExpression::Timestamp(op, e) => match op {
-                NumberOperator::GreaterThan => Some(FromOffset::Timestamp(e.timestamp_millis())),
-                NumberOperator::GreaterOrEqual => {
-                    Some(FromOffset::Timestamp(e.timestamp_millis() - 1000))
+                NumberOperator::GreaterThan => {
+                    Some(FromOffset::Timestamp(e.evaluate().timestamp_millis()))
                 }
-                NumberOperator::Equal => Some(FromOffset::Timestamp(e.timestamp_millis() - 1000)),
+                NumberOperator::GreaterOrEqual => Some(FromOffset::Timestamp(
+                    e.evaluate().timestamp_millis() - 1000,
+                )),
+                NumberOperator::Equal => Some(FromOffset::Timestamp(
+                    e.evaluate().timestamp_millis() - 1000,
+                )),
                 NumberOperator::LowerThan => None,
                 NumberOperator::LowerOrEqual => None,
                 _ => None,
@@ -36,6 +49,13 @@ impl Search for CompareExpression {
         }
     }
 
+    fn pinned_key(&self) -> Option<String> {
+        match self {
+            CompareExpression::Key(StringOperator::Equal, key) => Some(key.clone()),
+            _ => None,
+        }
+    }
+
     fn matches(&self, context: &SearchContext) -> bool {
         let record = context.record;
         match self {
@@ -56,6 +76,10 @@ impl Search for CompareExpression {
                 NumberOperator::Equal => record.offset == *p,
                 NumberOperator::NotEqual => record.offset != *p,
             },
+            CompareExpression::OffsetCommitted(op, group) => {
+                committed_offset(group, &record.topic, record.partition)
+                    .is_some_and(|committed| compare_numbers(record.offset, op, committed))
+            }
             CompareExpression::Topic(op, t) => match op {
                 StringOperator::Equal => record.topic == *t,
                 StringOperator::NotEqual => record.topic != *t,
@@ -86,8 +110,16 @@ impl Search for CompareExpression {
                     StringOperator::NotEqual => header != t,
                 }
             }
+            CompareExpression::ValueTimestamp(left, op, t) => {
+                let pointer = left.as_deref().map(json_pointer);
+                match date_millis(&record.value, pointer.as_deref()) {
+                    Some(date) => compare_numbers(date, op, t.evaluate().timestamp_millis()),
+                    None => false,
+                }
+            }
             CompareExpression::Timestamp(op, t) => {
                 let ts = record.timestamp_as_local_date_time().unwrap();
+                let t = &t.evaluate();
                 match op {
                     NumberOperator::GreaterThan => ts > *t,
                     NumberOperator::GreaterOrEqual => ts >= *t,
@@ -99,8 +131,14 @@ impl Search for CompareExpression {
             }
             CompareExpression::TimestampBetween(from, to) => {
                 let ts = record.timestamp_as_local_date_time().unwrap();
-                from <= &ts && &ts <= to
+                from.evaluate() <= ts && ts <= to.evaluate()
             }
+            CompareExpression::FunctionString(call, op, t) => CompiledCall::new(call)
+                .evaluate(record)
+                .is_some_and(|v| v.matches(&StringMatcher::new(op, t))),
+            CompareExpression::FunctionNumber(call, op, n) => CompiledCall::new(call)
+                .evaluate(record)
+                .is_some_and(|v| v.compare(op, *n)),
         }
     }
 
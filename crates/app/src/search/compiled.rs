@@ -0,0 +1,934 @@
+//! A search query compiled into a predicate tree.
+//!
+//! Walking the AST of a search query for every record is expensive on large topics:
+//! JSON pointers are rebuilt, strings are allocated and timestamps are converted for each comparison.
+//! [`Predicate::compile`] does that work once:
+//!  - JSON pointers, including the ones of function arguments, are parsed ahead of time,
+//!  - substring patterns are precompiled,
+//!  - timestamps are converted to nanoseconds, only `now` is evaluated again on each check,
+//!  - constant branches are folded and nested `&&`/`||` are flattened,
+//!  - cheap checks (partition, offset, size, timestamp) run before expensive ones (key, value, search filters).
+//!
+//! Most of the predicates can also be evaluated on the raw kafka message with [`Predicate::matches_message`],
+//! so records that cannot match are discarded before paying the cost of the schema registry and the deserialization.
+
+use std::borrow::Cow;
+
+use lib::{
+    DataType,
+    kafka::SchemaId,
+    search::{
+        atom::Atom,
+        compare::{CompareExpression, NumberOperator, StringOperator},
+        expression::{AndExpression, OrExpression},
+        filter::Filter,
+        term::Term,
+        timestamp::TimeExpression,
+    },
+};
+use memchr::memmem::Finder;
+use rdkafka::message::{Headers, Message, OwnedMessage};
+
+use super::{
+    Search, SearchContext,
+    committed::committed_offset,
+    function::CompiledCall,
+    highlight::{Field, Highlight},
+    sample::keep,
+};
+
+/// A node of the compiled search query.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Predicate {
+    #[default]
+    True,
+    False,
+    And(Vec<Predicate>),
+    Or(Vec<Predicate>),
+    Not(Box<Predicate>),
+    Partition(NumberOperator, i32),
+    Offset(NumberOperator, i64),
+    /// Compares the offset to the offset committed by the consumer group.
+    OffsetCommitted(NumberOperator, String),
+    Size(NumberOperator, usize),
+    Timestamp(NumberOperator, PointInTime),
+    TimestampBetween(PointInTime, PointInTime),
+    Topic(StringMatcher),
+    Key(StringMatcher),
+    Value(Option<String>, StringMatcher),
+    ValueTimestamp(Option<String>, NumberOperator, PointInTime),
+    Header(String, StringMatcher),
+    FunctionString(CompiledCall, StringMatcher),
+    FunctionNumber(CompiledCall, NumberOperator, i64),
+    Filter(Filter),
+    /// Keeps the record with a probability, in parts per million.
+    Sample(u32),
+}
+
+/// A point in time, expressed in nanoseconds since the epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointInTime {
+    Fixed(i128),
+    /// Nanoseconds relative to the time of the check.
+    Now(i128),
+}
+
+impl PointInTime {
+    pub fn new(time: &TimeExpression) -> Self {
+        match time {
+            TimeExpression::Date(date) => Self::Fixed(nanos(date)),
+            TimeExpression::Now(shift) => Self::Now(*shift as i128 * 1_000_000),
+        }
+    }
+
+    pub fn nanos(&self) -> i128 {
+        match self {
+            Self::Fixed(n) => *n,
+            Self::Now(shift) => nanos(&chrono::Utc::now()) + shift,
+        }
+    }
+}
+
+/// A string operator and its right operand, ready to be evaluated.
+#[derive(Debug, Clone)]
+pub struct StringMatcher {
+    operator: StringOperator,
+    right: String,
+    finder: Option<Finder<'static>>,
+}
+
+impl PartialEq for StringMatcher {
+    fn eq(&self, other: &Self) -> bool {
+        self.operator == other.operator && self.right == other.right
+    }
+}
+
+impl StringMatcher {
+    pub fn new(operator: &StringOperator, right: &str) -> Self {
+        let finder = match operator {
+            StringOperator::Contain => Some(Finder::new(right).into_owned()),
+            _ => None,
+        };
+        Self {
+            operator: operator.clone(),
+            right: right.to_string(),
+            finder,
+        }
+    }
+
+    pub fn matches(&self, value: &str) -> bool {
+        match self.operator {
+            StringOperator::Contain => match &self.finder {
+                Some(finder) => finder.find(value.as_bytes()).is_some(),
+                None => value.contains(&self.right),
+            },
+            StringOperator::Equal => value == self.right,
+            StringOperator::StartWith => value.starts_with(&self.right),
+            StringOperator::NotEqual => value != self.right,
+        }
+    }
+
+    /// The string to highlight when the comparison holds, `None` to highlight the whole string.
+    fn pattern(&self) -> Option<String> {
+        match self.operator {
+            StringOperator::NotEqual => None,
+            _ => Some(self.right.clone()),
+        }
+    }
+
+    /// Same semantic as [`lib::kafka::Comparable::compare`], without rebuilding the JSON pointer.
+    fn matches_data_type(&self, data: &DataType, json_pointer: Option<&str>) -> bool {
+        match data {
+            DataType::String(s) => self.matches(s),
+            DataType::Json(value) => match json_pointer {
+                Some(pointer) => match value.pointer(pointer).and_then(json_scalar) {
+                    Some(v) => self.matches(&v),
+                    None => false,
+                },
+                None => self.matches(&serde_json::to_string(value).unwrap_or_default()),
+            },
+        }
+    }
+}
+
+/// Returns the string representation of a JSON scalar, `None` for arrays and objects.
+pub(crate) fn json_scalar(value: &serde_json::Value) -> Option<Cow<'_, str>> {
+    match value {
+        serde_json::Value::Null => Some(Cow::Borrowed("null")),
+        serde_json::Value::Bool(true) => Some(Cow::Borrowed("true")),
+        serde_json::Value::Bool(false) => Some(Cow::Borrowed("false")),
+        serde_json::Value::Number(n) => Some(Cow::Owned(n.to_string())),
+        serde_json::Value::String(s) => Some(Cow::Borrowed(s)),
+        serde_json::Value::Array(_) | serde_json::Value::Object(_) => None,
+    }
+}
+
+/// Returns the date found in the data, in milliseconds since the epoch.
+/// Dates are RFC 3339 strings or numbers of milliseconds.
+pub(crate) fn date_millis(data: &DataType, json_pointer: Option<&str>) -> Option<i64> {
+    let value = match (data, json_pointer) {
+        (DataType::String(s), _) => return parse_date_millis(s),
+        (DataType::Json(value), Some(pointer)) => value.pointer(pointer)?,
+        (DataType::Json(value), None) => value,
+    };
+    match value {
+        serde_json::Value::Number(n) => n.as_i64(),
+        serde_json::Value::String(s) => parse_date_millis(s),
+        _ => None,
+    }
+}
+
+fn parse_date_millis(date: &str) -> Option<i64> {
+    chrono::DateTime::parse_from_rfc3339(date)
+        .ok()
+        .map(|d| d.timestamp_millis())
+}
+
+/// Converts `.album.tracks[0]` into the JSON pointer `/album/tracks/0`.
+pub(crate) fn json_pointer(path: &str) -> String {
+    path.replace(['.', '['], "/").replace(']', "")
+}
+
+pub(crate) fn compare_numbers<T: PartialOrd>(left: T, operator: &NumberOperator, right: T) -> bool {
+    match operator {
+        NumberOperator::GreaterThan => left > right,
+        NumberOperator::GreaterOrEqual => left >= right,
+        NumberOperator::LowerThan => left < right,
+        NumberOperator::LowerOrEqual => left <= right,
+        NumberOperator::Equal => left == right,
+        NumberOperator::NotEqual => left != right,
+    }
+}
+
+fn nanos<T: chrono::TimeZone>(date: &chrono::DateTime<T>) -> i128 {
+    date.timestamp() as i128 * 1_000_000_000 + date.timestamp_subsec_nanos() as i128
+}
+
+impl Predicate {
+    /// Compiles the expression of a search query.
+    pub fn compile(expression: &OrExpression) -> Self {
+        Self::from_or(expression).optimize()
+    }
+
+    fn from_or(expression: &OrExpression) -> Self {
+        match expression {
+            OrExpression::OrTerm(t) => Self::from_and(t),
+            OrExpression::OrExpression(e) if e.is_empty() => Self::True,
+            OrExpression::OrExpression(e) => Self::Or(e.iter().map(Self::from_and).collect()),
+        }
+    }
+
+    fn from_and(expression: &AndExpression) -> Self {
+        match expression {
+            AndExpression::AndTerm(t) => Self::from_term(t),
+            AndExpression::AndExpression(e) => Self::And(e.iter().map(Self::from_term).collect()),
+        }
+    }
+
+    fn from_term(term: &Term) -> Self {
+        match term {
+            Term::Not(a) => Self::Not(Box::new(Self::from_atom(a))),
+            Term::Atom(a) => Self::from_atom(a),
+        }
+    }
+
+    fn from_atom(atom: &Atom) -> Self {
+        match atom {
+            Atom::Symbol(_) => Self::False,
+            Atom::Compare(c) => Self::from_compare(c),
+            Atom::Filter(f) => Self::Filter(f.clone()),
+            Atom::Parenthesis(e) => Self::from_or(e),
+        }
+    }
+
+    fn from_compare(expression: &CompareExpression) -> Self {
+        match expression {
+            CompareExpression::OffsetTail(_) => Self::True,
+            CompareExpression::Partition(op, p) => Self::Partition(op.clone(), *p),
+            CompareExpression::Offset(op, o) => Self::Offset(op.clone(), *o),
+            CompareExpression::OffsetCommitted(op, group) => {
+                Self::OffsetCommitted(op.clone(), group.clone())
+            }
+            CompareExpression::Size(op, s) => Self::Size(op.clone(), *s as usize),
+            CompareExpression::Timestamp(op, t) => Self::Timestamp(op.clone(), PointInTime::new(t)),
+            CompareExpression::TimestampBetween(from, to) => {
+                Self::TimestampBetween(PointInTime::new(from), PointInTime::new(to))
+            }
+            CompareExpression::Topic(op, t) => Self::Topic(StringMatcher::new(op, t)),
+            CompareExpression::Key(op, t) => Self::Key(StringMatcher::new(op, t)),
+            CompareExpression::Value(left, op, t) => {
+                Self::Value(left.as_deref().map(json_pointer), StringMatcher::new(op, t))
+            }
+            CompareExpression::Header(name, op, t) => {
+                Self::Header(name.clone(), StringMatcher::new(op, t))
+            }
+            CompareExpression::ValueTimestamp(left, op, t) => Self::ValueTimestamp(
+                left.as_deref().map(json_pointer),
+                op.clone(),
+                PointInTime::new(t),
+            ),
+            CompareExpression::FunctionString(call, op, t) => {
+                Self::FunctionString(CompiledCall::new(call), StringMatcher::new(op, t))
+            }
+            CompareExpression::FunctionNumber(call, op, n) => {
+                Self::FunctionNumber(CompiledCall::new(call), op.clone(), *n)
+            }
+        }
+    }
+
+    /// Keeps a random sample of the records matching the predicate, `rate` is in parts per million.
+    pub fn sampled(self, rate: u32) -> Self {
+        Self::And(vec![Self::Sample(rate), self]).optimize()
+    }
+
+    /// Returns the consumer groups whose committed offsets are compared.
+    pub fn consumer_groups(&self) -> Vec<String> {
+        match self {
+            Self::OffsetCommitted(_, group) => vec![group.clone()],
+            Self::Not(p) => p.consumer_groups(),
+            Self::And(p) | Self::Or(p) => p.iter().flat_map(Self::consumer_groups).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Folds constants, flattens nested `&&`/`||` and puts the cheapest predicates of `&&` first.
+    fn optimize(self) -> Self {
+        match self {
+            Self::Not(p) => match p.optimize() {
+                Self::True => Self::False,
+                Self::False => Self::True,
+                Self::Not(p) => *p,
+                p => Self::Not(Box::new(p)),
+            },
+            Self::And(predicates) => {
+                let mut children = vec![];
+                for p in predicates.into_iter().map(Self::optimize) {
+                    match p {
+                        Self::True => (),
+                        Self::False => return Self::False,
+                        Self::And(nested) => children.extend(nested),
+                        p => children.push(p),
+                    }
+                }
+                // The sort is stable, search filters keep the order of the query
+                children.sort_by_key(Self::cost);
+                match children.len() {
+                    0 => Self::True,
+                    1 => children.remove(0),
+                    _ => Self::And(children),
+                }
+            }
+            Self::Or(predicates) => {
+                let mut children = vec![];
+                for p in predicates.into_iter().map(Self::optimize) {
+                    match p {
+                        Self::False => (),
+                        Self::True => return Self::True,
+                        Self::Or(nested) => children.extend(nested),
+                        p => children.push(p),
+                    }
+                }
+                // Branches are evaluated in the order of the query, it tells which ones
+                // stateful search filters see and which search filters a record matches.
+                match children.len() {
+                    0 => Self::False,
+                    1 => children.remove(0),
+                    _ => Self::Or(children),
+                }
+            }
+            p => p,
+        }
+    }
+
+    /// An estimation of how expensive the predicate is to evaluate.
+    fn cost(&self) -> u8 {
+        match self {
+            Self::True | Self::False => 0,
+            Self::Partition(_, _)
+            | Self::Offset(_, _)
+            | Self::Size(_, _)
+            | Self::Timestamp(_, _)
+            | Self::TimestampBetween(_, _)
+            | Self::Sample(_) => 1,
+            Self::Topic(_) | Self::Header(_, _) | Self::OffsetCommitted(_, _) => 2,
+            Self::Key(_) => 3,
+            Self::Value(_, _)
+            | Self::ValueTimestamp(_, _, _)
+            | Self::FunctionString(_, _)
+            | Self::FunctionNumber(_, _, _) => 4,
+            Self::Filter(_) => 5,
+            Self::Not(p) => p.cost(),
+            Self::And(p) | Self::Or(p) => p.iter().map(Self::cost).max().unwrap_or(0),
+        }
+    }
+
+    /// returns `true` if the record matches the predicate.
+    pub fn matches(&self, context: &SearchContext) -> bool {
+        let record = context.record;
+        match self {
+            Self::True => true,
+            Self::False => false,
+            Self::And(predicates) => predicates.iter().all(|p| p.matches(context)),
+            Self::Or(predicates) => predicates.iter().any(|p| p.matches(context)),
+            Self::Not(p) => !p.matches(context),
+            Self::Partition(op, p) => compare_numbers(record.partition, op, *p),
+            Self::Offset(op, o) => compare_numbers(record.offset, op, *o),
+            Self::OffsetCommitted(op, group) => {
+                committed_offset(group, &record.topic, record.partition)
+                    .is_some_and(|committed| compare_numbers(record.offset, op, committed))
+            }
+            Self::Size(op, s) => compare_numbers(record.size, op, *s),
+            Self::Timestamp(op, t) => match record.timestamp {
+                Some(ts) => compare_numbers(ts as i128 * 1_000_000, op, t.nanos()),
+                None => false,
+            },
+            Self::TimestampBetween(from, to) => match record.timestamp {
+                Some(ts) => (from.nanos()..=to.nanos()).contains(&(ts as i128 * 1_000_000)),
+                None => false,
+            },
+            Self::Topic(m) => m.matches(&record.topic),
+            Self::Key(m) => m.matches_data_type(&record.key, None),
+            Self::Value(pointer, m) => m.matches_data_type(&record.value, pointer.as_deref()),
+            Self::ValueTimestamp(pointer, op, t) => {
+                match date_millis(&record.value, pointer.as_deref()) {
+                    Some(date) => compare_numbers(date as i128 * 1_000_000, op, t.nanos()),
+                    None => false,
+                }
+            }
+            Self::Header(name, m) => match record.headers.get(name) {
+                Some(header) => m.matches(header),
+                None => false,
+            },
+            Self::FunctionString(call, m) => call.evaluate(record).is_some_and(|v| v.matches(m)),
+            Self::FunctionNumber(call, op, n) => {
+                call.evaluate(record).is_some_and(|v| v.compare(op, *n))
+            }
+            Self::Filter(f) => f.matches(context),
+            Self::Sample(rate) => keep(*rate, &record.topic, record.partition, record.offset),
+        }
+    }
+
+    /// Same as [`Predicate::matches`], returns the search filters that hold for the record to match, `None` if it doesn't match.
+    /// Filters under a `!` or in a branch of `||` that doesn't match are not part of them.
+    pub fn matching_filters(&self, context: &SearchContext) -> Option<Vec<Filter>> {
+        match self {
+            Self::And(predicates) => {
+                let mut filters = vec![];
+                for p in predicates {
+                    filters.extend(p.matching_filters(context)?);
+                }
+                Some(filters)
+            }
+            Self::Or(predicates) => predicates.iter().find_map(|p| p.matching_filters(context)),
+            Self::Filter(f) => f.matches(context).then(|| vec![f.clone()]),
+            p => p.matches(context).then(Vec::new),
+        }
+    }
+
+    /// Returns where the record matches the predicate, `None` if it doesn't match.
+    /// Every branch of a `||` is evaluated so all the matching comparisons are highlighted.
+    /// Search filters are not executed, the record is expected to match already: they are assumed to hold.
+    pub fn locate(&self, context: &SearchContext) -> Option<Vec<Highlight>> {
+        match self {
+            Self::And(predicates) => {
+                let mut highlights = vec![];
+                for p in predicates {
+                    highlights.extend(p.locate(context)?);
+                }
+                Some(highlights)
+            }
+            Self::Or(predicates) => {
+                predicates
+                    .iter()
+                    .filter_map(|p| p.locate(context))
+                    .reduce(|mut highlights, h| {
+                        highlights.extend(h);
+                        highlights
+                    })
+            }
+            Self::Filter(_) => Some(vec![]),
+            Self::Not(p) if p.has_filter() => Some(vec![]),
+            Self::Not(p) => (!p.matches(context)).then(Vec::new),
+            p => p.matches(context).then(|| p.highlights()),
+        }
+    }
+
+    /// Returns `true` when a search filter is part of the predicate.
+    fn has_filter(&self) -> bool {
+        match self {
+            Self::Filter(_) => true,
+            Self::Not(p) => p.has_filter(),
+            Self::And(p) | Self::Or(p) => p.iter().any(Self::has_filter),
+            _ => false,
+        }
+    }
+
+    /// The highlights of a comparison that holds.
+    fn highlights(&self) -> Vec<Highlight> {
+        match self {
+            Self::Topic(m) => vec![Highlight::new(Field::Topic, None, m.pattern())],
+            Self::Key(m) => vec![Highlight::new(Field::Key, None, m.pattern())],
+            Self::Value(pointer, m) => {
+                vec![Highlight::new(Field::Value, pointer.clone(), m.pattern())]
+            }
+            Self::ValueTimestamp(pointer, _, _) => {
+                vec![Highlight::new(Field::Value, pointer.clone(), None)]
+            }
+            Self::Header(name, m) => {
+                vec![Highlight::new(
+                    Field::Header(name.clone()),
+                    None,
+                    m.pattern(),
+                )]
+            }
+            Self::FunctionString(call, _) | Self::FunctionNumber(call, _, _) => {
+                vec![call.highlight()]
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Evaluates the predicate on the raw kafka message, before it is decoded.
+    /// Returns `None` when the record must be decoded to know whether it matches.
+    pub fn matches_message(&self, message: &OwnedMessage) -> Option<bool> {
+        match self {
+            Self::True => Some(true),
+            Self::False => Some(false),
+            Self::And(predicates) => {
+                let mut result = Some(true);
+                for p in predicates {
+                    match p.matches_message(message) {
+                        Some(false) => return Some(false),
+                        Some(true) => (),
+                        None => result = None,
+                    }
+                }
+                result
+            }
+            Self::Or(predicates) => {
+                let mut result = Some(false);
+                for p in predicates {
+                    match p.matches_message(message) {
+                        Some(true) => return Some(true),
+                        Some(false) => (),
+                        None => result = None,
+                    }
+                }
+                result
+            }
+            Self::Not(p) => p.matches_message(message).map(|m| !m),
+            Self::Partition(op, p) => Some(compare_numbers(message.partition(), op, *p)),
+            Self::Offset(op, o) => Some(compare_numbers(message.offset(), op, *o)),
+            Self::Sample(rate) => Some(keep(
+                *rate,
+                message.topic(),
+                message.partition(),
+                message.offset(),
+            )),
+            Self::OffsetCommitted(op, group) => Some(
+                committed_offset(group, message.topic(), message.partition())
+                    .is_some_and(|committed| compare_numbers(message.offset(), op, committed)),
+            ),
+            Self::Size(op, s) => {
+                let size = message.payload().map(|e| e.len()).unwrap_or(0)
+                    + message.key().map(|e| e.len()).unwrap_or(0);
+                Some(compare_numbers(size, op, *s))
+            }
+            Self::Timestamp(op, t) => match message.timestamp().to_millis() {
+                Some(ts) => Some(compare_numbers(ts as i128 * 1_000_000, op, t.nanos())),
+                None => Some(false),
+            },
+            Self::TimestampBetween(from, to) => match message.timestamp().to_millis() {
+                Some(ts) => Some((from.nanos()..=to.nanos()).contains(&(ts as i128 * 1_000_000))),
+                None => Some(false),
+            },
+            Self::Topic(m) => Some(m.matches(message.topic())),
+            Self::Header(name, m) => {
+                let header = message
+                    .headers()
+                    .and_then(|headers| headers.iter().filter(|h| h.key == name).last());
+                match header {
+                    Some(header) => match header.value {
+                        Some(v) => {
+                            Some(m.matches(std::str::from_utf8(v).unwrap_or("<unable to parse>")))
+                        }
+                        None => Some(m.matches("")),
+                    },
+                    None => Some(false),
+                }
+            }
+            Self::Key(m) => plain_string(message.key()).map(|k| m.matches(k)),
+            // A plain string payload ignores the JSON pointer, like `DataType::String` does.
+            Self::Value(_, m) => plain_string(message.payload()).map(|v| m.matches(v)),
+            Self::ValueTimestamp(_, _, _)
+            | Self::FunctionString(_, _)
+            | Self::FunctionNumber(_, _, _)
+            | Self::Filter(_) => None,
+        }
+    }
+}
+
+/// Returns the payload as it will be decoded when it is neither serialized with a schema, nor JSON.
+/// Returns `None` when the payload needs to be decoded.
+fn plain_string(payload: Option<&[u8]>) -> Option<&str> {
+    let payload = payload.unwrap_or_default();
+    if SchemaId::parse(Some(payload)).is_some() {
+        return None;
+    }
+    let first = payload
+        .iter()
+        .find(|b| !matches!(b, b' ' | b'\t' | b'\n' | b'\r'));
+    match first {
+        Some(b'{' | b'[' | b'"' | b'-' | b'0'..=b'9' | b't' | b'f' | b'n') => None,
+        // Invalid UTF-8 payloads are decoded into an empty string
+        _ => Some(std::str::from_utf8(payload).unwrap_or_default()),
+    }
+}
+
+#[cfg(test)]
+use lib::{KafkaRecord, parse_search_query};
+
+#[cfg(test)]
+fn compile(input: &str) -> Predicate {
+    Predicate::compile(&parse_search_query(input).unwrap().1.expression)
+}
+
+#[test]
+fn test_cheap_checks_first() {
+    assert_eq!(
+        compile(r#"value.album.title == "Virtue" && key contains "foo" && partition == 2"#),
+        Predicate::And(vec![
+            Predicate::Partition(NumberOperator::Equal, 2),
+            Predicate::Key(StringMatcher::new(&StringOperator::Contain, "foo")),
+            Predicate::Value(
+                Some("/album/title".to_string()),
+                StringMatcher::new(&StringOperator::Equal, "Virtue")
+            ),
+        ])
+    );
+}
+
+#[test]
+fn test_or_keeps_the_order_of_the_query() {
+    assert_eq!(
+        compile(r#"value.album.title == "Virtue" || partition == 2"#),
+        Predicate::Or(vec![
+            Predicate::Value(
+                Some("/album/title".to_string()),
+                StringMatcher::new(&StringOperator::Equal, "Virtue")
+            ),
+            Predicate::Partition(NumberOperator::Equal, 2),
+        ])
+    );
+}
+
+#[test]
+fn test_constant_folding() {
+    assert_eq!(compile("offsetTail == 10"), Predicate::True);
+    assert_eq!(
+        compile("offset > 5 && !(offsetTail == 10)"),
+        Predicate::False
+    );
+}
+
+#[test]
+fn test_same_result_as_the_search_query() {
+    let record = KafkaRecord {
+        topic: "purchases".to_string(),
+        timestamp: Some(1_732_363_200_000),
+        partition: 2,
+        offset: 3_460,
+        size: 120,
+        key: DataType::String("order-1".to_string()),
+        value: DataType::Json(
+            serde_json::json!({"album": {"title": "Virtue", "tracks": [1, 2]}, "price": 12.5, "gift": false, "paidAt": "2024-11-23T12:30:00.000+01:00"}),
+        ),
+        headers: [("source".to_string(), "web".to_string())].into(),
+        ..Default::default()
+    };
+    let context = SearchContext::new(&record, std::path::Path::new("."));
+    for input in [
+        r#"key == "order-1""#,
+        r#"key contains "der""#,
+        r#"value.album.title == "Virtue""#,
+        r#"value.album.tracks[1] == "2""#,
+        r#"value.price == "12.5""#,
+        r#"value.gift == "false""#,
+        r#"value.album == "Virtue""#,
+        r#"value contains "Virtue""#,
+        r#"headers.source starts with "we""#,
+        r#"headers.missing == "web""#,
+        r#"topic != "purchases""#,
+        r#"partition == 1 || partition == 2"#,
+        r#"!(offset >= 3_460) || size > 100"#,
+        r#"timestamp >= "2024-11-23T12:00:00.000+01:00""#,
+        r#"timestamp between "2024-11-23T12:00:00.000+01:00" and "2024-11-23T13:00:00.000+01:00""#,
+        r#"timestamp < now - 1d && timestamp > "2024-11-23T12:00:00.000+01:00" - 1h"#,
+        r#"value.paidAt > "2024-11-23T12:00:00.000+01:00" + 15m"#,
+        r#"value.paidAt >= now - 15m"#,
+        r#"value.price < now"#,
+        r#"lower(value.album.title) == "virtue""#,
+        r#"length(value.album.tracks) > 1 && json_type(value.gift) == "boolean""#,
+        r#"length(key) == "7""#,
+    ] {
+        let query = parse_search_query(input).unwrap().1;
+        assert_eq!(
+            compile(input).matches(&context),
+            query.matches(&context),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_relative_time_is_evaluated_on_each_check() {
+    assert_eq!(
+        compile("timestamp > now - 15m"),
+        Predicate::Timestamp(
+            NumberOperator::GreaterThan,
+            PointInTime::Now(-15 * 60 * 1_000_000_000)
+        )
+    );
+    let record = KafkaRecord {
+        timestamp: Some(chrono::Utc::now().timestamp_millis()),
+        value: DataType::Json(
+            serde_json::json!({"createdAt": chrono::Utc::now().timestamp_millis()}),
+        ),
+        ..Default::default()
+    };
+    let context = SearchContext::new(&record, std::path::Path::new("."));
+    assert!(compile("timestamp > now - 15m").matches(&context));
+    assert!(
+        compile("value.createdAt > now - 15m && value.createdAt <= now + 1s").matches(&context)
+    );
+    assert!(!compile("timestamp between now - 2h and now - 1h").matches(&context));
+}
+
+#[test]
+fn test_offset_committed() {
+    use super::committed::set_committed_offsets;
+    set_committed_offsets(
+        "test-offset-committed",
+        [(("purchases".to_string(), 2), 3_000)].into(),
+    );
+    let predicate = compile(r#"offset < committed("test-offset-committed")"#);
+    assert_eq!(predicate.consumer_groups(), vec!["test-offset-committed"]);
+    let record = |partition: i32, offset: i64| KafkaRecord {
+        topic: "purchases".to_string(),
+        partition,
+        offset,
+        ..Default::default()
+    };
+    let matches = |record: &KafkaRecord| {
+        let context = SearchContext::new(record, std::path::Path::new("."));
+        let query = parse_search_query(r#"offset < committed("test-offset-committed")"#)
+            .unwrap()
+            .1;
+        assert_eq!(predicate.matches(&context), query.matches(&context));
+        predicate.matches(&context)
+    };
+    assert!(matches(&record(2, 2_999)));
+    assert!(!matches(&record(2, 3_000)));
+    // Nothing is committed on the partition
+    assert!(!matches(&record(1, 0)));
+}
+
+#[test]
+fn test_locate() {
+    let record = KafkaRecord {
+        key: DataType::String("order-1".to_string()),
+        value: DataType::Json(serde_json::json!({"status": "timeout", "retries": 3})),
+        ..Default::default()
+    };
+    let context = SearchContext::new(&record, std::path::Path::new("."));
+    assert_eq!(
+        compile(r#"value.status == "timeout" && (key contains "der" || offset > 10)"#)
+            .locate(&context),
+        Some(vec![
+            Highlight::new(Field::Key, None, Some("der".to_string())),
+            Highlight::new(
+                Field::Value,
+                Some("/status".to_string()),
+                Some("timeout".to_string())
+            ),
+        ])
+    );
+    assert_eq!(
+        compile(r#"!(key == "order-2") && value.retries != "0""#).locate(&context),
+        Some(vec![Highlight::new(
+            Field::Value,
+            Some("/retries".to_string()),
+            None
+        )])
+    );
+    assert_eq!(compile(r#"key == "order-2""#).locate(&context), None);
+    // The search filters are not loaded, they must not be executed
+    assert_eq!(
+        compile(r#"key contains "der" && (not-loaded() || !not-loaded-either())"#).locate(&context),
+        Some(vec![Highlight::new(
+            Field::Key,
+            None,
+            Some("der".to_string())
+        )])
+    );
+}
+
+#[test]
+fn test_matching_filters() {
+    use super::native::register_native_filter;
+    use serde_json::Value;
+    register_native_filter("matching-yes", |_: &KafkaRecord, _: &[Value]| true);
+    register_native_filter("matching-also-yes", |_: &KafkaRecord, _: &[Value]| true);
+    register_native_filter("matching-no", |_: &KafkaRecord, _: &[Value]| false);
+    let filter = |name: &str| Filter {
+        name: name.to_string(),
+        parameters: vec![],
+    };
+    let record = KafkaRecord::default();
+    let context = SearchContext::new(&record, std::path::Path::new("."));
+    let matching = |input: &str| compile(input).matching_filters(&context);
+
+    assert_eq!(
+        matching("!matching-no() && (matching-no() || matching-also-yes())"),
+        Some(vec![filter("matching-also-yes")])
+    );
+    assert_eq!(
+        matching("matching-yes() || matching-also-yes()"),
+        Some(vec![filter("matching-yes")])
+    );
+    assert_eq!(
+        matching("!matching-no() && (offset == 0 || matching-yes())"),
+        Some(vec![])
+    );
+    assert_eq!(
+        matching("matching-yes() || offset == 0"),
+        Some(vec![filter("matching-yes")])
+    );
+    assert_eq!(matching("matching-no() || !matching-yes()"), None);
+}
+
+#[test]
+fn test_same_result_as_the_search_query_with_filters_and_functions() {
+    use super::native::register_native_filter;
+    use itertools::Itertools;
+    use serde_json::Value;
+    use std::sync::{Arc, Mutex};
+
+    let calls = Arc::new(Mutex::new(vec![]));
+    let log = calls.clone();
+    register_native_filter(
+        "equivalence-even-offset",
+        move |record: &KafkaRecord, _: &[Value]| {
+            log.lock().unwrap().push(record.offset);
+            record.offset % 2 == 0
+        },
+    );
+    register_native_filter(
+        "equivalence-key-ends-with",
+        |record: &KafkaRecord, params: &[Value]| {
+            record
+                .key_as_string
+                .ends_with(params[0].as_str().unwrap_or_default())
+        },
+    );
+    let records = (0..4).map(|i| KafkaRecord {
+        topic: "purchases".to_string(),
+        partition: i % 2,
+        offset: i64::from(i),
+        key: DataType::String(format!("Order-{i}")),
+        key_as_string: format!("Order-{i}"),
+        value: DataType::Json(serde_json::json!({"tracks": vec![0; i as usize]})),
+        ..Default::default()
+    });
+    let atoms = [
+        "equivalence-even-offset()",
+        r#"equivalence-key-ends-with("1")"#,
+        r#"lower(key) == "order-2""#,
+        "length(value.tracks) > 1",
+        r#"json_type(value.tracks) == "array""#,
+        "partition == 1",
+        r#"key contains "3""#,
+        "offset > 0",
+    ];
+    let inputs = atoms
+        .iter()
+        .cartesian_product(atoms.iter())
+        .flat_map(|(a, b)| {
+            [
+                format!("{a} && {b}"),
+                format!("{a} || {b}"),
+                format!("!({a}) || {b}"),
+                format!("({a} || {b}) && !(offset == 1)"),
+            ]
+        })
+        .collect::<Vec<_>>();
+    for record in records {
+        let context = SearchContext::new(&record, std::path::Path::new("."));
+        for input in &inputs {
+            let query = parse_search_query(input).unwrap().1;
+            calls.lock().unwrap().clear();
+            let expected = query.matches(&context);
+            let expected_calls = calls.lock().unwrap().clone();
+            calls.lock().unwrap().clear();
+            assert_eq!(compile(input).matches(&context), expected, "{}", input);
+            if !input.contains("&&") {
+                // `||` calls the search filters of the same records as the query
+                assert_eq!(*calls.lock().unwrap(), expected_calls, "{}", input);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_matches_message() {
+    use rdkafka::message::{Header, OwnedHeaders};
+    let message = |key: &str, value: &str| {
+        OwnedMessage::new(
+            Some(value.as_bytes().to_vec()),
+            Some(key.as_bytes().to_vec()),
+            "purchases".to_string(),
+            rdkafka::Timestamp::CreateTime(1_732_363_200_000),
+            2,
+            3_460,
+            Some(OwnedHeaders::new().insert(Header {
+                key: "source",
+                value: Some("web"),
+            })),
+        )
+    };
+    let plain = message("order-1", "release of the album");
+    let json = message("order-1", r#"{"album": "Virtue"}"#);
+    for (input, expected_plain, expected_json) in [
+        ("partition == 1", Some(false), Some(false)),
+        ("offset >= 3_460 && size > 10", Some(true), Some(true)),
+        (r#"headers.source == "web""#, Some(true), Some(true)),
+        (r#"key == "order-1""#, Some(true), Some(true)),
+        (r#"value contains "release""#, Some(true), None),
+        (r#"value contains "Virtue""#, Some(false), None),
+        (
+            r#"partition == 1 || value contains "Virtue""#,
+            Some(false),
+            None,
+        ),
+        (
+            r#"partition == 2 || value contains "Virtue""#,
+            Some(true),
+            Some(true),
+        ),
+        (
+            r#"timestamp < "2024-11-23T12:00:00.000+01:00""#,
+            Some(false),
+            Some(false),
+        ),
+    ] {
+        let predicate = compile(input);
+        assert_eq!(
+            predicate.matches_message(&plain),
+            expected_plain,
+            "{}",
+            input
+        );
+        assert_eq!(predicate.matches_message(&json), expected_json, "{}", input);
+    }
+}
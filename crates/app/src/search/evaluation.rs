@@ -0,0 +1,112 @@
+//! Evaluation of the search query on several threads.
+//!
+//! Search filters are the slowest part of a search. When the search query uses some,
+//! records are evaluated on the blocking threads of the runtime, several at a time.
+//! The results are awaited in the order of the records.
+//! Stateful search filters need the records one at a time, in order: they are evaluated sequentially.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use lib::KafkaRecord;
+use tokio::task::JoinHandle;
+
+use super::{Search, SearchContext, ValidSearchQuery};
+
+/// Evaluates the search query on the records.
+#[derive(Debug, Clone)]
+pub struct Evaluator {
+    search_query: Arc<ValidSearchQuery>,
+    filters_directory: PathBuf,
+    /// `true` when the search query uses search filters
+    filters: bool,
+    /// `true` when records are evaluated on several threads
+    parallel: bool,
+}
+
+/// The result of the evaluation of a record, maybe not known yet.
+#[allow(clippy::large_enum_variant)]
+pub enum Evaluation {
+    Done(KafkaRecord, bool),
+    Pending(JoinHandle<(KafkaRecord, bool)>),
+}
+
+impl Evaluator {
+    pub fn new(search_query: &ValidSearchQuery, filters_directory: &Path) -> Self {
+        let filters = !search_query.filters().is_empty();
+        Self {
+            filters,
+            parallel: filters && !search_query.is_stateful(),
+            search_query: Arc::new(search_query.clone()),
+            filters_directory: filters_directory.to_path_buf(),
+        }
+    }
+
+    /// Number of records evaluated at the same time.
+    pub fn concurrency(&self) -> usize {
+        match self.parallel {
+            true => std::thread::available_parallelism()
+                .map(|n| n.get() * 2)
+                .unwrap_or(2),
+            false => 1,
+        }
+    }
+
+    /// Evaluates the search query on the record.
+    /// `prefilter` is the result of [`ValidSearchQuery::matches_message`], the record is only evaluated when it's `None`.
+    /// A matching record is rewritten by the `transform` function of the search filters.
+    pub fn evaluate(&self, record: KafkaRecord, prefilter: Option<bool>) -> Evaluation {
+        if prefilter == Some(false) || !self.filters {
+            let matches = prefilter.unwrap_or_else(|| {
+                self.search_query
+                    .matches(&SearchContext::new(&record, &self.filters_directory))
+            });
+            return Evaluation::Done(record, matches);
+        }
+        if !self.parallel {
+            let (record, matches) = evaluate(
+                &self.search_query,
+                &self.filters_directory,
+                record,
+                prefilter,
+            );
+            return Evaluation::Done(record, matches);
+        }
+        let search_query = self.search_query.clone();
+        let filters_directory = self.filters_directory.clone();
+        Evaluation::Pending(tokio::task::spawn_blocking(move || {
+            evaluate(&search_query, &filters_directory, record, prefilter)
+        }))
+    }
+}
+
+fn evaluate(
+    search_query: &ValidSearchQuery,
+    filters_directory: &Path,
+    record: KafkaRecord,
+    prefilter: Option<bool>,
+) -> (KafkaRecord, bool) {
+    // Decided from the raw message, no search filter was needed
+    let matching_filters = match prefilter {
+        Some(matches) => matches.then(Vec::new),
+        None => search_query.matching_filters(&SearchContext::new(&record, filters_directory)),
+    };
+    match matching_filters {
+        Some(filters) => (search_query.transform(record, &filters), true),
+        None => (record, false),
+    }
+}
+
+impl Evaluation {
+    /// Returns the record and whether it matches the search query.
+    pub async fn result(self) -> (KafkaRecord, bool) {
+        match self {
+            Self::Done(record, matches) => (record, matches),
+            Self::Pending(handle) => handle
+                .await
+                .expect("The evaluation of the search query panicked"),
+        }
+    }
+}
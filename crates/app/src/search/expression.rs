@@ -22,6 +22,13 @@ impl Search for AndExpression {
         }
     }
 
+    fn pinned_key(&self) -> Option<String> {
+        match self {
+            Self::AndTerm(t) => t.pinned_key(),
+            Self::AndExpression(v) => v.iter().find_map(|t| t.pinned_key()),
+        }
+    }
+
     fn matches(&self, context: &SearchContext) -> bool {
         let record = context;
         match self {
@@ -53,6 +60,13 @@ impl Search for OrExpression {
         }
     }
 
+    fn pinned_key(&self) -> Option<String> {
+        match self {
+            Self::OrTerm(t) => t.pinned_key(),
+            Self::OrExpression(_) => None,
+        }
+    }
+
     fn matches(&self, context: &SearchContext) -> bool {
         match self {
             Self::OrTerm(t) => t.matches(context),
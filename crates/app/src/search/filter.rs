@@ -1,34 +1,338 @@
 use std::{
     collections::HashMap,
-    sync::{LazyLock, Mutex},
+    path::Path,
+    sync::{Arc, LazyLock, Mutex, RwLock},
 };
 
-use extism::{Plugin, convert::Json};
+use extism::{Manifest, Wasm, convert::Json};
+use indexmap::IndexMap;
 use itertools::Itertools;
 use lib::{
-    FilterResult,
+    FilterResult, KafkaRecord, TransformResult,
     search::filter::{Filter, FilterInput},
 };
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString};
 
-use super::{Search, SearchContext};
+use super::{
+    Search, SearchContext, catalog::filter_path, native::native_filter, plugin_pool::PluginPool,
+};
 
 pub const MATCHES_FUNCTION_NAME: &str = "matches";
 pub const PARSE_PARAMETERS_FUNCTION_NAME: &str = "parse_parameters";
+pub const TRANSFORM_FUNCTION_NAME: &str = "transform";
+pub const ON_SEARCH_START_FUNCTION_NAME: &str = "on_search_start";
+pub const ON_SEARCH_END_FUNCTION_NAME: &str = "on_search_end";
+
+/// Number of errors logged per search filter, the next ones are only counted.
+pub const LOGGED_ERRORS: usize = 10;
+
+/// Search filters loaded in memory, by name.
+pub type Filters = LazyLock<RwLock<HashMap<String, Arc<PluginPool>>>>;
+
+/// FILTERS are lazy loaded and cached in memory. Each one has a pool of instances.
+pub(crate) static CACHED_FILTERS: Filters = LazyLock::new(|| HashMap::new().into());
+
+/// What a search filter returns when calling the wasm module fails.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize, Display, EnumString,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum FilterErrorPolicy {
+    /// The record matches
+    #[default]
+    Match,
+    /// The record doesn't match
+    NoMatch,
+    /// The record doesn't match and the search stops
+    Abort,
+}
+
+/// Resource limits of a search filter. `None` means no limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub struct Limits {
+    /// Maximum duration of a call to the wasm module, in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Maximum memory of an instance of the wasm module, in megabytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_mb: Option<u32>,
+}
+
+/// Resource limits of the search filters.
+/// A search filter exceeding them is disabled for the rest of the session. There is no limit by default.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub struct FilterLimits {
+    /// Maximum duration of a call, in milliseconds
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// Maximum memory of an instance, in megabytes
+    #[serde(default)]
+    pub memory_mb: Option<u32>,
+    /// Limits of specific search filters, by name. They override the limits above.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub filters: IndexMap<String, Limits>,
+}
+
+impl FilterLimits {
+    /// Returns the limits of the given search filter.
+    pub fn of(&self, name: &str) -> Limits {
+        let limits = self.filters.get(name).copied().unwrap_or_default();
+        Limits {
+            timeout_ms: limits.timeout_ms.or(self.timeout_ms),
+            memory_mb: limits.memory_mb.or(self.memory_mb),
+        }
+    }
+}
+
+static FILTER_LIMITS: LazyLock<RwLock<FilterLimits>> = LazyLock::new(Default::default);
+
+static FILTER_ERROR_POLICY: RwLock<FilterErrorPolicy> = RwLock::new(FilterErrorPolicy::Match);
+
+/// Errors of the search filters since the search started.
+#[derive(Debug, Default)]
+struct FilterErrors {
+    /// Number of errors per search filter
+    counts: IndexMap<String, usize>,
+    /// Why the search was aborted, with the [`FilterErrorPolicy::Abort`] policy
+    aborted: Option<String>,
+    /// Search filters disabled since the search started, with the message not reported yet
+    disabled: IndexMap<String, Option<String>>,
+}
+
+static FILTER_ERRORS: LazyLock<Mutex<FilterErrors>> = LazyLock::new(Default::default);
+
+pub fn set_filter_error_policy(policy: FilterErrorPolicy) {
+    *FILTER_ERROR_POLICY.write().unwrap() = policy;
+}
+
+pub fn filter_error_policy() -> FilterErrorPolicy {
+    *FILTER_ERROR_POLICY.read().unwrap()
+}
+
+/// Sets the resource limits of the search filters loaded from now on.
+pub fn set_filter_limits(limits: FilterLimits) {
+    *FILTER_LIMITS.write().unwrap() = limits;
+}
+
+pub fn filter_limits() -> FilterLimits {
+    FILTER_LIMITS.read().unwrap().clone()
+}
+
+/// Forgets the errors of the previous search.
+pub fn reset_filter_errors() {
+    *FILTER_ERRORS.lock().unwrap() = FilterErrors::default();
+}
+
+/// Returns the number of errors of each search filter that failed since the search started.
+pub fn filter_errors() -> Vec<(String, usize)> {
+    FILTER_ERRORS
+        .lock()
+        .unwrap()
+        .counts
+        .iter()
+        .map(|(name, count)| (name.clone(), *count))
+        .collect()
+}
+
+/// Returns why the search must stop, when a search filter failed with the [`FilterErrorPolicy::Abort`] policy.
+pub fn aborted_by_filter() -> Option<String> {
+    FILTER_ERRORS.lock().unwrap().aborted.clone()
+}
+
+/// Returns a message for each search filter disabled since the last call.
+pub fn take_disabled_filters() -> Vec<String> {
+    FILTER_ERRORS
+        .lock()
+        .unwrap()
+        .disabled
+        .values_mut()
+        .filter_map(Option::take)
+        .collect()
+}
+
+/// Loads the wasm module of the search filter into a new pool of instances.
+pub fn load_filter(name: &str, filters_directory: &Path) -> Result<PluginPool, extism::Error> {
+    let manifest = Manifest::new([Wasm::file(filter_path(filters_directory, name))]);
+    PluginPool::new(manifest, filter_limits().of(name))
+}
+
+/// Returns `true` when the search filter is loaded.
+pub fn is_filter_loaded(name: &str) -> bool {
+    CACHED_FILTERS.read().unwrap().contains_key(name)
+}
+
+/// Replaces the loaded search filter with a new version, see [`load_filter`].
+/// Records evaluated from now on use the new version, those being evaluated keep the previous one.
+pub fn replace_filter(name: &str, pool: PluginPool) {
+    CACHED_FILTERS
+        .write()
+        .unwrap()
+        .insert(name.to_string(), Arc::new(pool));
+}
+
+/// Counts the error, logs the first ones and returns whether the record matches according to the policy.
+fn on_filter_error(
+    name: &str,
+    record: &KafkaRecord,
+    e: &extism::Error,
+    disabled: Option<String>,
+) -> bool {
+    let message = format!(
+        "Search filter '{}' failed on the record of topic '{}', partition {}, offset {}: {}",
+        name, record.topic, record.partition, record.offset, e
+    );
+    let policy = filter_error_policy();
+    let mut errors = FILTER_ERRORS.lock().unwrap();
+    if let Some(reason) = disabled {
+        errors.disabled.entry(name.to_string()).or_insert_with(|| {
+            let message = format!(
+                "Search filter '{}' is disabled for the rest of the session, {}",
+                name, reason
+            );
+            warn!("{}", message);
+            Some(message)
+        });
+    }
+    let count = errors.counts.entry(name.to_string()).or_default();
+    *count += 1;
+    match *count {
+        c if c < LOGGED_ERRORS => error!("{}", message),
+        LOGGED_ERRORS => {
+            error!("{}", message);
+            warn!(
+                "Search filter '{}' failed {} times, the next errors are not logged",
+                name, LOGGED_ERRORS
+            );
+        }
+        _ => (),
+    }
+    match policy {
+        FilterErrorPolicy::Match => true,
+        FilterErrorPolicy::NoMatch => false,
+        FilterErrorPolicy::Abort => {
+            errors.aborted.get_or_insert(message);
+            false
+        }
+    }
+}
+
+/// Empties the key/value store of the search filter and calls its `on_search_start` hook.
+pub(crate) fn on_search_start(name: &str, filters: &Filters) {
+    if let Some(pool) = filters.read().unwrap().get(name).cloned() {
+        start_search(name, &pool);
+    }
+}
+
+/// Calls the `on_search_end` hook of the search filter and returns what it reports, if anything.
+pub(crate) fn on_search_end(name: &str, filters: &Filters) -> Option<String> {
+    let pool = filters.read().unwrap().get(name).cloned()?;
+    end_search(name, &pool)
+}
+
+/// Empties the key/value store of a loaded wasm module and calls its `on_search_start` hook.
+pub fn start_search(name: &str, pool: &PluginPool) {
+    pool.clear_store();
+    if !pool.exports(ON_SEARCH_START_FUNCTION_NAME) {
+        return;
+    }
+    if let Err(e) = pool.call::<&str, ()>(ON_SEARCH_START_FUNCTION_NAME, "") {
+        error!(
+            "Error when calling '{}' from wasm module '{}': {}",
+            ON_SEARCH_START_FUNCTION_NAME, name, e
+        );
+    }
+}
+
+/// Calls the `on_search_end` hook of a loaded wasm module and returns what it reports, if anything.
+pub fn end_search(name: &str, pool: &PluginPool) -> Option<String> {
+    if !pool.exports(ON_SEARCH_END_FUNCTION_NAME) {
+        return None;
+    }
+    match pool.call::<&str, String>(ON_SEARCH_END_FUNCTION_NAME, "") {
+        Ok(report) if report.trim().is_empty() => None,
+        Ok(report) => Some(format!("{}: {}", name, report.trim())),
+        Err(e) => {
+            error!(
+                "Error when calling '{}' from wasm module '{}': {}",
+                ON_SEARCH_END_FUNCTION_NAME, name, e
+            );
+            None
+        }
+    }
+}
+
+/// Returns `true` when the search filter uses its key/value store.
+pub(crate) fn is_stateful(name: &str, filters: &Filters) -> bool {
+    filters
+        .read()
+        .unwrap()
+        .get(name)
+        .is_some_and(|pool| pool.stateful())
+}
 
-/// FILTERS are lazy loaded and cached in memory.
-pub(crate) static CACHED_FILTERS: LazyLock<Mutex<HashMap<String, Plugin>>> =
-    LazyLock::new(|| HashMap::new().into());
+/// Rewrites the record with the `transform` function of the search filter, if the wasm module exports one.
+/// The record is returned unchanged when the call fails.
+pub(crate) fn transform(filter: &Filter, record: KafkaRecord, filters: &Filters) -> KafkaRecord {
+    let Some(pool) = filters.read().unwrap().get(&filter.name).cloned() else {
+        return record;
+    };
+    if !pool.exports(TRANSFORM_FUNCTION_NAME) {
+        return record;
+    }
+    let input = FilterInput {
+        record,
+        params: filter.parameters.iter().map(|e| e.json()).collect_vec(),
+    };
+    match pool
+        .call::<String, Json<TransformResult>>(
+            TRANSFORM_FUNCTION_NAME,
+            serde_json::to_string(&input).unwrap(),
+        )
+        .map(|e| e.0)
+    {
+        Ok(result) => transformed(input.record, result),
+        Err(e) => {
+            on_filter_error(&filter.name, &input.record, &e, pool.disabled());
+            input.record
+        }
+    }
+}
+
+/// Returns the record returned by `transform`. The topic, the partition and the offset identify the record, they can't be changed.
+fn transformed(original: KafkaRecord, result: TransformResult) -> KafkaRecord {
+    let record = result.record;
+    KafkaRecord {
+        key_as_string: record.key.to_string(),
+        value_as_string: record.value.to_string(),
+        topic: original.topic,
+        partition: original.partition,
+        offset: original.offset,
+        ..record
+    }
+}
 
 impl Search for Filter {
     fn matches(&self, context: &SearchContext) -> bool {
-        let mut filters = context.filters.lock().unwrap();
-        let plugin = &mut filters.get_mut(&self.name).unwrap();
+        if let Some(native) = native_filter(&self.name) {
+            let params = self.parameters.iter().map(|e| e.json()).collect_vec();
+            return match native.matches(context.record, &params) {
+                Ok(matches) => matches,
+                Err(e) => on_filter_error(&self.name, context.record, &extism::Error::msg(e), None),
+            };
+        }
+        let Some(pool) = context.filters.read().unwrap().get(&self.name).cloned() else {
+            let e = extism::Error::msg(format!("search filter '{}' is not loaded", self.name));
+            return on_filter_error(&self.name, context.record, &e, None);
+        };
         let input = FilterInput {
             record: context.record.clone(),
             params: self.parameters.iter().map(|e| e.json()).collect_vec(),
         };
 
-        match plugin
+        match pool
             .call::<String, Json<FilterResult>>(
                 MATCHES_FUNCTION_NAME,
                 serde_json::to_string(&input).unwrap(),
@@ -36,7 +340,7 @@ impl Search for Filter {
             .map(|e| e.0)
         {
             Ok(res) => res.r#match,
-            Err(_e) => true,
+            Err(e) => on_filter_error(&self.name, context.record, &e, pool.disabled()),
         }
     }
 
@@ -44,3 +348,84 @@ impl Search for Filter {
         vec![]
     }
 }
+
+#[test]
+fn test_transformed() {
+    let original = KafkaRecord {
+        topic: "orders".to_string(),
+        partition: 2,
+        offset: 42,
+        value: lib::DataType::Json(serde_json::json!({"secret": "a2V5", "id": 1})),
+        ..Default::default()
+    };
+    let result: TransformResult = serde_json::from_value(serde_json::json!({
+        "record": {
+            "topic": "other",
+            "timestamp": null,
+            "partition": 0,
+            "offset": 0,
+            "headers": {"decrypted": "true"},
+            "key": null,
+            "value": {"id": 1}
+        }
+    }))
+    .unwrap();
+    let record = transformed(original, result);
+    assert_eq!(
+        (record.topic.as_str(), record.partition, record.offset),
+        ("orders", 2, 42)
+    );
+    assert_eq!(record.value_as_string, r#"{"id":1}"#);
+    assert_eq!(record.headers.get("decrypted").unwrap(), "true");
+}
+
+#[test]
+fn test_filter_limits() {
+    let limits: FilterLimits =
+        serde_json::from_str(r#"{"memory_mb": 64, "filters": {"slow": {"timeout_ms": 5000}}}"#)
+            .unwrap();
+    assert_eq!(
+        limits.of("slow"),
+        Limits {
+            timeout_ms: Some(5000),
+            memory_mb: Some(64)
+        }
+    );
+    assert_eq!(
+        limits.of("other"),
+        Limits {
+            timeout_ms: None,
+            memory_mb: Some(64)
+        }
+    );
+    let unlimited: FilterLimits = serde_json::from_str("{}").unwrap();
+    assert_eq!(unlimited, FilterLimits::default());
+    assert_eq!(unlimited.of("slow"), Limits::default());
+}
+
+#[test]
+fn test_filter_error_policy() {
+    use std::str::FromStr;
+    assert_eq!(
+        FilterErrorPolicy::from_str("no-match"),
+        Ok(FilterErrorPolicy::NoMatch)
+    );
+    assert_eq!(FilterErrorPolicy::Abort.to_string(), "abort");
+    assert_eq!(
+        serde_json::from_str::<FilterErrorPolicy>(r#""no-match""#).unwrap(),
+        FilterErrorPolicy::NoMatch
+    );
+}
+
+#[test]
+fn test_filter_not_loaded() {
+    let filter = Filter {
+        name: "test-filter-not-loaded".to_string(),
+        parameters: vec![],
+    };
+    let record = KafkaRecord::default();
+    let context = SearchContext::new(&record, Path::new("/nonexistent"));
+    // Handled like any error of a search filter, the default policy is to match
+    assert!(filter.matches(&context));
+    assert!(filter_errors().contains(&("test-filter-not-loaded".to_string(), 1)));
+}
@@ -0,0 +1,219 @@
+//! Evaluation of the built-in functions of the query language.
+//!
+//! A function returns `None` when it cannot be applied to its argument,
+//! for instance `length(value.price)` on a number. The comparison is then false, like a missing header.
+
+use std::borrow::Cow;
+
+use base64::{Engine, engine::general_purpose};
+use lib::{
+    DataType, KafkaRecord,
+    search::{
+        compare::NumberOperator,
+        function::{Function, FunctionCall},
+        symbol::Symbol,
+    },
+};
+use serde_json::Value;
+
+use super::{
+    compiled::{StringMatcher, compare_numbers, json_pointer, json_scalar},
+    highlight::{Field, Highlight},
+};
+
+/// A function call, with the JSON pointer of its argument built ahead of time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompiledCall {
+    function: Function,
+    argument: Argument,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Argument {
+    Topic,
+    Key,
+    Value(Option<String>),
+    Header(String),
+}
+
+/// The result of a function.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FunctionValue {
+    String(String),
+    Number(i64),
+}
+
+impl FunctionValue {
+    /// Compares the result to a string, numbers are compared as their string representation.
+    pub fn matches(&self, matcher: &StringMatcher) -> bool {
+        match self {
+            Self::String(s) => matcher.matches(s),
+            Self::Number(n) => matcher.matches(&n.to_string()),
+        }
+    }
+
+    /// Compares the result to a number, strings are parsed first.
+    pub fn compare(&self, operator: &NumberOperator, right: i64) -> bool {
+        match self {
+            Self::Number(n) => compare_numbers(*n, operator, right),
+            Self::String(s) => s
+                .trim()
+                .parse::<i64>()
+                .is_ok_and(|n| compare_numbers(n, operator, right)),
+        }
+    }
+}
+
+impl CompiledCall {
+    pub fn new(call: &FunctionCall) -> Self {
+        let argument = match &call.argument {
+            Symbol::Topic => Argument::Topic,
+            Symbol::Key => Argument::Key,
+            Symbol::Header(name) => Argument::Header(name.clone()),
+            Symbol::Value(path) => Argument::Value(path.as_deref().map(json_pointer)),
+            // The parser only accepts the symbols above
+            _ => Argument::Value(None),
+        };
+        Self {
+            function: call.function,
+            argument,
+        }
+    }
+
+    /// Highlights the argument of the function, the result of the function is not part of the record.
+    pub fn highlight(&self) -> Highlight {
+        match &self.argument {
+            Argument::Topic => Highlight::new(Field::Topic, None, None),
+            Argument::Key => Highlight::new(Field::Key, None, None),
+            Argument::Value(pointer) => Highlight::new(Field::Value, pointer.clone(), None),
+            Argument::Header(name) => Highlight::new(Field::Header(name.clone()), None, None),
+        }
+    }
+
+    /// Applies the function to the argument read from the record.
+    pub fn evaluate(&self, record: &KafkaRecord) -> Option<FunctionValue> {
+        let argument = self.argument(record)?;
+        match self.function {
+            Function::Lower => Some(FunctionValue::String(text(&argument).to_lowercase())),
+            Function::Length => match argument.as_ref() {
+                Value::String(s) => Some(FunctionValue::Number(s.chars().count() as i64)),
+                Value::Array(a) => Some(FunctionValue::Number(a.len() as i64)),
+                Value::Object(o) => Some(FunctionValue::Number(o.len() as i64)),
+                _ => None,
+            },
+            Function::JsonType => {
+                let json_type = match argument.as_ref() {
+                    Value::Null => "null",
+                    Value::Bool(_) => "boolean",
+                    Value::Number(_) => "number",
+                    Value::String(_) => "string",
+                    Value::Array(_) => "array",
+                    Value::Object(_) => "object",
+                };
+                Some(FunctionValue::String(json_type.to_string()))
+            }
+            Function::Base64Decode => match argument.as_ref() {
+                Value::String(s) => decode_base64(s.trim()).map(FunctionValue::String),
+                _ => None,
+            },
+        }
+    }
+
+    fn argument<'a>(&self, record: &'a KafkaRecord) -> Option<Cow<'a, Value>> {
+        match &self.argument {
+            Argument::Topic => Some(Cow::Owned(Value::String(record.topic.clone()))),
+            Argument::Key => data_type_value(&record.key, None),
+            Argument::Value(pointer) => data_type_value(&record.value, pointer.as_deref()),
+            Argument::Header(name) => record
+                .headers
+                .get(name)
+                .map(|header| Cow::Owned(Value::String(header.clone()))),
+        }
+    }
+}
+
+/// A plain string payload ignores the JSON pointer, like [`StringMatcher`] does.
+fn data_type_value<'a>(data: &'a DataType, pointer: Option<&str>) -> Option<Cow<'a, Value>> {
+    match (data, pointer) {
+        (DataType::String(s), _) => Some(Cow::Owned(Value::String(s.clone()))),
+        (DataType::Json(value), Some(pointer)) => value.pointer(pointer).map(Cow::Borrowed),
+        (DataType::Json(value), None) => Some(Cow::Borrowed(value)),
+    }
+}
+
+/// Arrays and objects are serialized to JSON.
+fn text(value: &Value) -> Cow<'_, str> {
+    json_scalar(value).unwrap_or_else(|| Cow::Owned(value.to_string()))
+}
+
+/// Padded and unpadded, standard and URL safe alphabets are accepted.
+fn decode_base64(input: &str) -> Option<String> {
+    [
+        general_purpose::STANDARD,
+        general_purpose::STANDARD_NO_PAD,
+        general_purpose::URL_SAFE,
+        general_purpose::URL_SAFE_NO_PAD,
+    ]
+    .iter()
+    .find_map(|engine| engine.decode(input).ok())
+    .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+}
+
+#[test]
+fn test_evaluate_functions() {
+    let record = KafkaRecord {
+        topic: "Purchases".to_string(),
+        key: DataType::String("ORDER-1".to_string()),
+        value: DataType::Json(serde_json::json!({
+            "email": "Jane@Example.com",
+            "items": [1, 2, 3, 4],
+            "id": "a1",
+            "price": 12.5,
+        })),
+        headers: [("payload".to_string(), "aGVsbG8gd29ybGQ=".to_string())].into(),
+        ..Default::default()
+    };
+    let evaluate = |function: Function, argument: Symbol| {
+        CompiledCall::new(&FunctionCall::new(function, argument)).evaluate(&record)
+    };
+    let path = |path: &str| Symbol::Value(Some(path.to_string()));
+    let string = |s: &str| Some(FunctionValue::String(s.to_string()));
+    assert_eq!(
+        evaluate(Function::Lower, path(".email")),
+        string("jane@example.com")
+    );
+    assert_eq!(evaluate(Function::Lower, Symbol::Key), string("order-1"));
+    assert_eq!(
+        evaluate(Function::Lower, Symbol::Topic),
+        string("purchases")
+    );
+    assert_eq!(
+        evaluate(Function::Length, path(".items")),
+        Some(FunctionValue::Number(4))
+    );
+    assert_eq!(
+        evaluate(Function::Length, Symbol::Value(None)),
+        Some(FunctionValue::Number(4))
+    );
+    assert_eq!(evaluate(Function::Length, path(".price")), None);
+    assert_eq!(evaluate(Function::JsonType, path(".id")), string("string"));
+    assert_eq!(
+        evaluate(Function::JsonType, path(".price")),
+        string("number")
+    );
+    assert_eq!(evaluate(Function::JsonType, path(".missing")), None);
+    assert_eq!(
+        evaluate(
+            Function::Base64Decode,
+            Symbol::Header("payload".to_string())
+        ),
+        string("hello world")
+    );
+    assert_eq!(
+        evaluate(
+            Function::Base64Decode,
+            Symbol::Header("missing".to_string())
+        ),
+        None
+    );
+}
@@ -0,0 +1,216 @@
+//! Locations of the matches of a search query in a kafka record.
+//!
+//! [`Search::locate`](super::Search::locate) returns a [`Highlight`] for each comparison that holds.
+//! A highlight doesn't store byte offsets: the TUI renders the value compacted in the list of records
+//! and pretty-printed in the details view. [`Highlight::spans`] finds the offsets in the text that is displayed.
+
+use std::ops::Range;
+
+/// The part of the record a highlight refers to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Field {
+    Topic,
+    Key,
+    Value,
+    Header(String),
+}
+
+/// A comparison of the search query that holds for the record.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Highlight {
+    pub field: Field,
+    /// JSON pointer of the compared property, `/album/title` for `value.album.title`.
+    pub path: Option<String>,
+    /// The matched string, `None` when the whole property matched, for instance with `!=`.
+    pub pattern: Option<String>,
+}
+
+impl Highlight {
+    pub fn new(field: Field, path: Option<String>, pattern: Option<String>) -> Self {
+        Self {
+            field,
+            path,
+            pattern,
+        }
+    }
+
+    /// Returns the byte ranges to highlight in the text of the field.
+    /// The text is either the raw string or a JSON document, compact or pretty-printed.
+    pub fn spans(&self, text: &str) -> Vec<Range<usize>> {
+        let scope = self
+            .path
+            .as_deref()
+            .and_then(|path| locate_pointer(text, path))
+            .unwrap_or(0..text.len());
+        let pattern = match self.pattern.as_deref() {
+            Some(pattern) if !pattern.is_empty() => pattern,
+            _ if scope.is_empty() => return vec![],
+            _ => return vec![scope],
+        };
+        text[scope.clone()]
+            .match_indices(pattern)
+            .map(|(i, m)| scope.start + i..scope.start + i + m.len())
+            .collect()
+    }
+}
+
+/// Returns the byte ranges to highlight in the text, for the highlights of the given field.
+/// Overlapping ranges are merged.
+pub fn spans_of(highlights: &[Highlight], field: &Field, text: &str) -> Vec<Range<usize>> {
+    let mut spans: Vec<Range<usize>> = highlights
+        .iter()
+        .filter(|h| &h.field == field)
+        .flat_map(|h| h.spans(text))
+        .collect();
+    spans.sort_by_key(|s| s.start);
+    let mut merged: Vec<Range<usize>> = vec![];
+    for span in spans {
+        match merged.last_mut() {
+            Some(last) if span.start <= last.end => last.end = last.end.max(span.end),
+            _ => merged.push(span),
+        }
+    }
+    merged
+}
+
+/// Returns the byte range of the value the JSON pointer refers to, in a serialized JSON document.
+fn locate_pointer(text: &str, pointer: &str) -> Option<Range<usize>> {
+    let segments: Vec<String> = pointer
+        .split('/')
+        .skip(1)
+        .map(|s| s.replace("~1", "/").replace("~0", "~"))
+        .collect();
+    let bytes = text.as_bytes();
+    let mut position = skip_whitespaces(bytes, 0);
+    for segment in &segments {
+        position = match bytes.get(position)? {
+            b'{' => member(bytes, position, segment)?,
+            b'[' => element(bytes, position, segment.parse().ok()?)?,
+            _ => return None,
+        };
+    }
+    let end = skip_value(bytes, position)?;
+    // The quotes of a string are not highlighted
+    match *bytes.get(position)? {
+        b'"' => Some(position + 1..end - 1),
+        _ => Some(position..end),
+    }
+}
+
+/// Returns the position of the value of the member of the object starting at `position`.
+fn member(bytes: &[u8], position: usize, name: &str) -> Option<usize> {
+    let mut position = skip_whitespaces(bytes, position + 1);
+    while *bytes.get(position)? == b'"' {
+        let end = skip_value(bytes, position)?;
+        let key: String = serde_json::from_slice(&bytes[position..end]).ok()?;
+        position = skip_whitespaces(bytes, end);
+        if *bytes.get(position)? != b':' {
+            return None;
+        }
+        position = skip_whitespaces(bytes, position + 1);
+        if key == name {
+            return Some(position);
+        }
+        position = skip_whitespaces(bytes, skip_value(bytes, position)?);
+        if *bytes.get(position)? == b',' {
+            position = skip_whitespaces(bytes, position + 1);
+        }
+    }
+    None
+}
+
+/// Returns the position of the `index`-th element of the array starting at `position`.
+fn element(bytes: &[u8], position: usize, index: usize) -> Option<usize> {
+    let mut position = skip_whitespaces(bytes, position + 1);
+    for _ in 0..index {
+        if *bytes.get(position)? == b']' {
+            return None;
+        }
+        position = skip_whitespaces(bytes, skip_value(bytes, position)?);
+        if *bytes.get(position)? != b',' {
+            return None;
+        }
+        position = skip_whitespaces(bytes, position + 1);
+    }
+    match *bytes.get(position)? {
+        b']' => None,
+        _ => Some(position),
+    }
+}
+
+/// Returns the position right after the JSON value starting at `position`.
+fn skip_value(bytes: &[u8], position: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, b) in bytes.iter().enumerate().skip(position) {
+        match (in_string, b) {
+            (true, _) if escaped => escaped = false,
+            (true, b'\\') => escaped = true,
+            (true, b'"') => {
+                in_string = false;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            (true, _) => (),
+            (false, b'"') => in_string = true,
+            (false, b'{' | b'[') => depth += 1,
+            (false, b'}' | b']') if depth == 0 => return Some(i),
+            (false, b'}' | b']') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            (false, b',' | b' ' | b'\n' | b'\r' | b'\t') if depth == 0 => return Some(i),
+            (false, _) => (),
+        }
+    }
+    (depth == 0 && !in_string).then_some(bytes.len())
+}
+
+fn skip_whitespaces(bytes: &[u8], position: usize) -> usize {
+    position
+        + bytes[position.min(bytes.len())..]
+            .iter()
+            .take_while(|b| b.is_ascii_whitespace())
+            .count()
+}
+
+#[test]
+fn test_locate_pointer() {
+    let value = serde_json::json!({"album": {"title": "Virtue", "tracks": [1, {"name": "a\"b"}]}, "price": 12.5});
+    for text in [
+        serde_json::to_string(&value).unwrap(),
+        serde_json::to_string_pretty(&value).unwrap(),
+    ] {
+        let at = |pointer: &str| locate_pointer(&text, pointer).map(|r| &text[r]);
+        assert_eq!(at("/album/title"), Some("Virtue"));
+        assert_eq!(at("/price"), Some("12.5"));
+        assert_eq!(at("/album/tracks/0"), Some("1"));
+        assert_eq!(at("/album/tracks/1/name"), Some(r#"a\"b"#));
+        assert_eq!(at("/album/tracks/2"), None);
+        assert_eq!(at("/missing"), None);
+        assert!(at("/album").unwrap().starts_with('{'));
+    }
+}
+
+#[test]
+fn test_spans() {
+    let text = r#"{"title":"Timeout after timeout","code":"timeout"}"#;
+    let contains = Highlight::new(Field::Value, None, Some("timeout".to_string()));
+    assert_eq!(contains.spans(text), vec![24..31, 41..48]);
+    let in_path = Highlight::new(
+        Field::Value,
+        Some("/code".to_string()),
+        Some("timeout".to_string()),
+    );
+    assert_eq!(in_path.spans(text), vec![41..48]);
+    let whole = Highlight::new(Field::Value, Some("/title".to_string()), None);
+    assert_eq!(&text[whole.spans(text)[0].clone()], "Timeout after timeout");
+    assert_eq!(
+        spans_of(&[contains, in_path], &Field::Value, text),
+        vec![24..31, 41..48]
+    );
+}
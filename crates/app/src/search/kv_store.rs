@@ -0,0 +1,78 @@
+//! Key/value store of a search filter.
+//!
+//! `matches` only sees one record at a time. Search filters remembering records across calls,
+//! to detect duplicates or gaps in sequences, use the host functions `kv_get`, `kv_set` and `kv_delete`.
+//! The store is shared by the instances of the search filter and is emptied when a search starts.
+//!
+//! A search filter importing one of these functions is stateful: its records are evaluated one at a time, in order.
+
+use std::collections::HashMap;
+
+use extism::{EXTISM_USER_MODULE, Function, PTR, UserData, convert::Json, host_fn};
+use wasmparser::{Parser, Payload};
+
+pub type KvStore = HashMap<String, String>;
+
+/// Names of the host functions giving access to the store.
+pub const KV_FUNCTIONS: [&str; 3] = ["kv_get", "kv_set", "kv_delete"];
+
+host_fn!(kv_get(store: KvStore; key: String) -> Json<Option<String>> {
+    let store = store.get()?;
+    let value = store.lock().unwrap().get(&key).cloned();
+    Ok(Json(value))
+});
+
+// Returns the previous value, a search filter detects duplicates with a single call.
+host_fn!(kv_set(store: KvStore; key: String, value: String) -> Json<Option<String>> {
+    let store = store.get()?;
+    let previous = store.lock().unwrap().insert(key, value);
+    Ok(Json(previous))
+});
+
+host_fn!(kv_delete(store: KvStore; key: String) -> Json<Option<String>> {
+    let store = store.get()?;
+    let previous = store.lock().unwrap().remove(&key);
+    Ok(Json(previous))
+});
+
+/// Returns the host functions giving access to the store.
+pub fn host_functions(store: &UserData<KvStore>) -> Vec<Function> {
+    vec![
+        Function::new(KV_FUNCTIONS[0], [PTR], [PTR], store.clone(), kv_get),
+        Function::new(KV_FUNCTIONS[1], [PTR, PTR], [PTR], store.clone(), kv_set),
+        Function::new(KV_FUNCTIONS[2], [PTR], [PTR], store.clone(), kv_delete),
+    ]
+}
+
+/// Returns `true` when the wasm module imports one of the functions of the store.
+pub fn is_stateful(wasm: &[u8]) -> bool {
+    Parser::new(0)
+        .parse_all(wasm)
+        .filter_map(Result::ok)
+        .any(|payload| match payload {
+            Payload::ImportSection(imports) => imports
+                .into_iter()
+                .filter_map(Result::ok)
+                .any(|i| i.module == EXTISM_USER_MODULE && KV_FUNCTIONS.contains(&i.name)),
+            _ => false,
+        })
+}
+
+#[test]
+fn test_is_stateful() {
+    // A module with a function type and a single imported function
+    let module = |name: &str| {
+        let mut wasm = b"\0asm\x01\0\0\0\x01\x04\x01\x60\0\0".to_vec();
+        let mut section = vec![1, EXTISM_USER_MODULE.len() as u8];
+        section.extend(EXTISM_USER_MODULE.as_bytes());
+        section.push(name.len() as u8);
+        section.extend(name.as_bytes());
+        section.extend([0, 0]);
+        wasm.extend([2, section.len() as u8]);
+        wasm.extend(section);
+        wasm
+    };
+    assert!(is_stateful(&module("kv_set")));
+    assert!(!is_stateful(&module("log")));
+    assert!(!is_stateful(b"not wasm"));
+}
@@ -0,0 +1,67 @@
+//! Compacted view of a topic, evaluation of the `distinct key` clause.
+//!
+//! Only the latest record of each key is kept, like a consumer rebuilding a state from a compacted topic would do.
+//! A tombstone deletes the key. A record that doesn't match the search query also removes the previous version of its key:
+//! the view shows the keys whose current state matches the query.
+
+use indexmap::IndexMap;
+use lib::KafkaRecord;
+
+/// Latest record of each key, ordered by the time the key was last updated.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LatestByKey {
+    records: IndexMap<(String, String), KafkaRecord>,
+}
+
+impl LatestByKey {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the state of the key of the record.
+    pub fn push(&mut self, record: KafkaRecord, matches: bool) {
+        let id = (record.topic.clone(), record.key_as_string.clone());
+        self.records.shift_remove(&id);
+        if matches && !record.tombstone {
+            self.records.insert(id, record);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = &KafkaRecord> {
+        self.records.values()
+    }
+
+    pub fn into_records(self) -> impl Iterator<Item = KafkaRecord> {
+        self.records.into_values()
+    }
+}
+
+#[test]
+fn test_latest_by_key() {
+    let record = |key: &str, offset: i64, tombstone: bool| KafkaRecord {
+        topic: "users".to_string(),
+        key_as_string: key.to_string(),
+        offset,
+        tombstone,
+        ..Default::default()
+    };
+    let mut view = LatestByKey::new();
+    view.push(record("alice", 0, false), true);
+    view.push(record("bob", 1, false), true);
+    view.push(record("alice", 2, false), true);
+    view.push(record("carol", 3, false), true);
+    view.push(record("bob", 4, true), true);
+    view.push(record("carol", 5, false), false);
+    assert_eq!(
+        view.records().map(|r| r.offset).collect::<Vec<_>>(),
+        vec![2]
+    );
+}
@@ -1,35 +1,68 @@
 //! Module implementing the search logic
 
 use std::{
-    collections::HashMap,
     path::{Path, PathBuf},
-    sync::{LazyLock, Mutex},
+    sync::Arc,
 };
 
-use extism::{Manifest, Plugin, Wasm};
-use filter::{CACHED_FILTERS, PARSE_PARAMETERS_FUNCTION_NAME};
+use compiled::Predicate;
+use filter::{CACHED_FILTERS, Filters, PARSE_PARAMETERS_FUNCTION_NAME};
+use highlight::Highlight;
 use itertools::Itertools;
 use lib::{
-    KafkaRecord, SearchQuery, parse_search_query,
-    search::{filter::Filter, offset::FromOffset},
+    KafkaRecord, SearchQuery,
+    error::SearchError,
+    parse_search_query,
+    search::{
+        diagnostic::{Diagnostic, did_you_mean},
+        filter::Filter,
+        offset::FromOffset,
+        sample::Sample,
+    },
 };
 use log::error;
+use rdkafka::message::OwnedMessage;
 
+pub mod aggregation;
 pub mod atom;
+pub mod catalog;
+pub mod committed;
 pub mod compare;
+pub mod compiled;
+pub mod evaluation;
 pub mod expression;
 pub mod filter;
+pub mod function;
+pub mod highlight;
+pub mod kv_store;
+pub mod latest_by_key;
+pub mod native;
+pub mod order;
+pub mod plugin_pool;
+pub mod sample;
 pub mod search_query;
 pub mod term;
+pub mod watcher;
 
 pub trait Search {
     /// Returns the offset from which the search should start.
     fn offset(&self) -> Option<FromOffset> {
         None
     }
+    /// Returns the key every matching record must have, when the search query pins it with `key == "..."`.
+    fn pinned_key(&self) -> Option<String> {
+        None
+    }
     /// returns `true` if the record matches the search query.
     fn matches(&self, context: &SearchContext) -> bool;
 
+    /// Returns where the record matches the search query, `None` if it doesn't match.
+    /// Implementations that cannot tell where the record matches return no highlight.
+    /// It's meant for records that matched: search filters are not executed again.
+    fn locate(&self, context: &SearchContext) -> Option<Vec<Highlight>> {
+        self.matches(context).then(Vec::new)
+    }
+
     /// Returns the search filters that are used in the search query.
     fn filters(&self) -> Vec<Filter>;
 }
@@ -40,7 +73,7 @@ pub struct SearchContext<'a> {
     /// The record that is being searched.
     pub record: &'a KafkaRecord,
     /// The search filters that are already loaded in memory.
-    pub filters: &'a LazyLock<Mutex<HashMap<String, Plugin>>>,
+    pub filters: &'a Filters,
     /// The directory containing the search filters
     pub filters_directory: PathBuf,
 }
@@ -55,8 +88,10 @@ impl SearchContext<'_> {
     }
 }
 
+/// A search query whose search filters are loaded.
+/// The expression is compiled into a [`Predicate`] once, records are matched against it.
 #[derive(Debug, Clone, PartialEq, Default)]
-pub struct ValidSearchQuery(SearchQuery);
+pub struct ValidSearchQuery(SearchQuery, Predicate);
 
 impl ValidSearchQuery {
     pub fn is_empty(&self) -> bool {
@@ -70,56 +105,169 @@ impl ValidSearchQuery {
     pub fn query(&self) -> &SearchQuery {
         &self.0
     }
+
+    /// Returns the consumer groups whose committed offsets are needed by the search query.
+    pub fn consumer_groups(&self) -> Vec<String> {
+        let mut groups = self.1.consumer_groups();
+        if let Some(FromOffset::Group(group)) = &self.0.from {
+            groups.push(group.clone());
+        }
+        groups.into_iter().unique().collect()
+    }
+
+    /// Returns the search filters the record matches, `None` if the record doesn't match the search query.
+    pub fn matching_filters(&self, context: &SearchContext) -> Option<Vec<Filter>> {
+        self.1.matching_filters(context)
+    }
+
+    /// Rewrites a matching record with the `transform` function of the search filters it matches,
+    /// see [`ValidSearchQuery::matching_filters`], in the order they appear in the query.
+    pub fn transform(&self, record: KafkaRecord, matching_filters: &[Filter]) -> KafkaRecord {
+        self.0
+            .filters()
+            .iter()
+            .filter(|filter| matching_filters.contains(filter))
+            .fold(record, |record, filter| {
+                filter::transform(filter, record, &CACHED_FILTERS)
+            })
+    }
+
+    /// Returns `true` when a search filter of the query uses its key/value store.
+    /// Records must then be evaluated one at a time, in order.
+    pub fn is_stateful(&self) -> bool {
+        self.filter_names()
+            .iter()
+            .any(|name| filter::is_stateful(name, &CACHED_FILTERS))
+    }
+
+    /// Prepares the search filters for a new search: their key/value stores are emptied and their `on_search_start` hooks are called.
+    pub fn start_search(&self) {
+        for name in self.filter_names() {
+            filter::on_search_start(&name, &CACHED_FILTERS);
+        }
+    }
+
+    /// Calls the `on_search_end` hook of the search filters and returns their reports.
+    pub fn end_search(&self) -> Vec<String> {
+        self.filter_names()
+            .iter()
+            .filter_map(|name| filter::on_search_end(name, &CACHED_FILTERS))
+            .collect()
+    }
+
+    fn filter_names(&self) -> Vec<String> {
+        self.0
+            .filters()
+            .into_iter()
+            .map(|f| f.name)
+            .unique()
+            .collect()
+    }
+
+    /// Evaluates the search query on the kafka message, before it is decoded.
+    /// `Some(false)` means the record cannot match, `None` means the record must be decoded to know.
+    pub fn matches_message(&self, message: &OwnedMessage) -> Option<bool> {
+        self.1.matches_message(message)
+    }
 }
 
 impl ValidSearchQuery {
     pub fn from(input: &str, filters_directory: &Path) -> Result<Self, lib::Error> {
         let query = parse_search_query(input).map_err(lib::Error::Search)?.1;
+        // There is nothing to sample after the end of the partitions
+        if matches!(query.sample, Some(Sample::PerPartition(_)))
+            && query.offset() == Some(FromOffset::End)
+        {
+            return Err(lib::Error::Error(
+                "'sample <n> per partition' reads the records already in the partitions, it cannot start 'from end'".to_string(),
+            ));
+        }
         let filters = query.filters();
         for filter in filters {
             let name = filter.name;
-            let path = filters_directory.join(format!("{}.wasm", &name));
-            let url = Wasm::file(&path);
-            let manifest = Manifest::new([url]);
-            let mut filters = CACHED_FILTERS.lock().unwrap();
+            if let Some(native) = native::native_filter(&name) {
+                let params = filter.parameters.iter().map(|e| e.json()).collect_vec();
+                if let Err(e) = native.parse_parameters(&params) {
+                    error!("Invalid parameters for search filter '{}': {}", name, e);
+                    return Err(lib::Error::Error(format!("{}: {e}", name)));
+                }
+                continue;
+            }
+            let mut filters = CACHED_FILTERS.write().unwrap();
             if !filters.contains_key(&name) {
-                match Plugin::new(manifest, [], true) {
-                    Ok(plugin) => filters.insert(name.to_string(), plugin),
+                match filter::load_filter(&name, filters_directory) {
+                    Ok(pool) => filters.insert(name.to_string(), Arc::new(pool)),
                     Err(err) => {
+                        let path = catalog::filter_path(filters_directory, &name);
                         error!("No such file '{}': {}", path.display(), err);
-                        return Err(lib::Error::Error(format!(
-                            "Cannot find search filter '{}'",
-                            name
-                        )));
+                        return Err(lib::Error::Search(SearchError::Filter(unknown_filter(
+                            input,
+                            &name,
+                            filters_directory,
+                        ))));
                     }
                 };
             }
+            let pool = filters.get(&name).unwrap().clone();
+            drop(filters);
+            if let Some(reason) = pool.disabled() {
+                return Err(lib::Error::Error(format!(
+                    "Search filter '{}' is disabled for the rest of the session, {}",
+                    name, reason
+                )));
+            }
             let params = filter.parameters;
-            let wasm_module = &mut filters.get_mut(&name).unwrap();
-            if let Err(e) = wasm_module.call::<&str, &str>(
-                PARSE_PARAMETERS_FUNCTION_NAME,
+            if let Err(e) = pool.parse_parameters(
                 &serde_json::to_string(&params.iter().map(|e| e.json()).collect_vec()).unwrap(),
             ) {
                 error!(
                     "Error when calling '{}' from wasm module '{}': {:?}",
                     PARSE_PARAMETERS_FUNCTION_NAME, name, e
                 );
-                return Err(lib::Error::Error(format!("{}: {e}", &name)));
+                return Err(lib::Error::Error(format!("{}: {e}", name)));
             };
         }
 
-        Ok(ValidSearchQuery(query))
+        let predicate = match query.sample {
+            Some(Sample::Rate(rate)) => Predicate::compile(&query.expression).sampled(rate),
+            _ => Predicate::compile(&query.expression),
+        };
+        Ok(ValidSearchQuery(query, predicate))
     }
 }
 
+/// Locates the unknown search filter in the query and suggests one of the installed search filters.
+fn unknown_filter(input: &str, name: &str, filters_directory: &Path) -> Diagnostic {
+    let start = input
+        .find(&format!("{}(", name))
+        .or_else(|| input.find(name))
+        .unwrap_or_default();
+    let mut installed = catalog::installed_filters(filters_directory);
+    installed.extend(native::native_filters());
+    Diagnostic::new(
+        input,
+        start..start + name.len(),
+        &format!("unknown search filter '{}'", name),
+    )
+    .with_hint(did_you_mean(name, installed.iter().map(String::as_str)))
+}
+
 impl Search for ValidSearchQuery {
     /// Returns the offset from which the search should start.
     fn offset(&self) -> Option<FromOffset> {
         self.0.offset()
     }
 
+    fn pinned_key(&self) -> Option<String> {
+        self.0.pinned_key()
+    }
+
     fn matches(&self, context: &SearchContext) -> bool {
-        self.0.matches(context)
+        self.1.matches(context)
+    }
+
+    fn locate(&self, context: &SearchContext) -> Option<Vec<Highlight>> {
+        self.1.locate(context)
     }
 
     fn filters(&self) -> Vec<Filter> {
@@ -0,0 +1,114 @@
+//! Search filters implemented in Rust by the applications embedding yozefu.
+//!
+//! A native search filter is called with the same `name(args)` syntax as a wasm search filter.
+//! When a native search filter and a wasm module have the same name, the native search filter is used.
+//!
+//! ```rust
+//! use yozefu_app::search::native::register_native_filter;
+//! use lib::KafkaRecord;
+//! use serde_json::Value;
+//!
+//! register_native_filter("from-acme", |record: &KafkaRecord, _: &[Value]| {
+//!     record.headers.get("origin").is_some_and(|origin| origin == "acme")
+//! });
+//! ```
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, RwLock},
+};
+
+use itertools::Itertools;
+use lib::KafkaRecord;
+use serde_json::Value;
+
+/// A search filter implemented in Rust.
+pub trait NativeFilter: Send + Sync {
+    /// Checks the parameters once per search query, before any record is evaluated.
+    fn parse_parameters(&self, _params: &[Value]) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Returns `true` if the record matches. An error is handled like an error of a wasm search filter.
+    fn matches(&self, record: &KafkaRecord, params: &[Value]) -> Result<bool, String>;
+}
+
+impl<F> NativeFilter for F
+where
+    F: Fn(&KafkaRecord, &[Value]) -> bool + Send + Sync,
+{
+    fn matches(&self, record: &KafkaRecord, params: &[Value]) -> Result<bool, String> {
+        Ok(self(record, params))
+    }
+}
+
+static NATIVE_FILTERS: LazyLock<RwLock<HashMap<String, Arc<dyn NativeFilter>>>> =
+    LazyLock::new(Default::default);
+
+/// Registers a native search filter, replacing the one with the same name.
+pub fn register_native_filter(name: &str, filter: impl NativeFilter + 'static) {
+    NATIVE_FILTERS
+        .write()
+        .unwrap()
+        .insert(name.to_string(), Arc::new(filter));
+}
+
+/// Returns the native search filter with this name.
+pub fn native_filter(name: &str) -> Option<Arc<dyn NativeFilter>> {
+    NATIVE_FILTERS.read().unwrap().get(name).cloned()
+}
+
+/// Returns the names of the native search filters, sorted.
+pub fn native_filters() -> Vec<String> {
+    NATIVE_FILTERS
+        .read()
+        .unwrap()
+        .keys()
+        .cloned()
+        .sorted()
+        .collect()
+}
+
+#[test]
+fn test_native_filter() {
+    use super::{Search, SearchContext, ValidSearchQuery};
+    use std::path::Path;
+
+    struct KeyEndsWith;
+    impl NativeFilter for KeyEndsWith {
+        fn parse_parameters(&self, params: &[Value]) -> Result<(), String> {
+            match params {
+                [Value::String(_)] => Ok(()),
+                _ => Err("a suffix is expected".to_string()),
+            }
+        }
+
+        fn matches(&self, record: &KafkaRecord, params: &[Value]) -> Result<bool, String> {
+            let suffix = params[0].as_str().unwrap_or_default();
+            Ok(record.key_as_string.ends_with(suffix))
+        }
+    }
+    register_native_filter("native-key-ends-with", KeyEndsWith);
+    register_native_filter("native-tombstone", |record: &KafkaRecord, _: &[Value]| {
+        record.tombstone
+    });
+    assert!(native_filters().contains(&"native-tombstone".to_string()));
+
+    let directory = Path::new("/nonexistent");
+    let query = ValidSearchQuery::from(
+        "native-key-ends-with('42') && !native-tombstone()",
+        directory,
+    )
+    .unwrap();
+    let record = KafkaRecord {
+        key_as_string: "order-42".to_string(),
+        ..Default::default()
+    };
+    assert!(query.matches(&SearchContext::new(&record, directory)));
+    let tombstone = KafkaRecord {
+        tombstone: true,
+        ..record.clone()
+    };
+    assert!(!query.matches(&SearchContext::new(&tombstone, directory)));
+    assert!(ValidSearchQuery::from("native-key-ends-with(42)", directory).is_err());
+}
@@ -0,0 +1,174 @@
+//! Evaluation of the `order by` clause.
+//!
+//! The same comparison is used to sort the records displayed by the TUI and the records buffered by the headless mode.
+//! JSON properties and headers are compared as numbers or dates when possible, as strings otherwise.
+//! Records without the property are listed last, whatever the direction of the sort key.
+
+use std::cmp::Ordering;
+
+use chrono::DateTime;
+use lib::{
+    DataType, KafkaRecord,
+    search::{Order, OrderBy, SortKey},
+};
+
+use super::compiled::json_pointer;
+
+/// Compares two records according to the sort keys of the `order by` clause.
+pub fn compare_records(order_by: &OrderBy, a: &KafkaRecord, b: &KafkaRecord) -> Ordering {
+    order_by
+        .keys
+        .iter()
+        .map(|key| compare_by_key(key, a, b))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+fn compare_by_key(key: &SortKey, a: &KafkaRecord, b: &KafkaRecord) -> Ordering {
+    let ordering = match &key.order {
+        Order::Timestamp => a.timestamp.cmp(&b.timestamp),
+        Order::Key => a.key_as_string.cmp(&b.key_as_string),
+        Order::Value(None) => a.value_as_string.cmp(&b.value_as_string),
+        Order::Value(Some(path)) => {
+            let pointer = json_pointer(path);
+            return compare_typed(
+                json_value_at(&a.value, &pointer),
+                json_value_at(&b.value, &pointer),
+                key.is_descending(),
+            );
+        }
+        Order::Header(name) => {
+            return compare_typed(
+                a.headers.get(name).map(|v| SortValue::parse(v)),
+                b.headers.get(name).map(|v| SortValue::parse(v)),
+                key.is_descending(),
+            );
+        }
+        Order::Partition => a.partition.cmp(&b.partition),
+        Order::Offset => a.offset.cmp(&b.offset),
+        Order::Size => a.size.cmp(&b.size),
+        Order::Topic => a.topic.cmp(&b.topic),
+    };
+    match key.is_descending() {
+        true => ordering.reverse(),
+        false => ordering,
+    }
+}
+
+/// Missing values are always listed last.
+fn compare_typed(a: Option<SortValue>, b: Option<SortValue>, descending: bool) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) if descending => b.compare(&a),
+        (Some(a), Some(b)) => a.compare(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+fn json_value_at(data: &DataType, pointer: &str) -> Option<SortValue> {
+    let value = match data {
+        DataType::Json(value) => value.pointer(pointer)?,
+        DataType::String(_) => return None,
+    };
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::Number(n) => n.as_f64().map(SortValue::Number),
+        serde_json::Value::String(s) => Some(SortValue::parse(s)),
+        v => Some(SortValue::Text(v.to_string())),
+    }
+}
+
+/// A property of a record, typed for the comparison.
+#[derive(Debug, Clone, PartialEq)]
+enum SortValue {
+    Number(f64),
+    /// Milliseconds since epoch of a RFC 3339 date.
+    Date(i64),
+    Text(String),
+}
+
+impl SortValue {
+    fn parse(value: &str) -> Self {
+        if let Ok(number) = value.parse::<f64>() {
+            return Self::Number(number);
+        }
+        match DateTime::parse_from_rfc3339(value) {
+            Ok(date) => Self::Date(date.timestamp_millis()),
+            Err(_) => Self::Text(value.to_string()),
+        }
+    }
+
+    /// Values of different types are ordered numbers first, then dates, then strings.
+    fn compare(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Number(a), Self::Number(b)) => a.total_cmp(b),
+            (Self::Date(a), Self::Date(b)) => a.cmp(b),
+            (Self::Text(a), Self::Text(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Number(_) => 0,
+            Self::Date(_) => 1,
+            Self::Text(_) => 2,
+        }
+    }
+}
+
+#[cfg(test)]
+fn record(offset: i64, value: serde_json::Value) -> KafkaRecord {
+    KafkaRecord {
+        offset,
+        timestamp: Some(1_000 - offset),
+        value: DataType::Json(value),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+fn sorted_offsets(input: &str, mut records: Vec<KafkaRecord>) -> Vec<i64> {
+    let order_by = lib::parse_search_query(input).unwrap().1.order_by;
+    records.sort_by(|a, b| compare_records(&order_by, a, b));
+    records.iter().map(|r| r.offset).collect()
+}
+
+#[test]
+fn test_order_by_json_path() {
+    let records = vec![
+        record(0, serde_json::json!({"priority": 2})),
+        record(1, serde_json::json!({"priority": "10"})),
+        record(2, serde_json::json!({})),
+        record(3, serde_json::json!({"priority": 2})),
+        record(4, serde_json::json!({"priority": 9.5})),
+    ];
+    assert_eq!(
+        sorted_offsets(
+            "order by value.priority desc, timestamp asc",
+            records.clone()
+        ),
+        vec![1, 4, 3, 0, 2]
+    );
+    assert_eq!(
+        sorted_offsets("order by value.priority, offset desc", records),
+        vec![3, 0, 4, 1, 2]
+    );
+}
+
+#[test]
+fn test_order_by_date() {
+    let records = vec![
+        record(
+            0,
+            serde_json::json!({"at": "2024-11-23T15:00:00.000+01:00"}),
+        ),
+        record(1, serde_json::json!({"at": "2024-11-23T13:00:00.000Z"})),
+        record(
+            2,
+            serde_json::json!({"at": "2024-11-23T12:00:00.000+01:00"}),
+        ),
+    ];
+    assert_eq!(sorted_offsets("order by value.at", records), vec![2, 1, 0]);
+}
@@ -0,0 +1,279 @@
+//! A pool of instances of a wasm search filter.
+//!
+//! An instance of a wasm module runs one call at a time.
+//! Each search filter gets a pool of instances so records can be evaluated on several threads.
+//! Instances are created on demand, up to the number of available threads.
+//! A pool exceeding its [`Limits`] is disabled, the next calls fail right away.
+//! A stateful search filter has a single instance, see [`super::kv_store`].
+
+use std::{
+    sync::{Condvar, Mutex},
+    time::Duration,
+};
+
+use extism::{FromBytesOwned, Manifest, Plugin, ToBytes, UserData, Wasm};
+use log::warn;
+
+use super::{
+    catalog::DESCRIBE_FUNCTION_NAME,
+    filter::{
+        Limits, MATCHES_FUNCTION_NAME, ON_SEARCH_END_FUNCTION_NAME, ON_SEARCH_START_FUNCTION_NAME,
+        PARSE_PARAMETERS_FUNCTION_NAME, TRANSFORM_FUNCTION_NAME,
+    },
+    kv_store::{KvStore, host_functions, is_stateful},
+};
+
+/// Number of pages of wasm memory, 64KiB each, in a megabyte
+const PAGES_PER_MB: u32 = 16;
+
+/// An instance of the wasm module and the parameters it received.
+struct Instance {
+    plugin: Plugin,
+    /// Generation of the parameters passed to `parse_parameters`
+    generation: usize,
+}
+
+#[derive(Default)]
+struct State {
+    idle: Vec<Instance>,
+    /// Number of instances, idle or busy
+    size: usize,
+    /// The last parameters accepted by `parse_parameters`, in JSON
+    parameters: Option<String>,
+    generation: usize,
+    /// Why the pool is disabled
+    disabled: Option<String>,
+}
+
+pub struct PluginPool {
+    manifest: Manifest,
+    limits: Limits,
+    /// Functions of the search filter API exported by the wasm module
+    exports: Vec<&'static str>,
+    /// Key/value store shared by the instances
+    store: UserData<KvStore>,
+    /// `true` when the wasm module uses the key/value store
+    stateful: bool,
+    max_size: usize,
+    state: Mutex<State>,
+    released: Condvar,
+}
+
+impl std::fmt::Debug for PluginPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PluginPool")
+            .field("max_size", &self.max_size)
+            .field("limits", &self.limits)
+            .field("exports", &self.exports)
+            .field("stateful", &self.stateful)
+            .field("size", &self.size())
+            .finish()
+    }
+}
+
+impl PluginPool {
+    /// Loads the wasm module. The first instance is created right away to report an invalid module.
+    pub fn new(manifest: Manifest, limits: Limits) -> Result<Self, extism::Error> {
+        let mut manifest = manifest;
+        if let Some(timeout_ms) = limits.timeout_ms {
+            manifest = manifest.with_timeout(Duration::from_millis(timeout_ms));
+        }
+        if let Some(memory_mb) = limits.memory_mb {
+            manifest = manifest.with_memory_max(memory_mb.saturating_mul(PAGES_PER_MB));
+        }
+        let store = UserData::new(KvStore::new());
+        let plugin = Plugin::new(manifest.clone(), host_functions(&store), true)?;
+        let exports = [
+            MATCHES_FUNCTION_NAME,
+            PARSE_PARAMETERS_FUNCTION_NAME,
+            TRANSFORM_FUNCTION_NAME,
+            ON_SEARCH_START_FUNCTION_NAME,
+            ON_SEARCH_END_FUNCTION_NAME,
+            DESCRIBE_FUNCTION_NAME,
+        ]
+        .into_iter()
+        .filter(|function| plugin.function_exists(function))
+        .collect();
+        let stateful = manifest.wasm.iter().any(|wasm| match wasm {
+            Wasm::File { path, .. } => std::fs::read(path).is_ok_and(|wasm| is_stateful(&wasm)),
+            Wasm::Data { data, .. } => is_stateful(data),
+            Wasm::Url { .. } => false,
+        });
+        let max_size = match stateful {
+            true => 1,
+            false => std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+        };
+        Ok(Self {
+            manifest,
+            limits,
+            exports,
+            store,
+            stateful,
+            max_size,
+            state: Mutex::new(State {
+                idle: vec![Instance {
+                    plugin,
+                    generation: 0,
+                }],
+                size: 1,
+                ..Default::default()
+            }),
+            released: Condvar::new(),
+        })
+    }
+
+    /// Number of instances of the wasm module.
+    pub fn size(&self) -> usize {
+        self.state.lock().unwrap().size
+    }
+
+    /// Returns the functions of the search filter API exported by the wasm module.
+    pub fn exported(&self) -> &[&'static str] {
+        &self.exports
+    }
+
+    /// Returns `true` when the wasm module exports the function.
+    pub fn exports(&self, function: &str) -> bool {
+        self.exports.contains(&function)
+    }
+
+    /// Returns `true` when the search filter uses the key/value store.
+    /// Its records must be evaluated one at a time, in order.
+    pub fn stateful(&self) -> bool {
+        self.stateful
+    }
+
+    /// Empties the key/value store.
+    pub fn clear_store(&self) {
+        if let Ok(store) = self.store.get() {
+            store.lock().unwrap().clear();
+        }
+    }
+
+    /// Returns why the pool is disabled, `None` if it's enabled.
+    pub fn disabled(&self) -> Option<String> {
+        self.state.lock().unwrap().disabled.clone()
+    }
+
+    /// Calls `parse_parameters` on one instance.
+    /// Once accepted, the other instances receive the same parameters before their next call.
+    pub fn parse_parameters(&self, parameters: &str) -> Result<(), extism::Error> {
+        let mut instance = self.acquire()?;
+        let result = instance
+            .plugin
+            .call::<&str, &[u8]>(PARSE_PARAMETERS_FUNCTION_NAME, parameters)
+            .map(|_| ());
+        let mut state = self.state.lock().unwrap();
+        if let Err(e) = &result {
+            self.check_limits(&mut state, e);
+        }
+        if result.is_ok() {
+            state.generation += 1;
+            state.parameters = Some(parameters.to_string());
+            instance.generation = state.generation;
+        }
+        drop(state);
+        self.release(instance);
+        result
+    }
+
+    /// Calls a function of the wasm module on an idle instance, waiting for one when all of them are busy.
+    pub fn call<'a, I: ToBytes<'a>, O: FromBytesOwned>(
+        &self,
+        function: &str,
+        input: I,
+    ) -> Result<O, extism::Error> {
+        let mut instance = self.acquire()?;
+        let result = instance.plugin.call::<I, O>(function, input);
+        if let Err(e) = &result {
+            self.check_limits(&mut self.state.lock().unwrap(), e);
+        }
+        self.release(instance);
+        result
+    }
+
+    /// Disables the pool when the error comes from exceeding the limits.
+    fn check_limits(&self, state: &mut State, e: &extism::Error) {
+        let reason = match e.to_string().as_str() {
+            "timeout" => format!(
+                "a call took more than {} ms",
+                self.limits.timeout_ms.unwrap_or_default()
+            ),
+            "oom" => format!(
+                "an instance used more than {} MB of memory",
+                self.limits.memory_mb.unwrap_or_default()
+            ),
+            _ => return,
+        };
+        state.disabled.get_or_insert(reason);
+    }
+
+    fn acquire(&self) -> Result<Instance, extism::Error> {
+        let mut state = self.state.lock().unwrap();
+        let mut instance = loop {
+            if let Some(reason) = &state.disabled {
+                return Err(extism::Error::msg(format!(
+                    "the search filter is disabled, {}",
+                    reason
+                )));
+            }
+            if let Some(instance) = state.idle.pop() {
+                break instance;
+            }
+            if state.size < self.max_size {
+                state.size += 1;
+                drop(state);
+                return self.create();
+            }
+            state = self.released.wait(state).unwrap();
+        };
+        // The parameters changed since this instance was used
+        if instance.generation != state.generation {
+            let parameters = state.parameters.clone();
+            instance.generation = state.generation;
+            drop(state);
+            if let Some(parameters) = parameters {
+                if let Err(e) = instance
+                    .plugin
+                    .call::<&str, &[u8]>(PARSE_PARAMETERS_FUNCTION_NAME, &parameters)
+                {
+                    warn!(
+                        "Cannot pass the parameters to an instance of the search filter: {}",
+                        e
+                    );
+                }
+            }
+        }
+        Ok(instance)
+    }
+
+    /// Creates a new instance, it receives the last accepted parameters.
+    fn create(&self) -> Result<Instance, extism::Error> {
+        let (parameters, generation) = {
+            let state = self.state.lock().unwrap();
+            (state.parameters.clone(), state.generation)
+        };
+        let plugin = Plugin::new(self.manifest.clone(), host_functions(&self.store), true)
+            .and_then(|mut plugin| {
+                if let Some(parameters) = parameters {
+                    plugin.call::<&str, &[u8]>(PARSE_PARAMETERS_FUNCTION_NAME, &parameters)?;
+                }
+                Ok(plugin)
+            });
+        match plugin {
+            Ok(plugin) => Ok(Instance { plugin, generation }),
+            Err(e) => {
+                self.state.lock().unwrap().size -= 1;
+                self.released.notify_one();
+                Err(e)
+            }
+        }
+    }
+
+    fn release(&self, instance: Instance) {
+        self.state.lock().unwrap().idle.push(instance);
+        self.released.notify_one();
+    }
+}
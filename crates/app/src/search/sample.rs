@@ -0,0 +1,151 @@
+//! Evaluation of the `sample` clause.
+//!
+//! `sample 1%` keeps each record with a probability of 1%. The draw is a hash of the topic, the partition and the offset,
+//! so running the same search query twice returns the same sample.
+//!
+//! `sample 1000 per partition` reads 1000 records at evenly spaced offsets of each partition.
+//! The consumer seeks from one position to the next instead of reading the whole partition.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::{DefaultHasher, Hash, Hasher},
+    time::Duration,
+};
+
+use log::warn;
+use rdkafka::{
+    Offset, TopicPartitionList,
+    consumer::{Consumer, StreamConsumer},
+};
+
+/// One million, the rate of `sample 100%`.
+const MILLION: u64 = 1_000_000;
+
+/// Returns `true` if the record is part of a sample of `rate` parts per million.
+pub fn keep(rate: u32, topic: &str, partition: i32, offset: i64) -> bool {
+    let mut hasher = DefaultHasher::new();
+    (topic, partition, offset).hash(&mut hasher);
+    hasher.finish() % MILLION < rate as u64
+}
+
+/// Offsets to read on each partition, for `sample <n> per partition`.
+#[derive(Debug, Clone, Default)]
+pub struct PartitionSampler {
+    positions: HashMap<(String, i32), VecDeque<i64>>,
+}
+
+impl PartitionSampler {
+    /// Spreads `count` positions over `[start, end)` of each partition.
+    pub fn new(partitions: &[(String, i32, i64, i64)], count: usize) -> Self {
+        let positions = partitions
+            .iter()
+            .map(|(topic, partition, start, end)| {
+                ((topic.clone(), *partition), positions(*start, *end, count))
+            })
+            .filter(|(_, positions)| !positions.is_empty())
+            .collect();
+        Self { positions }
+    }
+
+    /// The first position of each partition, where the consumer starts.
+    pub fn assignments(&self) -> TopicPartitionList {
+        let mut assignments = TopicPartitionList::new();
+        for ((topic, partition), positions) in &self.positions {
+            if let Some(first) = positions.front() {
+                let _ = assignments.add_partition_offset(topic, *partition, Offset::Offset(*first));
+            }
+        }
+        assignments
+    }
+
+    /// Number of records left to read.
+    pub fn len(&self) -> usize {
+        self.positions.values().map(VecDeque::len).sum()
+    }
+
+    /// Returns `true` once every position has been read.
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Returns `true` if the record is part of the sample, and seeks the consumer to the next position of the partition.
+    /// The seek is asynchronous, it doesn't block the stream of records it is called from.
+    /// Records between two positions were fetched before the seek, they are skipped.
+    /// Compacted partitions may not have a record at a position, the next record is kept instead.
+    pub fn sample(
+        &mut self,
+        consumer: &StreamConsumer,
+        topic: &str,
+        partition: i32,
+        offset: i64,
+    ) -> bool {
+        let key = (topic.to_string(), partition);
+        let Some(positions) = self.positions.get_mut(&key) else {
+            return false;
+        };
+        let mut kept = false;
+        while positions.front().is_some_and(|p| *p <= offset) {
+            positions.pop_front();
+            kept = true;
+        }
+        match positions.front() {
+            Some(next) if kept && *next > offset + 1 => {
+                if let Err(e) =
+                    consumer.seek(topic, partition, Offset::Offset(*next), Duration::ZERO)
+                {
+                    warn!(
+                        "Cannot seek to offset {} of topic '{}', partition {}: {}",
+                        next, topic, partition, e
+                    );
+                }
+            }
+            Some(_) => (),
+            None => {
+                self.positions.remove(&key);
+                let mut done = TopicPartitionList::new();
+                done.add_partition(topic, partition);
+                let _ = consumer.pause(&done);
+            }
+        }
+        kept
+    }
+}
+
+/// `count` evenly spaced offsets in `[start, end)`.
+fn positions(start: i64, end: i64, count: usize) -> VecDeque<i64> {
+    let length = (end - start).max(0) as i128;
+    if length <= count as i128 {
+        return (start..end.max(start)).collect();
+    }
+    (0..count as i128)
+        .map(|i| start + (i * length / count as i128) as i64)
+        .collect()
+}
+
+#[test]
+fn test_positions() {
+    assert_eq!(positions(0, 10, 5), vec![0, 2, 4, 6, 8]);
+    assert_eq!(positions(100, 103, 5), vec![100, 101, 102]);
+    assert_eq!(positions(10, 10, 5), Vec::<i64>::new());
+    assert_eq!(positions(0, 1_000_000_000_000, 2), vec![0, 500_000_000_000]);
+}
+
+#[test]
+fn test_keep() {
+    let kept = (0..100_000)
+        .filter(|offset| keep(10_000, "purchases", 0, *offset))
+        .count();
+    assert!((800..1200).contains(&kept), "{}", kept);
+    assert!((0..1000).all(|offset| keep(1_000_000, "purchases", 0, offset)));
+    assert!((0..1000).all(|offset| !keep(0, "purchases", 0, offset)));
+}
+
+#[test]
+fn test_sample_per_partition_from_end() {
+    use super::ValidSearchQuery;
+    let directory = std::path::Path::new("/nonexistent");
+    assert!(ValidSearchQuery::from("from end sample 10 per partition", directory).is_err());
+    assert!(ValidSearchQuery::from("from end - 100 sample 10 per partition", directory).is_ok());
+    assert!(ValidSearchQuery::from("sample 10 per partition", directory).is_ok());
+    assert!(ValidSearchQuery::from("from end sample 10%", directory).is_ok());
+}
@@ -1,17 +1,26 @@
 use lib::{
     SearchQuery,
-    search::{filter::Filter, offset::FromOffset},
+    search::{filter::Filter, offset::FromOffset, sample::Sample},
 };
 
-use super::{Search, SearchContext};
+use super::{Search, SearchContext, sample::keep};
 
 impl Search for SearchQuery {
     fn offset(&self) -> Option<FromOffset> {
         self.from.clone().or(self.expression.offset())
     }
 
+    fn pinned_key(&self) -> Option<String> {
+        self.expression.pinned_key()
+    }
+
     fn matches(&self, context: &SearchContext) -> bool {
-        self.expression.matches(context)
+        let record = context.record;
+        let sampled = match self.sample {
+            Some(Sample::Rate(rate)) => keep(rate, &record.topic, record.partition, record.offset),
+            _ => true,
+        };
+        sampled && self.expression.matches(context)
     }
 
     fn filters(&self) -> Vec<Filter> {
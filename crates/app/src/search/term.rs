@@ -10,6 +10,13 @@ impl Search for Term {
         }
     }
 
+    fn pinned_key(&self) -> Option<String> {
+        match self {
+            Term::Not(_) => None,
+            Term::Atom(a) => a.pinned_key(),
+        }
+    }
+
     fn matches(&self, context: &SearchContext) -> bool {
         match self {
             Term::Not(a) => !a.matches(context),
@@ -0,0 +1,111 @@
+//! Watches the filters directory so that rebuilt search filters are picked up without restarting yozefu.
+//!
+//! A build usually writes the wasm module in several steps. The events are debounced:
+//! a search filter is reported once its wasm module hasn't changed for [`DEBOUNCE`].
+
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+use indexmap::IndexSet;
+use log::warn;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// How long a wasm module must stay unchanged before the search filter is reported.
+pub const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Watches the filters directory as long as it is alive.
+pub struct FilterWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl FilterWatcher {
+    /// Calls `on_change` with the name of each search filter whose wasm module is created, modified or removed.
+    pub fn watch(
+        filters_directory: &Path,
+        on_change: impl Fn(String) + Send + 'static,
+    ) -> Result<Self, lib::Error> {
+        let (tx, rx) = mpsc::channel::<PathBuf>();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+                Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+                    for path in event.paths {
+                        let _ = tx.send(path);
+                    }
+                }
+                Ok(_) => (),
+                Err(e) => warn!("Error when watching the search filters: {}", e),
+            })
+            .map_err(|e| lib::Error::Error(e.to_string()))?;
+        watcher
+            .watch(filters_directory, RecursiveMode::NonRecursive)
+            .map_err(|e| {
+                lib::Error::Error(format!(
+                    "I cannot watch '{}': {}",
+                    filters_directory.display(),
+                    e
+                ))
+            })?;
+
+        // The thread ends when the watcher is dropped, since it owns the sender.
+        thread::spawn(move || {
+            let mut changed = IndexSet::new();
+            loop {
+                match rx.recv_timeout(DEBOUNCE) {
+                    Ok(path) => {
+                        if let Some(name) = filter_name(&path) {
+                            changed.insert(name);
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {
+                        for name in changed.drain(..) {
+                            on_change(name);
+                        }
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        });
+        Ok(Self { _watcher: watcher })
+    }
+}
+
+/// Returns the name of the search filter when the path is a wasm module.
+fn filter_name(path: &Path) -> Option<String> {
+    if path.extension()? != "wasm" {
+        return None;
+    }
+    Some(path.file_stem()?.to_str()?.to_string())
+}
+
+#[test]
+fn test_filter_name() {
+    assert_eq!(
+        filter_name(Path::new("/filters/key-ends-with.wasm")),
+        Some("key-ends-with".to_string())
+    );
+    assert_eq!(
+        filter_name(Path::new("/filters/key-ends-with.wasm.tmp")),
+        None
+    );
+    assert_eq!(filter_name(Path::new("/filters/README.md")), None);
+}
+
+#[test]
+fn test_watch() {
+    let directory = tempfile::tempdir().unwrap();
+    let (tx, rx) = mpsc::channel();
+    let _watcher = FilterWatcher::watch(directory.path(), move |name| {
+        tx.send(name).unwrap();
+    })
+    .unwrap();
+    for _ in 0..3 {
+        std::fs::write(directory.path().join("dedupe.wasm"), "").unwrap();
+    }
+    std::fs::write(directory.path().join("notes.txt"), "").unwrap();
+    assert_eq!(rx.recv_timeout(Duration::from_secs(10)).unwrap(), "dedupe");
+    assert!(rx.recv_timeout(DEBOUNCE * 2).is_err());
+}
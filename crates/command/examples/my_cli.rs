@@ -3,10 +3,12 @@
 use app::configuration::{ClusterConfig, YozefuConfig};
 use clap::Parser;
 use indexmap::IndexMap;
+use lib::KafkaRecord;
 use rdkafka::ClientConfig;
+use serde_json::Value;
 use strum::{Display, EnumIter, EnumString};
 use tui::TuiError;
-use yozefu_command::Cli;
+use yozefu_command::{Cli, register_filter};
 
 /// I have 4 kafka clusters
 #[derive(Debug, Clone, PartialEq, Eq, Display, EnumString, EnumIter, Default)]
@@ -52,6 +54,7 @@ impl MyCli {
             url_template: None,
             schema_registry: None,
             kafka: IndexMap::from_iter(config.config_map().clone()),
+            ..Default::default()
         }
     }
 
@@ -67,5 +70,12 @@ impl MyCli {
 #[tokio::main]
 async fn main() -> Result<(), String> {
     let parsed = MyCli::parse();
+    // Search filters can also be written in Rust: `from begin where from-acme()`
+    register_filter("from-acme", |record: &KafkaRecord, _: &[Value]| {
+        record
+            .headers
+            .get("origin")
+            .is_some_and(|origin| origin == "acme")
+    });
     parsed.execute().await.map_err(|e| e.to_string())
 }
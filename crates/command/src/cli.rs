@@ -5,7 +5,6 @@ use crate::theme::init_themes_file;
 use crate::version::VERSION_MESSAGE;
 use app::APPLICATION_NAME;
 use app::configuration::{ClusterConfig, GlobalConfig, SchemaRegistryConfig, YozefuConfig};
-use clap::command;
 use lib::Error;
 use reqwest::Url;
 use std::fs;
@@ -0,0 +1,90 @@
+//! The blueprints of [`crates/wasm-blueprints`](https://github.com/MAIF/yozefu/tree/main/crates/wasm-blueprints), embedded in the binary.
+//! A blueprint is rendered into a new directory with the name of the search filter substituted.
+//!
+//! The blueprints live outside of this crate, they are bundled in `blueprints.json` so the crate can be published on its own.
+//! When a blueprint changes, update the bundle with `UPDATE_BLUEPRINTS=1 cargo test -p yozefu-command blueprints`.
+
+use std::{fs, path::Path, sync::LazyLock};
+
+use indexmap::IndexMap;
+use lib::Error;
+
+/// The files of a blueprint, their content by path relative to the blueprint directory.
+pub(crate) type Blueprint = IndexMap<String, String>;
+
+/// The blueprints by language.
+static BLUEPRINTS: LazyLock<IndexMap<String, Blueprint>> = LazyLock::new(|| {
+    serde_json::from_str(include_str!("../../blueprints.json"))
+        .expect("The embedded blueprints should be valid JSON")
+});
+
+/// Returns the blueprint of the language: `rust`, `golang` or `js`.
+pub(crate) fn blueprint(language: &str) -> &'static Blueprint {
+    &BLUEPRINTS[language]
+}
+
+/// Writes the files of the blueprint to the directory.
+pub(crate) fn render(files: &Blueprint, directory: &Path, name: &str) -> Result<(), Error> {
+    for (path, content) in files {
+        let destination = directory.join(path);
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(destination, substitute(content, name))?;
+    }
+    Ok(())
+}
+
+/// Replaces the names of the blueprint projects with the name of the search filter.
+fn substitute(content: &str, name: &str) -> String {
+    let mut content = content.replace("--name 'key-ends-with'", &format!("--name '{}'", name));
+    for language in ["rust", "golang", "js"] {
+        content = content
+            .replace(&format!("yozefu-wasm-blueprints-{}", language), name)
+            .replace(
+                &format!("yozefu_wasm_blueprints_{}", language),
+                &name.replace('-', "_"),
+            );
+    }
+    content
+}
+
+#[test]
+fn test_render() {
+    let directory = tempfile::tempdir().unwrap();
+    render(blueprint("rust"), directory.path(), "dedupe-events").unwrap();
+    let cargo = fs::read_to_string(directory.path().join("Cargo.toml")).unwrap();
+    assert!(cargo.contains(r#"name = "dedupe-events""#));
+    let makefile = fs::read_to_string(directory.path().join("Makefile")).unwrap();
+    assert!(makefile.contains("release/dedupe_events.wasm"));
+    let readme = fs::read_to_string(directory.path().join("README.md")).unwrap();
+    assert!(readme.contains("--name 'dedupe-events'"));
+    assert!(directory.path().join("src/lib.rs").exists());
+    for language in ["rust", "golang", "js"] {
+        assert!(blueprint(language).contains_key("Makefile"));
+    }
+}
+
+#[test]
+fn test_blueprints_are_up_to_date() {
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let directory = manifest.join("../wasm-blueprints");
+    // The published crate comes without the blueprints
+    if !directory.exists() {
+        return;
+    }
+    let mut blueprints = BLUEPRINTS.clone();
+    for (language, files) in blueprints.iter_mut() {
+        for (path, content) in files.iter_mut() {
+            *content = fs::read_to_string(directory.join(language).join(path.as_str())).unwrap();
+        }
+    }
+    if std::env::var("UPDATE_BLUEPRINTS").is_ok() {
+        let json = serde_json::to_string_pretty(&blueprints).unwrap();
+        fs::write(manifest.join("blueprints.json"), json + "\n").unwrap();
+    }
+    assert!(
+        blueprints == *BLUEPRINTS,
+        "The blueprints changed, run `UPDATE_BLUEPRINTS=1 cargo test -p yozefu-command blueprints`"
+    );
+}
@@ -1,21 +1,21 @@
 //! Command to create a new wasm filter.
-use std::{
-    fs,
-    path::{Path, PathBuf},
-};
+use std::{fs, path::PathBuf};
 
+use app::search::catalog::validate_filter_name;
 use chrono::Local;
 use clap::Args;
 use lib::Error;
-use log::{info, warn};
+use log::info;
 use strum::{Display, EnumIter, EnumString};
 
 use crate::{
     APPLICATION_NAME,
-    command::{Command, default_editor},
+    command::{Command, ImportFilterCommand, default_editor},
 };
 use std::process::Command as ProcessCommand;
 
+use super::blueprints::{self, Blueprint};
+
 #[derive(Debug, Clone, Args)]
 pub(crate) struct CreateFilterCommand {
     #[clap(short, long)]
@@ -24,6 +24,12 @@ pub(crate) struct CreateFilterCommand {
     #[clap(long)]
     /// Location of the search filter repository
     directory: Option<PathBuf>,
+    /// Build the wasm module with `make build` and import it
+    #[clap(long)]
+    import: bool,
+    /// Overwrite the search filter if it is already imported
+    #[clap(long, requires = "import")]
+    force: bool,
     /// Name of the search filter
     name: String,
 }
@@ -33,10 +39,23 @@ pub(crate) struct CreateFilterCommand {
 pub enum SupportedLanguages {
     Rust,
     Golang,
+    Js,
+}
+
+impl SupportedLanguages {
+    /// Returns the files of the blueprint embedded in the binary.
+    fn blueprint(&self) -> &'static Blueprint {
+        match self {
+            Self::Rust => blueprints::blueprint("rust"),
+            Self::Golang => blueprints::blueprint("golang"),
+            Self::Js => blueprints::blueprint("js"),
+        }
+    }
 }
 
 impl Command for CreateFilterCommand {
     async fn execute(&self) -> Result<(), Error> {
+        validate_filter_name(&self.name)?;
         let repo_dir = match &self.directory {
             Some(d) => d.clone(),
             None => std::env::temp_dir().join(format!(
@@ -46,77 +65,55 @@ impl Command for CreateFilterCommand {
                 Local::now().timestamp()
             )),
         };
+        if fs::read_dir(&repo_dir).is_ok_and(|mut entries| entries.next().is_some()) {
+            return Err(Error::Error(format!(
+                "The directory '{}' is not empty",
+                repo_dir.display()
+            )));
+        }
 
-        let editor = default_editor(&None);
-
-        info!("Cloning the filter repository to '{}'", repo_dir.display());
-        let output = ProcessCommand::new("git")
-            .arg("clone")
-            .arg("git@github.com:MAIF/yozefu.git")
-            .arg("--depth")
-            .arg("1")
-            .arg(&repo_dir)
-            .spawn()?
-            .wait()?;
-
-        match output.success() {
-            true => {
-                self.prepare_git_repository(&repo_dir)?;
+        blueprints::render(self.language.blueprint(), &repo_dir, &self.name)?;
+        info!(
+            "The {} blueprint has been written to '{}'",
+            self.language,
+            repo_dir.display()
+        );
 
-                info!(
-                    "The filter repository has been initialized: '{}'",
+        let wasm_file = repo_dir.join("module.wasm");
+        if self.import {
+            info!("Building the wasm module");
+            let status = ProcessCommand::new("make")
+                .arg("-C")
+                .arg(&repo_dir)
+                .arg("build")
+                .spawn()?
+                .wait()?;
+            if !status.success() {
+                return Err(Error::Error(format!(
+                    "I was not able to build the wasm module. Run `make -C '{}' build` to see what went wrong.",
                     repo_dir.display()
-                );
-                info!(
-                    "You can now implement your wasm filter in the repository: '{}'",
-                    repo_dir.display()
-                );
-            }
-            false => {
-                warn!("I was not able to clone the repository. Please download it manually.");
-                println!("    mkdir -p '{}'", repo_dir.parent().unwrap().display());
-                println!(
-                    "    curl -L 'https://github.com/MAIF/yozefu/archive/refs/heads/main.zip'"
-                );
-                println!("    unzip yozefu-main.zip -d .");
-                println!("    mv 'yozefu-main' {}", repo_dir.display());
+                )));
             }
+            return ImportFilterCommand::new(wasm_file, &self.name, self.force)
+                .execute()
+                .await;
         }
 
+        let editor = default_editor(&None);
+        info!(
+            "You can now implement your wasm filter in the repository: '{}'",
+            repo_dir.display()
+        );
         println!("    {} '{}'", editor, repo_dir.display());
         println!("    make -C '{}' build", repo_dir.display());
         let binary = std::env::current_exe()?;
         println!(
             "    {} import-filter '{}' --name '{}'",
             binary.file_name().unwrap().to_str().unwrap(),
-            repo_dir.join("module.wasm").display(),
+            wasm_file.display(),
             self.name
         );
-        println!("    rm '{}'", repo_dir.display());
-        Ok(())
-    }
-}
-
-impl CreateFilterCommand {
-    /// Clones the [`wasm-blueprints`](https://github.com/MAIF/yozefu/tree/main/crates/wasm-blueprints) repository
-    /// and reorganizes directories to keep only the programming language selected by the user.
-    fn prepare_git_repository(&self, repo_dir: &Path) -> Result<(), Error> {
-        let source = repo_dir
-            .join("crates")
-            .join("wasm-blueprints")
-            .join(self.language.to_string());
-        let temp = repo_dir.parent().unwrap().join(
-            repo_dir
-                .file_name()
-                .map(|e| format!("{}-temp", e.to_str().unwrap()))
-                .unwrap(),
-        );
-
-        fs::rename(source, &temp)?;
-        fs::remove_dir_all(repo_dir)?;
-        fs::rename(&temp, repo_dir)?;
-        info!("Preparing the repository");
-
+        println!("    rm -r '{}'", repo_dir.display());
         Ok(())
     }
 }
@@ -0,0 +1,137 @@
+//! Commands to manage the installed search filters.
+//!
+//! ```bash
+//! yozf filters list
+//! yozf filters info key-ends-with
+//! yozf filters remove key-ends-with
+//! ```
+
+use std::fs;
+
+use app::{
+    configuration::GlobalConfig,
+    search::{
+        catalog::{FilterInfo, filter_path, installed_filters, validate_filter_name},
+        native::native_filters,
+    },
+};
+use clap::{Args, Subcommand};
+use lib::Error;
+use log::info;
+
+use crate::command::Command;
+
+/// Manage the installed search filters.
+#[derive(Debug, Clone, Args)]
+pub(crate) struct FiltersCommand {
+    #[command(subcommand)]
+    pub subcommand: FiltersSubCommand,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub(crate) enum FiltersSubCommand {
+    /// List the installed search filters
+    #[clap(alias = "ls")]
+    List,
+    /// Print the description, the parameters and the limits of a search filter
+    Info {
+        /// Name of the search filter
+        name: String,
+    },
+    /// Remove a search filter
+    #[clap(alias = "rm")]
+    Remove {
+        /// Name of the search filter
+        name: String,
+    },
+}
+
+impl Command for FiltersCommand {
+    async fn execute(&self) -> Result<(), Error> {
+        let config = GlobalConfig::read(&GlobalConfig::path()?)?;
+        let directory = config.filters_dir();
+        match &self.subcommand {
+            FiltersSubCommand::List => {
+                let names = installed_filters(&directory);
+                let native = native_filters();
+                if names.is_empty() && native.is_empty() {
+                    info!("No search filter in '{}'", directory.display());
+                }
+                let width = names
+                    .iter()
+                    .chain(&native)
+                    .map(|n| n.len())
+                    .max()
+                    .unwrap_or_default();
+                for name in names {
+                    let summary = match FilterInfo::load(&directory, &name) {
+                        Ok(FilterInfo {
+                            description: Some(d),
+                            ..
+                        }) => format!("{:<8} {}", d.version, d.description),
+                        Ok(_) => String::new(),
+                        Err(e) => format!("invalid: {}", e),
+                    };
+                    println!("{:<width$}  {}", name, summary.trim_end());
+                }
+                for name in native {
+                    println!("{:<width$}  native", name);
+                }
+            }
+            FiltersSubCommand::Info { name } => {
+                let filter = FilterInfo::load(&directory, name)?;
+                let limits = config.filter_limits.of(name);
+                println!("{}", filter.signature());
+                if let Some(description) = &filter.description {
+                    if !description.description.is_empty() {
+                        println!("  {}", description.description);
+                    }
+                    println!();
+                    if !description.version.is_empty() {
+                        println!("Version     {}", description.version);
+                    }
+                    for parameter in &description.parameters {
+                        println!(
+                            "Parameter   {:<12} {:<8} {}",
+                            parameter.name, parameter.r#type, parameter.description
+                        );
+                    }
+                } else {
+                    println!();
+                }
+                println!("Path        {}", filter.path.display());
+                println!("Size        {} bytes", filter.size);
+                println!("Exports     {}", filter.exports.join(", "));
+                println!("Stateful    {}", filter.stateful);
+                println!(
+                    "Timeout     {}",
+                    limits
+                        .timeout_ms
+                        .map(|t| format!("{} ms", t))
+                        .unwrap_or("none".to_string())
+                );
+                println!(
+                    "Memory      {}",
+                    limits
+                        .memory_mb
+                        .map(|m| format!("{} MB", m))
+                        .unwrap_or("none".to_string())
+                );
+            }
+            FiltersSubCommand::Remove { name } => {
+                validate_filter_name(name)?;
+                let path = filter_path(&directory, name);
+                if fs::metadata(&path).is_err() {
+                    return Err(Error::Error(format!(
+                        "There is no search filter '{}' in '{}'",
+                        name,
+                        directory.display()
+                    )));
+                }
+                fs::remove_file(&path)?;
+                info!("'{}' has been removed", path.display());
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+//! Command that prints a search query in its canonical form.
+//!
+//! ```bash
+//! yozf format-query 'from begin where k contains "foo" limit 10'
+//! yozf format-query --json 'value.status == "open"' | jq '.expression'
+//! ```
+
+use clap::Args;
+use lib::{Error, parse_search_query};
+
+use crate::command::Command;
+
+/// Print a search query in its canonical form.
+/// The canonical form is parsed back to the same query.
+#[derive(Debug, Clone, Args)]
+pub(crate) struct FormatQueryCommand {
+    /// Print the abstract syntax tree of the query as JSON
+    #[clap(long)]
+    json: bool,
+    /// The search query
+    #[clap(trailing_var_arg = true, allow_hyphen_values = true)]
+    query: Vec<String>,
+}
+
+impl Command for FormatQueryCommand {
+    async fn execute(&self) -> Result<(), Error> {
+        let input = self.query.join(" ");
+        let (_, query) = parse_search_query(&input).map_err(Error::Search)?;
+        match self.json {
+            true => println!("{}", serde_json::to_string_pretty(&query)?),
+            false => println!("{}", query),
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_format_query_out_of_range() {
+    for query in [
+        "timestamp > now - 99999999999999999w",
+        r#"timestamp > "2024-01-01T00:00:00Z" + 100000000w"#,
+        "from now - 100000000w",
+    ] {
+        let command = FormatQueryCommand {
+            json: false,
+            query: vec![query.to_string()],
+        };
+        let result = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(command.execute());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("the date is out of range")
+        );
+    }
+}
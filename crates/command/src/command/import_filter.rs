@@ -3,10 +3,13 @@ use std::{fs, path::PathBuf};
 
 use app::{
     configuration::GlobalConfig,
-    search::filter::{MATCHES_FUNCTION_NAME, PARSE_PARAMETERS_FUNCTION_NAME},
+    search::{
+        filter::{MATCHES_FUNCTION_NAME, PARSE_PARAMETERS_FUNCTION_NAME},
+        kv_store::{KvStore, host_functions},
+    },
 };
 use clap::Args;
-use extism::{Manifest, Plugin, Wasm};
+use extism::{Manifest, Plugin, UserData, Wasm};
 use lib::Error;
 use log::info;
 
@@ -51,6 +54,14 @@ impl Command for ImportFilterCommand {
 }
 
 impl ImportFilterCommand {
+    pub fn new(file: PathBuf, name: &str, force: bool) -> Self {
+        Self {
+            file,
+            filter_name: Some(name.to_string()),
+            force,
+        }
+    }
+
     /// Returns the path to the wasm file.
     pub fn destination(&self) -> Result<PathBuf, Error> {
         let name = self.name();
@@ -73,8 +84,9 @@ impl ImportFilterCommand {
     fn check_wasm_module(&self, wasm_file: &PathBuf) -> Result<(), Error> {
         let url = Wasm::file(wasm_file);
         let manifest = Manifest::new([url]);
-        let mut filter =
-            Plugin::new(manifest, [], true).map_err(|e| Error::Error(e.to_string()))?;
+        let store = UserData::new(KvStore::new());
+        let mut filter = Plugin::new(manifest, host_functions(&store), true)
+            .map_err(|e| Error::Error(e.to_string()))?;
         check_presence_of_functions(&mut filter)?;
         Ok(())
     }
@@ -13,13 +13,14 @@ use app::configuration::{
     ClusterConfig, Configuration, GlobalConfig, InternalConfig, YozefuConfig,
 };
 use app::search::ValidSearchQuery;
+use app::search::filter::{FilterErrorPolicy, set_filter_error_policy, set_filter_limits};
 
 use app::App;
 use clap::error::ErrorKind;
 use clap::{CommandFactory, Parser};
 use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
 use itertools::Itertools;
-use lib::Error;
+use lib::{Error, parse_search_query};
 use log::{debug, info, warn};
 use rdkafka::consumer::BaseConsumer;
 use strum::{Display, EnumString};
@@ -43,6 +44,15 @@ where
         .map_err(|e: <T as FromStr>::Err| Error::Error(e.to_string()))
 }
 
+fn parse_filter_error_policy(s: &str) -> Result<FilterErrorPolicy, Error> {
+    s.parse().map_err(|_| {
+        Error::Error(format!(
+            "Unknown policy '{}', expected 'match', 'no-match' or 'abort'",
+            s
+        ))
+    })
+}
+
 #[derive(Parser, Clone)]
 #[command(author, version, about, long_about = None, propagate_version = true)]
 pub struct MainCommand<T>
@@ -92,6 +102,9 @@ where
     #[clap(long)]
     /// Use a specific config file
     pub config: Option<PathBuf>,
+    #[clap(long, value_parser = parse_filter_error_policy)]
+    /// What a search filter returns when it fails: 'match', 'no-match' or 'abort'. Overrides the 'filter_error_policy' of the configuration
+    pub on_filter_error: Option<FilterErrorPolicy>,
     #[clap(skip)]
     pub(crate) logs_file: Option<PathBuf>,
 }
@@ -162,19 +175,7 @@ where
             return Ok(buffer);
         }
 
-        match q.starts_with("@") {
-            true => {
-                let query_file = Path::new(&q[1..]);
-                fs::read_to_string(query_file).map_err(|e| {
-                    Error::Error(format!(
-                        "Cannot read search query from file {:?}: {}",
-                        query_file.display(),
-                        e
-                    ))
-                })
-            }
-            false => Ok(q),
-        }
+        config.resolve_query(&q)
     }
 
     fn config(&self, yozefu_config: &YozefuConfig) -> Result<GlobalConfig, Error> {
@@ -299,6 +300,8 @@ where
     fn app(&self, query: &str, yozefu_config: &YozefuConfig) -> Result<App, Error> {
         debug!("{:?}", yozefu_config);
         let config = self.config(yozefu_config)?;
+        set_filter_error_policy(self.on_filter_error.unwrap_or(config.filter_error_policy));
+        set_filter_limits(config.filter_limits.clone());
         let search_query = ValidSearchQuery::from(query, &config.filters_dir())?;
 
         let internal_config = InternalConfig::new(yozefu_config.clone(), config);
@@ -344,8 +347,13 @@ where
         Ok(())
     }
 
+    /// Prints a command that can be run again as-is.
+    /// The search query is printed in its canonical form.
     fn print_full_command(&self, cluster: &str, topics: &[String], query: &str) {
         if self.topics.is_empty() {
+            let query = parse_search_query(query)
+                .map(|(_, q)| q.to_string())
+                .unwrap_or(query.to_string());
             let binary = std::env::current_exe()
                 .map(|f| f.file_name().unwrap().to_str().unwrap().to_string())
                 .unwrap_or(APPLICATION_NAME.to_string());
@@ -354,7 +362,7 @@ where
                 binary,
                 cluster,
                 topics.join(","),
-                query
+                query.replace('\'', r"'\''")
             )
         }
     }
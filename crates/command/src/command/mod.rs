@@ -3,16 +3,23 @@ use std::env::var;
 
 use lib::Error;
 
+mod blueprints;
 mod config_command;
 pub mod configure;
 mod create_filter;
+mod filters_command;
+mod format_query;
 mod import_filter;
 mod main_command;
+mod test_filter;
 mod utility_commands;
 
 pub(crate) use create_filter::CreateFilterCommand;
+pub(crate) use filters_command::FiltersCommand;
+pub(crate) use format_query::FormatQueryCommand;
 pub(crate) use import_filter::ImportFilterCommand;
 pub use main_command::MainCommand;
+pub(crate) use test_filter::TestFilterCommand;
 pub use utility_commands::UtilityCommands;
 
 #[cfg(target_family = "windows")]
@@ -0,0 +1,264 @@
+//! Command to test a search filter against recorded kafka records.
+//!
+//! ```bash
+//! yozf test-filter module.wasm --params '1234' --input records.json
+//! yozf test-filter module.wasm --params '1234' --input records.ndjson --expect expected.json
+//! ```
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use app::{
+    configuration::GlobalConfig,
+    search::{
+        filter::{FilterLimits, MATCHES_FUNCTION_NAME, end_search, start_search},
+        plugin_pool::PluginPool,
+    },
+};
+use clap::Args;
+use extism::{Manifest, Wasm, convert::Json};
+use itertools::Itertools;
+use lib::{Error, FilterResult, KafkaRecord, search::filter::FilterInput};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::command::Command;
+
+/// Test a search filter against kafka records.
+/// The records come from an export file or a file containing a JSON record per line.
+#[derive(Debug, Clone, Args)]
+pub(crate) struct TestFilterCommand {
+    /// The wasm module of the search filter
+    file: PathBuf,
+    /// A parameter passed to the search filter, parsed as JSON or taken as a string
+    #[clap(long = "params", num_args = 1..)]
+    parameters: Vec<String>,
+    /// Records to test, an export file or one JSON record per line
+    #[clap(long)]
+    input: PathBuf,
+    /// Expected outcome of each record, in order: a JSON array of `true`, `false` or `"error"`
+    #[clap(long)]
+    expect: Option<PathBuf>,
+    /// Print the outcome of each record
+    #[clap(short, long)]
+    verbose: bool,
+}
+
+/// What the search filter returned for a record.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+enum Outcome {
+    Match(bool),
+    Error(ErrorOutcome),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ErrorOutcome {
+    Error,
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Match(true) => write!(f, "match"),
+            Outcome::Match(false) => write!(f, "no match"),
+            Outcome::Error(_) => write!(f, "error"),
+        }
+    }
+}
+
+impl Command for TestFilterCommand {
+    async fn execute(&self) -> Result<(), Error> {
+        let name = self
+            .file
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default();
+        let limits = GlobalConfig::path()
+            .and_then(|path| GlobalConfig::read(&path))
+            .map(|config| config.filter_limits)
+            .unwrap_or_default();
+        let pool = load(&self.file, name, &limits, &self.parameters())?;
+        let records = read_records(&self.input)?;
+
+        // The records are tested as one search
+        start_search(name, &pool);
+        let mut outcomes = vec![];
+        let mut latencies = vec![];
+        for record in records {
+            let input = FilterInput {
+                record,
+                params: self.parameters(),
+            };
+            let start = Instant::now();
+            let result = pool.call::<String, Json<FilterResult>>(
+                MATCHES_FUNCTION_NAME,
+                serde_json::to_string(&input)?,
+            );
+            let latency = start.elapsed();
+            latencies.push(latency);
+            let outcome = match result {
+                Ok(Json(result)) => Outcome::Match(result.r#match),
+                Err(e) => {
+                    eprintln!("{}: {}", id(&input.record), e);
+                    Outcome::Error(ErrorOutcome::Error)
+                }
+            };
+            if self.verbose {
+                println!("{:<40} {:<9} {:?}", id(&input.record), outcome, latency);
+            }
+            outcomes.push(outcome);
+        }
+        if let Some(report) = end_search(name, &pool) {
+            println!("{}", report);
+        }
+
+        let count = |o: &Outcome| outcomes.iter().filter(|e| *e == o).count();
+        println!(
+            "{} records, {} matches, {} no matches, {} errors",
+            outcomes.len(),
+            count(&Outcome::Match(true)),
+            count(&Outcome::Match(false)),
+            count(&Outcome::Error(ErrorOutcome::Error))
+        );
+        if let Some(summary) = latency_summary(&mut latencies) {
+            println!("Latency per call: {}", summary);
+        }
+
+        if let Some(expect) = &self.expect {
+            let expected: Vec<Outcome> = serde_json::from_str(&fs::read_to_string(expect)?)?;
+            let mismatches = compare(&expected, &outcomes)?;
+            if !mismatches.is_empty() {
+                return Err(Error::Error(format!(
+                    "{} records don't have the expected outcome:\n{}",
+                    mismatches.len(),
+                    mismatches.iter().take(20).join("\n")
+                )));
+            }
+            println!("All the records have the expected outcome");
+        }
+        Ok(())
+    }
+}
+
+impl TestFilterCommand {
+    /// Returns the parameters, parsed as JSON or taken as strings.
+    fn parameters(&self) -> Vec<Value> {
+        self.parameters
+            .iter()
+            .map(|p| serde_json::from_str(p).unwrap_or(Value::String(p.clone())))
+            .collect()
+    }
+}
+
+/// Loads the wasm module and passes the parameters to `parse_parameters`, like a search query does.
+fn load(
+    file: &Path,
+    name: &str,
+    limits: &FilterLimits,
+    parameters: &[Value],
+) -> Result<PluginPool, Error> {
+    let pool = PluginPool::new(Manifest::new([Wasm::file(file)]), limits.of(name))
+        .map_err(|e| Error::Error(format!("{}: {}", file.display(), e)))?;
+    pool.parse_parameters(&serde_json::to_string(parameters)?)
+        .map_err(|e| Error::Error(format!("The parameters are invalid: {}", e)))?;
+    Ok(pool)
+}
+
+/// Reads an export file, a JSON array of records, or a file containing a JSON record per line.
+fn read_records(path: &Path) -> Result<Vec<KafkaRecord>, Error> {
+    let content = fs::read_to_string(path)?;
+    let records: Vec<KafkaRecord> = match content.trim_start().starts_with('[') {
+        true => serde_json::from_str(&content)?,
+        false => content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .try_collect()?,
+    };
+    Ok(records
+        .into_iter()
+        .map(|record| KafkaRecord {
+            key_as_string: record.key.to_string(),
+            value_as_string: record.value.to_string(),
+            ..record
+        })
+        .collect())
+}
+
+fn id(record: &KafkaRecord) -> String {
+    format!("{}-{}@{}", record.topic, record.partition, record.offset)
+}
+
+/// Returns the records whose outcome is not the expected one.
+fn compare(expected: &[Outcome], outcomes: &[Outcome]) -> Result<Vec<String>, Error> {
+    if expected.len() != outcomes.len() {
+        return Err(Error::Error(format!(
+            "{} outcomes are expected but {} records were tested",
+            expected.len(),
+            outcomes.len()
+        )));
+    }
+    Ok(expected
+        .iter()
+        .zip(outcomes)
+        .enumerate()
+        .filter(|(_, (e, o))| e != o)
+        .map(|(i, (e, o))| format!("  record #{}: expected {}, got {}", i + 1, e, o))
+        .collect())
+}
+
+/// Returns the mean, the median, the 99th percentile and the maximum latency.
+fn latency_summary(latencies: &mut [Duration]) -> Option<String> {
+    if latencies.is_empty() {
+        return None;
+    }
+    latencies.sort();
+    let percentile = |p: usize| latencies[(latencies.len() * p / 100).min(latencies.len() - 1)];
+    let mean = latencies.iter().sum::<Duration>() / latencies.len() as u32;
+    Some(format!(
+        "mean {:?}, p50 {:?}, p99 {:?}, max {:?}",
+        mean,
+        percentile(50),
+        percentile(99),
+        latencies[latencies.len() - 1]
+    ))
+}
+
+#[test]
+fn test_compare() {
+    let expected: Vec<Outcome> = serde_json::from_str(r#"[true, false, "error"]"#).unwrap();
+    let outcomes = vec![
+        Outcome::Match(true),
+        Outcome::Match(true),
+        Outcome::Error(ErrorOutcome::Error),
+    ];
+    assert_eq!(
+        compare(&expected, &outcomes).unwrap(),
+        vec!["  record #2: expected no match, got match"]
+    );
+    assert!(compare(&expected, &outcomes[..1]).is_err());
+}
+
+#[test]
+fn test_read_records() {
+    let directory = tempfile::tempdir().unwrap();
+    let record = serde_json::json!({"topic": "orders", "timestamp": null, "partition": 0, "offset": 1, "headers": {}, "key": "a", "value": {"id": 1}});
+    let ndjson = directory.path().join("records.ndjson");
+    fs::write(&ndjson, format!("{}\n\n{}\n", record, record)).unwrap();
+    let records = read_records(&ndjson).unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].value_as_string, r#"{"id":1}"#);
+
+    let mut exported = record.clone();
+    exported["absolute_delta_in_ms"] = 0.into();
+    exported["relative_delta_in_ms"] = 0.into();
+    exported["search_query"] = "from begin".into();
+    let export = directory.path().join("export.json");
+    fs::write(&export, serde_json::json!([exported]).to_string()).unwrap();
+    assert_eq!(read_records(&export).unwrap().len(), 1);
+}
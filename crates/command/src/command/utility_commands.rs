@@ -7,8 +7,8 @@ use strum::{Display, EnumString};
 use crate::log::init_logging_stderr;
 
 use super::{
-    Command, CreateFilterCommand, ImportFilterCommand, config_command::ConfigCommand,
-    configure::ConfigureCommand,
+    Command, CreateFilterCommand, FiltersCommand, FormatQueryCommand, ImportFilterCommand,
+    TestFilterCommand, config_command::ConfigCommand, configure::ConfigureCommand,
 };
 
 #[derive(Subcommand, Debug)]
@@ -19,12 +19,19 @@ pub enum UtilityCommands {
     /// Helper to create a new WebAssembly search filter
     #[clap(alias = "new-filter")]
     CreateFilter(CreateFilterCommand),
+    /// List, describe or remove the installed search filters
+    Filters(FiltersCommand),
+    /// Test a search filter against kafka records from a file
+    TestFilter(TestFilterCommand),
     /// Edit the configuration file
     Configure(ConfigureCommand),
     /// Print the config to `stdout`
     Config(ConfigCommand),
+    /// Print a search query in its canonical form, or its syntax tree as JSON
+    FormatQuery(FormatQueryCommand),
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone, EnumString, Display)]
 #[strum(serialize_all = "lowercase")]
 pub enum KafkaFormatterOption {
@@ -42,8 +49,11 @@ impl Command for UtilityCommands {
         match self {
             Self::ImportFilter(command) => command.execute().await,
             Self::CreateFilter(command) => command.execute().await,
+            Self::Filters(command) => command.execute().await,
+            Self::TestFilter(command) => command.execute().await,
             Self::Configure(command) => command.execute().await,
             Self::Config(command) => command.execute().await,
+            Self::FormatQuery(command) => command.execute().await,
         }
     }
 }
@@ -18,6 +18,7 @@
 //!    }
 //! }
 //! ```
+use app::search::aggregation::Aggregator;
 use lib::KafkaRecord;
 
 use super::KafkaFormatter;
@@ -41,4 +42,8 @@ impl KafkaFormatter for JsonFormatter {
     fn fmt(&self, record: &KafkaRecord) -> String {
         serde_json::to_string_pretty(&record).unwrap_or("".to_string())
     }
+
+    fn fmt_aggregation(&self, aggregator: &Aggregator) -> String {
+        serde_json::to_string_pretty(&aggregator.to_json()).unwrap_or("".to_string())
+    }
 }
@@ -10,9 +10,15 @@ pub use plain_formatter::PlainFormatter;
 pub use simple_formatter::SimpleFormatter;
 pub use transpose_formatter::TransposeFormatter;
 
+use app::search::aggregation::Aggregator;
 use lib::KafkaRecord;
 
 /// A kafka formatter displays a kafka record to stdout.
 pub trait KafkaFormatter: Sync + Send {
     fn fmt(&self, record: &KafkaRecord) -> String;
+
+    /// Formats the result of a `group by` clause. Defaults to CSV.
+    fn fmt_aggregation(&self, aggregator: &Aggregator) -> String {
+        aggregator.to_csv()
+    }
 }
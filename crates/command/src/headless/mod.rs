@@ -1,8 +1,13 @@
 //! Module gathering code for the headless mode.
 
 use app::App;
-use app::search::Search;
-use app::search::SearchContext;
+use app::search::aggregation::Aggregator;
+use app::search::evaluation::{Evaluation, Evaluator};
+use app::search::filter::{
+    aborted_by_filter, filter_error_policy, filter_errors, reset_filter_errors,
+};
+use app::search::latest_by_key::LatestByKey;
+use app::search::order::compare_records;
 use rdkafka::Message;
 use rdkafka::message::OwnedMessage;
 use std::time::Duration;
@@ -14,7 +19,8 @@ use futures::{StreamExt, TryStreamExt};
 use indicatif::ProgressBar;
 use lib::Error;
 use lib::KafkaRecord;
-use log::info;
+use lib::SearchQuery;
+use log::{info, warn};
 use rdkafka::consumer::Consumer;
 use tokio_util::sync::CancellationToken;
 
@@ -50,16 +56,28 @@ impl Headless {
         if self.topics.is_empty() {
             return Err("Please specify topics to consume".into());
         }
+        check_order_by(self.app.search_query.query())?;
         info!("Creating consumer for topics [{}]", self.topics.join(", "));
+        reset_filter_errors();
+        self.app.search_query.start_search();
         let consumer = self.app.create_consumer(&self.topics)?;
-        let mut records_channel = tokio::sync::mpsc::unbounded_channel::<KafkaRecord>();
+        let mut sampler = self.app.create_sampler(&consumer)?;
+        // Records that don't match are only sent to update the compacted view of `distinct key`.
+        let mut records_channel = tokio::sync::mpsc::unbounded_channel::<(KafkaRecord, bool)>();
         let search_query = self.app.search_query.clone();
+        let search_query_group_by = search_query.query().group_by.clone();
+        let distinct_key = search_query.query().distinct_key;
+        let order_by = search_query.query().order_by.clone();
         let token = CancellationToken::new();
+        let token_ctrl_c = token.clone();
         let progress = self.progress.clone();
         progress.enable_steady_tick(Duration::from_secs(10));
-        let count = self
-            .app
-            .estimate_number_of_records_to_read(consumer.assignment()?)?;
+        let count = match &sampler {
+            Some(sampler) => sampler.len() as i64,
+            None => self
+                .app
+                .estimate_number_of_records_to_read(consumer.assignment()?)?,
+        };
         progress.set_length(count as u64);
 
         let (tx_dd, mut rx_dd) = mpsc::unbounded_channel::<OwnedMessage>();
@@ -67,37 +85,89 @@ impl Headless {
         let token_cloned = token.clone();
 
         let filters_directory = self.app.config.global.filters_dir();
+        let evaluator = Evaluator::new(&search_query, &filters_directory);
+        // When the query uses search filters, several records are evaluated at the same time, the results are read in order.
+        let (tx_evaluations, mut rx_evaluations) =
+            mpsc::channel::<Evaluation>(evaluator.concurrency());
         tokio::spawn(async move {
             loop {
-                let mut limit = 0;
                 select! {
                     _ = token_cloned.cancelled() => {
                         info!("Consumer is about to be cancelled");
                         return;
                      },
-                    Some(message) = rx_dd.recv() => {
-                        let record = KafkaRecord::parse(message, &mut schema_registry).await;
-                        let context = SearchContext::new(&record, &filters_directory);
-                        if search_query.matches(&context) {
-                            records_channel.0.send(record).unwrap();
-                            limit += 1;
+                    message = rx_dd.recv() => {
+                        // The consumer stopped, every record it read has been processed
+                        let Some(message) = message else {
+                            return;
+                        };
+                        let prefilter = search_query.matches_message(&message);
+                        if prefilter == Some(false) && !distinct_key {
+                            continue;
                         }
-                        if let Some(query_limit) = search_query.limit() {
-                            if limit >= query_limit {
-                                token_cloned.cancel();
-                            }
+                        let record = KafkaRecord::parse(message, &mut schema_registry).await;
+                        if tx_evaluations.send(evaluator.evaluate(record, prefilter)).await.is_err() {
+                            return;
                         }
                     }
                 }
             }
         });
 
+        let token_cloned = token.clone();
+        let search_query = self.app.search_query.clone();
+        tokio::spawn(async move {
+            let mut limit = Limit::new(search_query.limit());
+            while let Some(evaluation) = rx_evaluations.recv().await {
+                if token_cloned.is_cancelled() {
+                    break;
+                }
+                let (record, matches) = evaluation.result().await;
+                if matches {
+                    records_channel.0.send((record, true)).unwrap();
+                    limit.count();
+                } else if distinct_key {
+                    records_channel.0.send((record, false)).unwrap();
+                }
+                if aborted_by_filter().is_some() {
+                    token_cloned.cancel();
+                    break;
+                }
+                if limit.reached() {
+                    token_cloned.cancel();
+                    break;
+                }
+            }
+            for report in search_query.end_search() {
+                info!("{}", report);
+            }
+        });
+
         tokio::spawn(async move {
             let mut current_time = Instant::now();
+            // Reading the last position of every partition stops the consumer, not the processing of the records read so far
+            let sampling = token.child_token();
+            if sampler.as_ref().is_some_and(|s| s.is_empty()) {
+                sampling.cancel();
+            }
             let task = consumer
                 .stream()
-                .take_until(token.cancelled())
+                .take_until(sampling.cancelled())
                 .try_for_each(|message| {
+                    if let Some(sampler) = sampler.as_mut() {
+                        let sampled = sampler.sample(
+                            &consumer,
+                            message.topic(),
+                            message.partition(),
+                            message.offset(),
+                        );
+                        if sampler.is_empty() {
+                            sampling.cancel();
+                        }
+                        if !sampled {
+                            return futures::future::ok(());
+                        }
+                    }
                     let message = message.detach();
                     let timestamp = message.timestamp().to_millis().unwrap_or_default();
                     tx_dd.send(message).unwrap();
@@ -114,12 +184,134 @@ impl Headless {
             task
         });
 
-        while let Some(record) = records_channel.1.recv().await {
-            println!("{}", self.formatter.fmt(&record));
+        let mut aggregator = search_query_group_by.map(Aggregator::new);
+        let mut latest_by_key = distinct_key.then(LatestByKey::new);
+        // With an `order by` clause, records are buffered and printed once sorted.
+        let mut sorted_records = (!order_by.is_empty() && aggregator.is_none()).then(Vec::new);
+        if aggregator.is_some() || latest_by_key.is_some() || sorted_records.is_some() {
+            // The aggregation, the compacted view and sorted records are printed once the consumer stops, Ctrl+C stops it.
+            tokio::spawn(async move {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    info!("Consumer is about to be cancelled");
+                    token_ctrl_c.cancel();
+                }
+            });
+        }
+
+        while let Some((record, matches)) = records_channel.1.recv().await {
+            if let Some(latest_by_key) = latest_by_key.as_mut() {
+                latest_by_key.push(record, matches);
+                continue;
+            }
+            if let Some(aggregator) = aggregator.as_mut().filter(|_| matches) {
+                aggregator.push(&record);
+            }
+            if let Some(sorted_records) = sorted_records.as_mut() {
+                sorted_records.push(record);
+                continue;
+            }
+            if aggregator.is_none() {
+                println!("{}", self.formatter.fmt(&record));
+            }
             if self.export_records {
                 self.app.export_record(&record)?;
             }
         }
+        let mut buffered_records = match latest_by_key {
+            Some(latest_by_key) => {
+                info!("{} distinct keys", latest_by_key.len());
+                latest_by_key.into_records().collect()
+            }
+            None => sorted_records.unwrap_or_default(),
+        };
+        buffered_records.sort_by(|a, b| compare_records(&order_by, a, b));
+        // With `distinct key`, only the latest record of each key is aggregated
+        if let Some(aggregator) = aggregator.as_mut().filter(|_| distinct_key) {
+            for record in &buffered_records {
+                aggregator.push(record);
+            }
+        }
+        for record in &buffered_records {
+            if aggregator.is_none() {
+                println!("{}", self.formatter.fmt(record));
+            }
+            if self.export_records {
+                self.app.export_record(record)?;
+            }
+        }
+        if let Some(aggregator) = aggregator {
+            println!("{}", self.formatter.fmt_aggregation(&aggregator));
+        }
+        for (filter, count) in filter_errors() {
+            warn!(
+                "Search filter '{}' failed on {} records, policy '{}'",
+                filter,
+                count,
+                filter_error_policy()
+            );
+        }
+        if let Some(reason) = aborted_by_filter() {
+            return Err(Error::Error(format!("Search aborted. {}", reason)));
+        }
         Ok(())
     }
 }
+
+/// Sorted records are buffered until the consumer stops, a `limit` clause bounds the buffer.
+/// `group by` and `distinct key` only buffer one entry per group or key.
+fn check_order_by(query: &SearchQuery) -> Result<(), Error> {
+    if query.order_by.is_empty()
+        || query.limit.is_some()
+        || query.group_by.is_some()
+        || query.distinct_key
+    {
+        return Ok(());
+    }
+    Err("In headless mode, 'order by' buffers the records until the consumer stops, please add a 'limit' clause".into())
+}
+
+/// Counts the matching records until the `limit` clause is reached.
+/// The count covers the whole consumption, not a single record.
+struct Limit {
+    matched: usize,
+    limit: Option<usize>,
+}
+
+impl Limit {
+    fn new(limit: Option<usize>) -> Self {
+        Self { matched: 0, limit }
+    }
+
+    fn count(&mut self) {
+        self.matched += 1;
+    }
+
+    fn reached(&self) -> bool {
+        self.limit.is_some_and(|limit| self.matched >= limit)
+    }
+}
+
+#[test]
+fn test_limit() {
+    let mut limit = Limit::new(Some(3));
+    for _ in 0..2 {
+        limit.count();
+        assert!(!limit.reached());
+    }
+    limit.count();
+    assert!(limit.reached());
+
+    let mut unlimited = Limit::new(None);
+    unlimited.count();
+    assert!(!unlimited.reached());
+    assert!(Limit::new(Some(0)).reached());
+}
+
+#[test]
+fn test_check_order_by() {
+    let check = |input: &str| check_order_by(&lib::parse_search_query(input).unwrap().1);
+    assert!(check("offset > 10").is_ok());
+    assert!(check("offset > 10 order by timestamp desc limit 100").is_ok());
+    assert!(check("distinct key order by key").is_ok());
+    assert!(check("offset > 10 order by timestamp desc").is_err());
+}
@@ -16,6 +16,15 @@ use lib::Error;
 pub use tui::TuiError;
 
 pub use app::APPLICATION_NAME;
+pub use app::search::native::NativeFilter;
+use app::search::native::register_native_filter;
+
+/// Registers a search filter implemented in Rust.
+/// It is called with the same `name(args)` syntax as a wasm search filter and takes precedence over a wasm module with the same name.
+/// The registry is global: the search filter is available to every search query of the process, until it is replaced.
+pub fn register_filter(name: &str, filter: impl NativeFilter + 'static) {
+    register_native_filter(name, filter);
+}
 
 pub fn read_config() -> Result<GlobalConfig, Error> {
     GlobalConfig::read(&GlobalConfig::path()?)
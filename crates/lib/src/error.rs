@@ -1,4 +1,5 @@
 //! Error definitions and their implementations of the `From` trait.
+use crate::search::diagnostic::Diagnostic;
 use rdkafka::error::KafkaError;
 use std::fmt::Display;
 use std::str::Utf8Error;
@@ -22,7 +23,18 @@ pub enum Error {
 
 #[derive(Debug)]
 pub enum SearchError {
-    Parse(String),
+    /// The search query is not valid.
+    Parse(Diagnostic),
+    /// A search filter of the query cannot be loaded.
+    Filter(Diagnostic),
+}
+
+impl SearchError {
+    pub fn diagnostic(&self) -> &Diagnostic {
+        match self {
+            SearchError::Parse(d) | SearchError::Filter(d) => d,
+        }
+    }
 }
 
 impl std::error::Error for Error {}
@@ -45,7 +57,8 @@ impl Display for Error {
 impl Display for SearchError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            SearchError::Parse(e) => write!(f, "Cannot parse the search query at '{}'", e),
+            SearchError::Parse(d) => write!(f, "Cannot parse the search query:\n{}", d),
+            SearchError::Filter(d) => write!(f, "Cannot load the search filter:\n{}", d),
         }
     }
 }
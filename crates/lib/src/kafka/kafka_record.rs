@@ -32,6 +32,9 @@ pub struct KafkaRecord {
     #[serde(skip_serializing, default)]
     /// The value as a string. needed to be displayed in the TUI
     pub value_as_string: String,
+    /// `true` when the payload is null: the record deletes the key on a compacted topic.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub tombstone: bool,
 }
 
 #[cfg(feature = "native")]
@@ -89,6 +92,7 @@ impl KafkaRecord {
             }
         }
 
+        let tombstone = owned_message.payload().is_none();
         let size = owned_message.payload().map(|e| e.len()).unwrap_or(0)
             + owned_message.key().map(|e| e.len()).unwrap_or(0);
 
@@ -110,6 +114,7 @@ impl KafkaRecord {
             key_schema,
             value_schema,
             size,
+            tombstone,
         }
     }
 
@@ -7,9 +7,10 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, Deserialize, Serialize, Hash, PartialEq, Eq, Default)]
 pub struct SchemaId(pub u32);
 
-#[derive(Clone, Debug, Deserialize, Serialize, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Deserialize, Serialize, Hash, PartialEq, Eq, Default)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum SchemaType {
+    #[default]
     Json,
     Avro,
     Protobuf,
@@ -52,12 +53,6 @@ impl SchemaId {
     }
 }
 
-impl Default for SchemaType {
-    fn default() -> Self {
-        Self::Json
-    }
-}
-
 #[cfg(feature = "native")]
 const MAGIC_BYTE: u8 = 0;
 
@@ -15,5 +15,8 @@ pub mod search;
 pub use kafka::Comparable;
 pub use kafka::DataType;
 pub use kafka::KafkaRecord;
+pub use search::FilterDescription;
 pub use search::FilterResult;
+pub use search::ParameterDescription;
+pub use search::TransformResult;
 pub use search::compare::StringOperator;
@@ -1,4 +1,5 @@
 //! Atoms are the smallest unit of an expression. They can be a symbol, a comparison, a filter or a parenthesized expression.
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
 use nom::{
@@ -13,7 +14,7 @@ use super::{
     wsi::wsi,
 };
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Atom {
     Symbol(Symbol),
     Compare(CompareExpression),
@@ -39,7 +40,7 @@ impl Display for Atom {
         match self {
             Atom::Symbol(a) => write!(f, "{}", a),
             Atom::Compare(a) => write!(f, "{}", a),
-            Atom::Parenthesis(a) => write!(f, "{}", a),
+            Atom::Parenthesis(a) => write!(f, "({})", a),
             Atom::Filter(a) => write!(f, "{}", a),
         }
     }
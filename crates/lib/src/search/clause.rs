@@ -4,14 +4,16 @@ use nom::bytes::complete::tag_no_case;
 use nom::{
     IResult,
     branch::alt,
-    combinator::{map, opt},
+    combinator::{map, opt, value},
     sequence::{pair, preceded},
 };
 
 use super::expression::{Expression, parse_or_expression};
+use super::group_by::{GroupBy, parse_group_by};
 use super::number::parse_unsigned_number;
 use super::offset::{FromOffset, parse_from_offset};
-use super::order::{Order, OrderKeyword, parse_order, parse_order_keyword};
+use super::order::{OrderBy, parse_sort_keys};
+use super::sample::{Sample, parse_sample};
 use super::wsi::wsi;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -22,8 +24,14 @@ pub(crate) enum SearchClause {
     Expression(Expression),
     /// Clause for telling the consumer where to start consuming from
     From(FromOffset),
-    /// Clause defining how to sort the kafka records in the UI
-    OrderBy(Order, Option<OrderKeyword>),
+    /// Clause defining how to sort the kafka records
+    OrderBy(OrderBy),
+    /// Clause aggregating the kafka records
+    GroupBy(GroupBy),
+    /// Clause keeping only the latest record of each key
+    DistinctKey,
+    /// Clause keeping a representative slice of the records
+    Sample(Sample),
 }
 
 pub(crate) fn parse_expression(input: &str) -> IResult<&str, SearchClause> {
@@ -34,22 +42,37 @@ pub(crate) fn parse_expression(input: &str) -> IResult<&str, SearchClause> {
     .parse(input)
 }
 
-//pub(crate) fn parse_group_by_key(input: &str) -> IResult<&str, SearchClause> {
-//    value(
-//        SearchClause::GroupByKey,
-//        (
-//            wsi(tag_no_case("group")),
-//            wsi(tag_no_case("by")),
-//            wsi(tag_no_case("key")),
-//        ),
-//    )
-//    .parse(input)
-//}
+pub(crate) fn parse_group_by_clause(input: &str) -> IResult<&str, SearchClause> {
+    map(parse_group_by, SearchClause::GroupBy).parse(input)
+}
+
+/// `distinct key` or `latest by key`
+pub(crate) fn parse_distinct_key(input: &str) -> IResult<&str, SearchClause> {
+    value(
+        SearchClause::DistinctKey,
+        alt((
+            value((), (wsi(tag_no_case("distinct")), wsi(tag_no_case("key")))),
+            value(
+                (),
+                (
+                    wsi(tag_no_case("latest")),
+                    wsi(tag_no_case("by")),
+                    wsi(tag_no_case("key")),
+                ),
+            ),
+        )),
+    )
+    .parse(input)
+}
 
 pub(crate) fn parse_from_offset_clause(input: &str) -> IResult<&str, SearchClause> {
     map(parse_from_offset, SearchClause::From).parse(input)
 }
 
+pub(crate) fn parse_sample_clause(input: &str) -> IResult<&str, SearchClause> {
+    map(parse_sample, SearchClause::Sample).parse(input)
+}
+
 pub(crate) fn parse_limit(input: &str) -> IResult<&str, SearchClause> {
     map(
         preceded(wsi(tag_no_case("limit")), wsi(parse_unsigned_number)),
@@ -65,13 +88,25 @@ pub(crate) fn parse_order_by(input: &str) -> IResult<&str, SearchClause> {
                 wsi(alt((tag_no_case("order"), tag_no_case("sort")))),
                 wsi(tag_no_case("by")),
             ),
-            pair(parse_order, opt(parse_order_keyword)),
+            parse_sort_keys,
         ),
-        |(o, oo)| SearchClause::OrderBy(o, oo),
+        SearchClause::OrderBy,
     )
     .parse(input)
 }
 
+#[test]
+fn test_parse_distinct_key() {
+    assert_eq!(
+        parse_distinct_key(r#"distinct key"#),
+        Ok(("", SearchClause::DistinctKey))
+    );
+    assert_eq!(
+        parse_distinct_key(r#" latest by key"#),
+        Ok(("", SearchClause::DistinctKey))
+    );
+}
+
 #[test]
 fn test_parse_offset_clause() {
     assert_eq!(
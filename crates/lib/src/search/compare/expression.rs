@@ -3,11 +3,17 @@
 /// offset != 234
 /// key == "my-key"
 /// timestamp between "2 hours ago" and "1 hour ago"
+/// timestamp > now - 15m
+/// value.createdAt >= now - 1d
+/// lower(value.email) == "jane@example.com"
+/// offset < committed("billing-service")
 /// ```
+#[cfg(feature = "native")]
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
 #[cfg(feature = "native")]
-use chrono::{DateTime, Local};
+use crate::search::{function::FunctionCall, timestamp::TimeExpression};
 use nom::Parser;
 use nom::bytes::complete::tag_no_case;
 use nom::{
@@ -21,52 +27,68 @@ use super::number::NumberOperator;
 use super::string::StringOperator;
 
 #[cfg(feature = "native")]
-#[derive(Debug, PartialEq, Clone, Eq)]
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
 pub enum CompareExpression {
     Partition(NumberOperator, i32),
     OffsetTail(i64),
     Offset(NumberOperator, i64),
+    /// Compares the offset to the offset committed by a consumer group on the partition of the record.
+    OffsetCommitted(NumberOperator, String),
     Topic(StringOperator, String),
     Key(StringOperator, String),
     Value(Option<String>, StringOperator, String),
     Header(String, StringOperator, String),
+    /// Compares a date of the value, a RFC 3339 string or milliseconds since epoch.
+    ValueTimestamp(Option<String>, NumberOperator, TimeExpression),
     Size(NumberOperator, i64),
-    Timestamp(NumberOperator, DateTime<Local>),
-    TimestampBetween(DateTime<Local>, DateTime<Local>),
+    Timestamp(NumberOperator, TimeExpression),
+    TimestampBetween(TimeExpression, TimeExpression),
+    /// Compares the result of a built-in function to a string: `lower(value.email) == "jane@example.com"`.
+    FunctionString(FunctionCall, StringOperator, String),
+    /// Compares the result of a built-in function to a number: `length(value.items) > 3`.
+    FunctionNumber(FunctionCall, NumberOperator, i64),
 }
 
 #[cfg(feature = "native")]
 impl Display for CompareExpression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use crate::search::string::quote;
+
         match self {
             CompareExpression::Partition(op, r) => write!(f, "partition {} {}", op, r),
-            CompareExpression::OffsetTail(r) => write!(f, "offsetTail - {}", r),
+            CompareExpression::OffsetTail(r) => write!(f, "offsetTail == {}", r),
             CompareExpression::Offset(op, r) => write!(f, "offset {} {}", op, r),
-            CompareExpression::Topic(op, r) => write!(f, "topic {} {}", op, r),
-            CompareExpression::Key(op, r) => write!(f, "key {} {}", op, r),
+            CompareExpression::OffsetCommitted(op, group) => {
+                write!(f, "offset {} committed({})", op, quote(group))
+            }
+            CompareExpression::Topic(op, r) => write!(f, "topic {} {}", op, quote(r)),
+            CompareExpression::Key(op, r) => write!(f, "key {} {}", op, quote(r)),
             CompareExpression::Value(left, op, r) => write!(
                 f,
                 "value{} {} {}",
-                left.clone().unwrap_or("".to_string()),
+                left.as_deref().unwrap_or_default(),
                 op,
-                r
+                quote(r)
             ),
             CompareExpression::Header(left, op, r) => {
-                write!(f, "headers.{} {} {}", left.clone(), op, r)
+                write!(f, "headers.{} {} {}", left, op, quote(r))
             }
-            CompareExpression::Size(op, r) => write!(f, "size {} {}", op, r),
-            CompareExpression::Timestamp(op, r) => write!(
+            CompareExpression::ValueTimestamp(left, op, r) => write!(
                 f,
-                r#"timestamp {} "{}""#,
+                "value{} {} {}",
+                left.as_deref().unwrap_or_default(),
                 op,
-                r.to_rfc3339_opts(chrono::SecondsFormat::Millis, false)
-            ),
-            CompareExpression::TimestampBetween(l, r) => write!(
-                f,
-                r#"timestamp between "{}" and "{}"#,
-                l.to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
-                r.to_rfc3339_opts(chrono::SecondsFormat::Millis, false)
+                r
             ),
+            CompareExpression::Size(op, r) => write!(f, "size {} {}", op, r),
+            CompareExpression::Timestamp(op, r) => write!(f, "timestamp {} {}", op, r),
+            CompareExpression::TimestampBetween(l, r) => {
+                write!(f, "timestamp between {} and {}", l, r)
+            }
+            CompareExpression::FunctionString(call, op, r) => {
+                write!(f, "{} {} {}", call, op, quote(r))
+            }
+            CompareExpression::FunctionNumber(call, op, r) => write!(f, "{} {} {}", call, op, r),
         }
     }
 }
@@ -75,6 +97,7 @@ impl Display for CompareExpression {
 pub fn parse_compare(input: &str) -> IResult<&str, CompareExpression> {
     use crate::search::{
         compare::{parse_equal, string::parse_string_operator},
+        function::parse_function_call,
         number::parse_number,
         string::parse_string,
         symbol::{
@@ -88,10 +111,37 @@ pub fn parse_compare(input: &str) -> IResult<&str, CompareExpression> {
     use super::number::parse_number_operator;
 
     alt((
+        map(
+            (
+                parse_function_call,
+                wsi(parse_string_operator),
+                wsi(parse_string),
+            ),
+            |(call, op, r)| CompareExpression::FunctionString(call, op, r),
+        ),
+        map(
+            (
+                parse_function_call,
+                wsi(parse_number_operator),
+                wsi(parse_number),
+            ),
+            |(call, op, r)| CompareExpression::FunctionNumber(call, op, r),
+        ),
         map(
             (parse_offset, wsi(parse_number_operator), wsi(parse_number)),
             |(_, op, r)| CompareExpression::Offset(op, r),
         ),
+        map(
+            (
+                parse_offset,
+                wsi(parse_number_operator),
+                wsi(tag("committed")),
+                wsi(tag("(")),
+                wsi(parse_string),
+                wsi(tag(")")),
+            ),
+            |(_, op, _, _, group, _)| CompareExpression::OffsetCommitted(op, group),
+        ),
         map(
             (parse_size, wsi(parse_number_operator), wsi(parse_number)),
             |(_, op, r)| CompareExpression::Size(op, r),
@@ -128,6 +178,14 @@ pub fn parse_compare(input: &str) -> IResult<&str, CompareExpression> {
             ),
             |(left, op, r)| CompareExpression::Value(left.1, op, r),
         ),
+        map(
+            (
+                parse_value_symbol,
+                wsi(parse_number_operator),
+                wsi(parse_timestamp),
+            ),
+            |(left, op, r)| CompareExpression::ValueTimestamp(left.1, op, r),
+        ),
         map(
             (
                 parse_header_symbol,
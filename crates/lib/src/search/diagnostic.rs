@@ -0,0 +1,537 @@
+//! Diagnostics explaining why a search query is invalid.
+//!
+//! The parser only tells where it stopped. [`Diagnostic::from_remaining`] looks at the unparsed input
+//! to find the offending token, what was expected instead and, for misspelled words, what the user probably meant.
+//!
+//! ```text
+//! key == foo
+//!        ^^^ expected a string literal, found 'foo'
+//! ```
+use std::{fmt::Display, ops::Range};
+
+use super::{
+    compare::{number::parse_number_operator, parse_compare, string::parse_string_operator},
+    expression::parse_or_expression,
+    function::{FUNCTIONS, parse_argument, parse_function_call},
+    number::parse_number,
+    string::parse_string,
+    timestamp::{TimeExpression, parse_date, parse_timestamp},
+};
+
+/// Symbols of the query language, used to suggest a fix for a misspelled word.
+const SYMBOLS: [&str; 9] = [
+    "offset",
+    "offsetTail",
+    "topic",
+    "partition",
+    "key",
+    "size",
+    "timestamp",
+    "value",
+    "headers",
+];
+
+/// Keywords starting a clause or combining expressions.
+const KEYWORDS: [&str; 14] = [
+    "from", "where", "limit", "order", "sort", "group", "count", "min", "max", "distinct",
+    "latest", "sample", "and", "or",
+];
+
+/// A token the parser expected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expected {
+    Symbol,
+    Operator,
+    String,
+    Number,
+    Time,
+    Clause,
+    Keyword(&'static str),
+}
+
+impl Display for Expected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expected::Symbol => write!(f, "a symbol"),
+            Expected::Operator => write!(f, "an operator"),
+            Expected::String => write!(f, "a string literal"),
+            Expected::Number => write!(f, "a number"),
+            Expected::Time => write!(f, "a date"),
+            Expected::Clause => write!(f, "a clause"),
+            Expected::Keyword(k) => write!(f, "'{}'", k),
+        }
+    }
+}
+
+/// An error located in the search query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The search query.
+    pub query: String,
+    /// Byte range of the offending token in the query.
+    pub span: Range<usize>,
+    /// Tokens that would have been valid at this position.
+    pub expected: Vec<Expected>,
+    pub message: String,
+    /// A suggestion for a misspelled word.
+    pub hint: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(query: &str, span: Range<usize>, message: &str) -> Self {
+        Self {
+            query: query.to_string(),
+            span,
+            expected: vec![],
+            message: message.to_string(),
+            hint: None,
+        }
+    }
+
+    /// Explains why the parser stopped at `remaining`, the unparsed end of `query`.
+    pub fn from_remaining(query: &str, remaining: &str) -> Self {
+        diagnose(query, &query[query.len() - remaining.len()..])
+    }
+
+    fn expected(query: &str, at: &str, expected: Vec<Expected>) -> Self {
+        let span = token_span(query, at);
+        let token = &query[span.clone()];
+        let found = match token.chars().next() {
+            None => "end of query".to_string(),
+            Some('"' | '\'') => token.to_string(),
+            Some(_) => format!("'{}'", token),
+        };
+        let message = format!("expected {}, found {}", list(&expected), found);
+        Self {
+            query: query.to_string(),
+            span,
+            expected,
+            message,
+            hint: None,
+        }
+    }
+
+    pub fn with_hint(mut self, hint: Option<String>) -> Self {
+        self.hint = hint;
+        self
+    }
+
+    /// The offending token.
+    pub fn token(&self) -> &str {
+        &self.query[self.span.clone()]
+    }
+
+    /// One-line description of the error, without the query.
+    pub fn summary(&self) -> String {
+        let position = self.query[..self.span.start].chars().count();
+        match &self.hint {
+            Some(hint) => format!(
+                "{} at position {}. Did you mean '{}'?",
+                self.message, position, hint
+            ),
+            None => format!("{} at position {}", self.message, position),
+        }
+    }
+}
+
+/// Renders the line of the query containing the error, with a caret under the offending token.
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let line_start = self.query[..self.span.start]
+            .rfind('\n')
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let line_end = self.query[self.span.start..]
+            .find('\n')
+            .map(|i| i + self.span.start)
+            .unwrap_or(self.query.len());
+        let column = self.query[line_start..self.span.start].chars().count();
+        let width = self.query[self.span.start..self.span.end.min(line_end)]
+            .chars()
+            .count()
+            .max(1);
+        writeln!(f, "  {}", &self.query[line_start..line_end])?;
+        write!(
+            f,
+            "  {}{} {}",
+            " ".repeat(column),
+            "^".repeat(width),
+            self.message
+        )?;
+        if let Some(hint) = &self.hint {
+            write!(f, "\n  Did you mean '{}'?", hint)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the candidate the closest to `word`, if it's close enough to be a typo.
+pub fn did_you_mean<'a>(
+    word: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<String> {
+    let max_distance = (word.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|c| (levenshtein(&word.to_lowercase(), &c.to_lowercase()), c))
+        .filter(|(d, _)| *d > 0 && *d <= max_distance)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, c)| c.to_string())
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+fn list(expected: &[Expected]) -> String {
+    let expected = expected.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    match expected.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+        None => "nothing".to_string(),
+    }
+}
+
+/// Byte range of the token `at` starts with, `at` being a suffix of `query`.
+fn token_span(query: &str, at: &str) -> Range<usize> {
+    let trimmed = at.trim_start();
+    let start = query.len() - trimmed.len();
+    start..start + token(trimmed).len()
+}
+
+fn token(input: &str) -> &str {
+    let is_word = |c: char| c.is_alphanumeric() || "._-[]/".contains(c);
+    let end = match input.chars().next() {
+        None => 0,
+        Some(quote @ ('"' | '\'')) => input[1..].find(quote).map(|i| i + 2).unwrap_or(input.len()),
+        Some(c) if is_word(c) => input.find(|c| !is_word(c)).unwrap_or(input.len()),
+        Some(c) => input
+            .find(|c: char| !"=!<>~&|".contains(c))
+            .unwrap_or(input.len())
+            .max(c.len_utf8()),
+    };
+    &input[..end]
+}
+
+/// Returns the input following the token `at` starts with.
+fn after_token(at: &str) -> &str {
+    let trimmed = at.trim_start();
+    &trimmed[token(trimmed).len()..]
+}
+
+fn is_symbol(word: &str) -> bool {
+    let head = word.split(['.', '[']).next().unwrap_or_default();
+    SYMBOLS.contains(&head) || ["v", "h", "k", "t", "o", "p", "si", "ts"].contains(&head)
+}
+
+fn diagnose(query: &str, at: &str) -> Diagnostic {
+    let trimmed = at.trim_start();
+    // The parser stops in the middle of a date whose durations overflow.
+    let position = query.len() - trimmed.len();
+    let out_of_range = query[..position]
+        .char_indices()
+        .filter_map(|(start, _)| diagnose_out_of_range(query, &query[start..]))
+        .find(|d| d.span.end > position);
+    if let Some(d) = out_of_range {
+        return d;
+    }
+    let word = token(trimmed);
+    let rest = after_token(trimmed);
+    match word.to_lowercase().as_str() {
+        "" => Diagnostic::expected(query, at, vec![Expected::Clause]),
+        "limit" => Diagnostic::expected(query, rest, vec![Expected::Number]),
+        "sample" if parse_number(token(rest.trim_start())).is_ok() => Diagnostic::expected(
+            query,
+            after_token(rest),
+            vec![Expected::Keyword("%"), Expected::Keyword("per partition")],
+        ),
+        "sample" => Diagnostic::expected(query, rest, vec![Expected::Number]),
+        "from" if token(rest.trim_start()) == "group" => Diagnostic::expected(
+            query,
+            after_token(rest.trim_start()),
+            vec![Expected::String],
+        ),
+        "from" => diagnose_out_of_range(query, rest).unwrap_or_else(|| {
+            Diagnostic::expected(
+                query,
+                rest,
+                vec![
+                    Expected::Keyword("begin"),
+                    Expected::Keyword("end"),
+                    Expected::Keyword("group"),
+                    Expected::Time,
+                    Expected::Number,
+                ],
+            )
+        }),
+        "order" | "sort" | "group" if token(rest.trim_start()) != "by" => {
+            Diagnostic::expected(query, rest, vec![Expected::Keyword("by")])
+        }
+        "order" | "sort" | "group" => {
+            let rest = after_token(rest);
+            diagnose_word(query, rest, vec![Expected::Symbol])
+        }
+        "where" | "and" | "or" | "&&" | "||" | "!" if rest.trim().is_empty() => {
+            Diagnostic::expected(query, rest, vec![Expected::Symbol])
+        }
+        "where" | "and" | "or" | "&&" | "||" | "!" => diagnose(query, rest),
+        "(" => match parse_or_expression(rest) {
+            Ok((remaining, _)) if !remaining.trim_start().starts_with(')') => {
+                Diagnostic::expected(query, remaining, vec![Expected::Keyword(")")])
+            }
+            _ => diagnose(query, rest),
+        },
+        _ if FUNCTIONS.contains(&word) || word == "base64_decode" => {
+            diagnose_function(query, trimmed, rest)
+        }
+        _ if rest.starts_with('(') => Diagnostic::expected(
+            query,
+            &rest[1..],
+            vec![Expected::String, Expected::Number, Expected::Keyword(")")],
+        )
+        .with_hint(did_you_mean(word, FUNCTIONS)),
+        _ if is_symbol(word) => diagnose_comparison(query, trimmed, word, rest),
+        _ => diagnose_word(query, trimmed, vec![Expected::Symbol, Expected::Clause]),
+    }
+}
+
+/// The word at `at` is not known, it's probably misspelled.
+fn diagnose_word(query: &str, at: &str, expected: Vec<Expected>) -> Diagnostic {
+    let word = token(at.trim_start());
+    let (head, tail) = match word.find(['.', '[']) {
+        Some(i) => word.split_at(i),
+        None => (word, ""),
+    };
+    let hint = did_you_mean(head, SYMBOLS.into_iter().chain(KEYWORDS))
+        .map(|hint| format!("{}{}", hint, tail));
+    Diagnostic::expected(query, at, expected).with_hint(hint)
+}
+
+fn diagnose_comparison(query: &str, at: &str, word: &str, rest: &str) -> Diagnostic {
+    if let Some(d) = parse_number_operator(rest.trim_start())
+        .ok()
+        .and_then(|(r, _)| diagnose_out_of_range(query, r))
+    {
+        return d;
+    }
+    if let Ok((remaining, _)) = parse_compare(at) {
+        return diagnose(query, remaining);
+    }
+    let head = word.split(['.', '[']).next().unwrap_or_default();
+    let rest_trimmed = rest.trim_start();
+    match head {
+        "timestamp" | "ts" => {
+            if let Some(r) = rest_trimmed.strip_prefix("between") {
+                return match parse_timestamp(r.trim_start()) {
+                    Ok((r, _)) if !r.trim_start().starts_with("and") => {
+                        Diagnostic::expected(query, r, vec![Expected::Keyword("and")])
+                    }
+                    Ok((r, _)) => {
+                        diagnose_out_of_range(query, after_token(r)).unwrap_or_else(|| {
+                            Diagnostic::expected(query, after_token(r), vec![Expected::Time])
+                        })
+                    }
+                    Err(_) => diagnose_out_of_range(query, r)
+                        .unwrap_or_else(|| Diagnostic::expected(query, r, vec![Expected::Time])),
+                };
+            }
+            match parse_number_operator(rest_trimmed) {
+                Ok((r, _)) => Diagnostic::expected(query, r, vec![Expected::Time]),
+                Err(_) => Diagnostic::expected(
+                    query,
+                    rest,
+                    vec![Expected::Operator, Expected::Keyword("between")],
+                ),
+            }
+        }
+        "offset" | "o" | "partition" | "p" | "size" | "si" | "offsetTail" => {
+            match parse_number_operator(rest_trimmed) {
+                Ok((r, _)) if parse_number(r.trim_start()).is_err() => {
+                    Diagnostic::expected(query, r, vec![Expected::Number])
+                }
+                _ => Diagnostic::expected(query, rest, vec![Expected::Operator]),
+            }
+        }
+        _ => match parse_string_operator(rest_trimmed) {
+            Ok((r, _)) if parse_string(r.trim_start()).is_err() => {
+                let mut expected = vec![Expected::String];
+                if head.starts_with('v') {
+                    expected.push(Expected::Time);
+                }
+                Diagnostic::expected(query, r, expected)
+            }
+            Ok((r, _)) => diagnose(query, r),
+            Err(_) => match parse_number_operator(rest_trimmed) {
+                Ok((r, _)) if head.starts_with('v') => {
+                    Diagnostic::expected(query, r, vec![Expected::Time])
+                }
+                _ => Diagnostic::expected(query, rest, vec![Expected::Operator]),
+            },
+        },
+    }
+}
+
+/// `now - 99999999999999999w`, the date is valid but shifting it overflows.
+fn diagnose_out_of_range(query: &str, at: &str) -> Option<Diagnostic> {
+    let trimmed = at.trim_start();
+    let (mut rest, _) = parse_date(trimmed).ok()?;
+    while let Some(duration) = rest.trim_start().strip_prefix(['+', '-']) {
+        let duration = duration.trim_start();
+        let word = token(duration);
+        if !is_duration(word) {
+            return None;
+        }
+        rest = &duration[word.len()..];
+    }
+    let (remaining, _) = parse_timestamp(trimmed)
+        .ok()
+        .unwrap_or((trimmed, TimeExpression::Now(0)));
+    let start = query.len() - trimmed.len();
+    (remaining.len() > rest.len()).then(|| {
+        Diagnostic::new(
+            query,
+            start..query.len() - rest.len(),
+            "the date is out of range",
+        )
+    })
+}
+
+/// `1h30m`, whatever its value.
+fn is_duration(word: &str) -> bool {
+    let mut rest = word;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let Some(unit) = ["ms", "s", "m", "h", "d", "w"]
+            .into_iter()
+            .find(|unit| rest[digits..].starts_with(unit))
+        else {
+            return false;
+        };
+        if digits == 0 {
+            return false;
+        }
+        rest = &rest[digits + unit.len()..];
+    }
+    !word.is_empty()
+}
+
+/// `lower(value.email) == "jane@example.com"`, the argument is a symbol and the result must be compared.
+fn diagnose_function(query: &str, at: &str, rest: &str) -> Diagnostic {
+    if let Ok((remaining, _)) = parse_compare(at) {
+        return diagnose(query, remaining);
+    }
+    let Ok((remaining, _)) = parse_function_call(at) else {
+        let Some(argument) = rest.trim_start().strip_prefix('(') else {
+            return Diagnostic::expected(query, rest, vec![Expected::Keyword("(")]);
+        };
+        return match parse_argument(argument) {
+            Ok((r, _)) => Diagnostic::expected(query, r, vec![Expected::Keyword(")")]),
+            Err(_) => diagnose_word(query, argument, vec![Expected::Symbol]),
+        };
+    };
+    let trimmed = remaining.trim_start();
+    match (
+        parse_string_operator(trimmed),
+        parse_number_operator(trimmed),
+    ) {
+        (Ok((r, _)), _) => Diagnostic::expected(query, r, vec![Expected::String, Expected::Number]),
+        (_, Ok((r, _))) => Diagnostic::expected(query, r, vec![Expected::Number]),
+        _ => Diagnostic::expected(query, remaining, vec![Expected::Operator]),
+    }
+}
+
+#[cfg(test)]
+fn diagnostic(query: &str) -> Diagnostic {
+    match super::parse_search_query(query) {
+        Err(crate::error::SearchError::Parse(d)) => d,
+        e => panic!("'{}' should not be parsed: {:?}", query, e),
+    }
+}
+
+#[test]
+fn test_expected_string() {
+    let d = diagnostic(r#"key == foo"#);
+    assert_eq!(d.token(), "foo");
+    assert_eq!(d.expected, vec![Expected::String]);
+    assert_eq!(d.message, "expected a string literal, found 'foo'");
+}
+
+#[test]
+fn test_expected_operator() {
+    let d = diagnostic(r#"from begin partition is 2"#);
+    assert_eq!(d.token(), "is");
+    assert_eq!(d.expected, vec![Expected::Operator]);
+}
+
+#[test]
+fn test_misspelled_symbol() {
+    let d = diagnostic(r#"key == "a" and partiton == 2"#);
+    assert_eq!(d.token(), "partiton");
+    assert_eq!(d.hint, Some("partition".to_string()));
+    assert_eq!(
+        d.to_string(),
+        [
+            r#"  key == "a" and partiton == 2"#,
+            r#"                 ^^^^^^^^ expected a symbol or a clause, found 'partiton'"#,
+            r#"  Did you mean 'partition'?"#,
+        ]
+        .join("\n")
+    );
+    let d = diagnostic(r#"from begin ordr by key"#);
+    assert_eq!(d.hint, Some("order".to_string()));
+    let d = diagnostic(r#"from begin order key"#);
+    assert_eq!(d.message, "expected 'by', found 'key'");
+}
+
+#[test]
+fn test_expected_at_end_of_query() {
+    let d = diagnostic(r#"offset > 10 limit"#);
+    assert_eq!(d.span, 17..17);
+    assert_eq!(d.message, "expected a number, found end of query");
+    let d = diagnostic(r#"(partition == 2 or partition == 3"#);
+    assert_eq!(d.expected, vec![Expected::Keyword(")")]);
+}
+
+#[test]
+fn test_did_you_mean() {
+    assert_eq!(
+        did_you_mean("timestmp", SYMBOLS),
+        Some("timestamp".to_string())
+    );
+    assert_eq!(did_you_mean("foo", SYMBOLS), None);
+}
+
+#[test]
+fn test_function_call() {
+    let d = diagnostic(r#"lower(value.email) == jane"#);
+    assert_eq!(d.token(), "jane");
+    assert_eq!(d.expected, vec![Expected::String, Expected::Number]);
+    let d = diagnostic(r#"length(offset) > 2"#);
+    assert_eq!(d.token(), "offset");
+    assert_eq!(d.expected, vec![Expected::Symbol]);
+    let d = diagnostic(r#"lowr(value.email) == "jane""#);
+    assert_eq!(d.hint, Some("lower".to_string()));
+}
+
+#[test]
+fn test_date_out_of_range() {
+    let d = diagnostic("timestamp > now - 99999999999999999w");
+    assert_eq!(d.token(), "now - 99999999999999999w");
+    assert_eq!(d.message, "the date is out of range");
+    let d = diagnostic(r#"timestamp > "2024-01-01T00:00:00Z" + 100000000w"#);
+    assert_eq!(d.token(), r#""2024-01-01T00:00:00Z" + 100000000w"#);
+    let d = diagnostic("from now - 100000000w");
+    assert_eq!(d.token(), "now - 100000000w");
+}
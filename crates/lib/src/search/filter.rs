@@ -33,9 +33,13 @@ use std::fmt::Display;
 
 use crate::KafkaRecord;
 
-use super::{number::parse_number, string::parse_string, wsi::wsi};
+use super::{
+    number::parse_number,
+    string::{parse_string, quote},
+    wsi::wsi,
+};
 
-#[derive(Debug, PartialEq, Clone, Default)]
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
 pub struct Filter {
     pub name: String,
     pub parameters: Vec<Parameter>,
@@ -52,7 +56,7 @@ impl Display for Filter {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Eq)]
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
 pub enum Parameter {
     Number(i64),
     String(String),
@@ -62,7 +66,7 @@ impl Display for Parameter {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             Parameter::Number(i) => write!(f, "{}", i),
-            Parameter::String(i) => write!(f, "'{}'", i),
+            Parameter::String(i) => write!(f, "{}", quote(i)),
         }
     }
 }
@@ -0,0 +1,146 @@
+//! Built-in functions transform a symbol before it is compared.
+//!
+//! ```sql
+//! lower(value.email) == "jane@example.com"
+//! length(value.items) > 3
+//! json_type(value.id) == "string"
+//! b64decode(headers.payload) contains "error"
+//! ```
+//!
+//! Unlike search filters, functions are evaluated natively, they don't need a wasm module.
+use std::fmt::Display;
+
+use nom::{
+    IResult, Parser,
+    branch::alt,
+    bytes::complete::tag,
+    combinator::{map, value},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    group_by::symbol_to_query,
+    symbol::{Symbol, parse_header_symbol, parse_key, parse_topic, parse_value_symbol},
+    wsi::wsi,
+};
+
+/// Names of the built-in functions.
+pub const FUNCTIONS: [&str; 4] = ["lower", "length", "json_type", "b64decode"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Function {
+    /// The argument in lowercase.
+    Lower,
+    /// Number of characters of a string, of items of an array or of properties of an object.
+    Length,
+    /// JSON type of the argument: `string`, `number`, `boolean`, `null`, `array` or `object`.
+    JsonType,
+    /// The argument decoded from base64.
+    Base64Decode,
+}
+
+/// A function applied to a symbol, on the left-hand side of a comparison.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionCall {
+    pub function: Function,
+    /// `topic`, `key`, `value`, `value.<json-path>` or `headers.<name>`.
+    pub argument: Symbol,
+}
+
+impl FunctionCall {
+    pub fn new(function: Function, argument: Symbol) -> Self {
+        Self { function, argument }
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Function::Lower => "lower",
+            Function::Length => "length",
+            Function::JsonType => "json_type",
+            Function::Base64Decode => "b64decode",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Display for FunctionCall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}({})", self.function, symbol_to_query(&self.argument))
+    }
+}
+
+fn parse_function(input: &str) -> IResult<&str, Function> {
+    wsi(alt((
+        value(Function::Lower, tag("lower")),
+        value(Function::Length, tag("length")),
+        value(Function::JsonType, tag("json_type")),
+        value(
+            Function::Base64Decode,
+            alt((tag("b64decode"), tag("base64_decode"))),
+        ),
+    )))
+    .parse(input)
+}
+
+pub(crate) fn parse_argument(input: &str) -> IResult<&str, Symbol> {
+    wsi(alt((
+        map(parse_value_symbol, |s| s.0),
+        map(parse_header_symbol, |s| s.0),
+        parse_topic,
+        parse_key,
+    )))
+    .parse(input)
+}
+
+/// Parses a function call.
+/// ```text
+/// lower(value.email)
+/// b64decode(headers.payload)
+/// ```
+pub(crate) fn parse_function_call(input: &str) -> IResult<&str, FunctionCall> {
+    map(
+        (parse_function, wsi(tag("(")), parse_argument, wsi(tag(")"))),
+        |(function, _, argument, _)| FunctionCall::new(function, argument),
+    )
+    .parse(input)
+}
+
+#[test]
+fn test_parse_function_call() {
+    assert_eq!(
+        parse_function_call(r#"lower(value.email)"#),
+        Ok((
+            "",
+            FunctionCall::new(Function::Lower, Symbol::Value(Some(".email".to_string())))
+        ))
+    );
+    assert_eq!(
+        parse_function_call(r#"length( value )"#),
+        Ok(("", FunctionCall::new(Function::Length, Symbol::Value(None))))
+    );
+    assert_eq!(
+        parse_function_call(r#"base64_decode(headers.payload)"#),
+        Ok((
+            "",
+            FunctionCall::new(
+                Function::Base64Decode,
+                Symbol::Header("payload".to_string())
+            )
+        ))
+    );
+    assert!(parse_function_call(r#"lower(offset)"#).is_err());
+    assert!(parse_function_call(r#"lower("foo")"#).is_err());
+}
+
+#[test]
+fn test_display_function_call() {
+    for input in [
+        "json_type(value.id)",
+        "b64decode(headers.payload)",
+        "lower(key)",
+    ] {
+        assert_eq!(parse_function_call(input).unwrap().1.to_string(), input);
+    }
+}
@@ -0,0 +1,216 @@
+//! Aggregation clauses group the matching kafka records and compute a metric for each group.
+//!
+//! ```sql
+//! group by value.type count
+//! count by key top 20
+//! group by partition max size
+//! min timestamp by headers.source
+//! ```
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+use nom::{
+    IResult, Parser,
+    branch::alt,
+    bytes::complete::tag_no_case,
+    combinator::{map, opt, value},
+    sequence::preceded,
+};
+
+use super::{
+    number::parse_unsigned_number,
+    symbol::{
+        Symbol, parse_header_symbol, parse_key, parse_offset, parse_partition, parse_size,
+        parse_timestamp_symbol, parse_topic, parse_value_symbol,
+    },
+    wsi::wsi,
+};
+
+/// Groups kafka records by a symbol and aggregates each group.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupBy {
+    /// The symbol records are grouped by: `topic`, `partition`, `key`, `value` or a header.
+    pub key: Symbol,
+    /// The function applied to each group.
+    pub aggregate: Aggregate,
+    /// Only keep the N first groups.
+    pub top: Option<usize>,
+}
+
+/// Aggregate functions supported by the `group by` clause.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Aggregate {
+    /// Number of records in the group.
+    #[default]
+    Count,
+    /// Lowest numeric value of the symbol in the group.
+    Min(Symbol),
+    /// Highest numeric value of the symbol in the group.
+    Max(Symbol),
+}
+
+impl GroupBy {
+    pub fn new(key: Symbol, aggregate: Aggregate, top: Option<usize>) -> Self {
+        Self {
+            key,
+            aggregate,
+            top,
+        }
+    }
+}
+
+/// Returns the symbol as it is written in a search query.
+pub fn symbol_to_query(symbol: &Symbol) -> String {
+    match symbol {
+        Symbol::Offset => "offset".to_string(),
+        Symbol::Topic => "topic".to_string(),
+        Symbol::Partition => "partition".to_string(),
+        Symbol::OffsetTail => "offsetTail".to_string(),
+        Symbol::Key => "key".to_string(),
+        Symbol::Size => "size".to_string(),
+        Symbol::Timestamp => "timestamp".to_string(),
+        Symbol::Value(path) => format!("value{}", path.clone().unwrap_or_default()),
+        Symbol::Header(name) => format!("headers.{}", name),
+    }
+}
+
+impl Display for Aggregate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Aggregate::Count => write!(f, "count"),
+            Aggregate::Min(s) => write!(f, "min {}", symbol_to_query(s)),
+            Aggregate::Max(s) => write!(f, "max {}", symbol_to_query(s)),
+        }
+    }
+}
+
+impl Display for GroupBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "group by {} {}",
+            symbol_to_query(&self.key),
+            self.aggregate
+        )?;
+        if let Some(top) = self.top {
+            write!(f, " top {}", top)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_group_key(input: &str) -> IResult<&str, Symbol> {
+    wsi(alt((
+        map(parse_value_symbol, |s| s.0),
+        map(parse_header_symbol, |s| s.0),
+        parse_topic,
+        parse_partition,
+        parse_key,
+    )))
+    .parse(input)
+}
+
+fn parse_numeric_symbol(input: &str) -> IResult<&str, Symbol> {
+    wsi(alt((
+        map(parse_value_symbol, |s| s.0),
+        parse_offset,
+        parse_size,
+        parse_timestamp_symbol,
+        parse_partition,
+    )))
+    .parse(input)
+}
+
+fn parse_aggregate(input: &str) -> IResult<&str, Aggregate> {
+    alt((
+        value(Aggregate::Count, wsi(tag_no_case("count"))),
+        map(
+            preceded(wsi(tag_no_case("min")), parse_numeric_symbol),
+            Aggregate::Min,
+        ),
+        map(
+            preceded(wsi(tag_no_case("max")), parse_numeric_symbol),
+            Aggregate::Max,
+        ),
+    ))
+    .parse(input)
+}
+
+fn parse_top(input: &str) -> IResult<&str, usize> {
+    preceded(wsi(tag_no_case("top")), wsi(parse_unsigned_number)).parse(input)
+}
+
+/// Parses `group by <key> [<aggregate>] [top <n>]` or `<aggregate> by <key> [top <n>]`.
+pub(crate) fn parse_group_by(input: &str) -> IResult<&str, GroupBy> {
+    alt((
+        map(
+            (
+                wsi(tag_no_case("group")),
+                wsi(tag_no_case("by")),
+                parse_group_key,
+                opt(parse_aggregate),
+                opt(parse_top),
+            ),
+            |(_, _, key, aggregate, top)| GroupBy::new(key, aggregate.unwrap_or_default(), top),
+        ),
+        map(
+            (
+                parse_aggregate,
+                wsi(tag_no_case("by")),
+                parse_group_key,
+                opt(parse_top),
+            ),
+            |(aggregate, _, key, top)| GroupBy::new(key, aggregate, top),
+        ),
+    ))
+    .parse(input)
+}
+
+#[test]
+fn test_parse_group_by() {
+    assert_eq!(
+        parse_group_by("group by value.type count"),
+        Ok((
+            "",
+            GroupBy::new(
+                Symbol::Value(Some(".type".to_string())),
+                Aggregate::Count,
+                None
+            )
+        ))
+    );
+    assert_eq!(
+        parse_group_by("count by key top 20"),
+        Ok(("", GroupBy::new(Symbol::Key, Aggregate::Count, Some(20))))
+    );
+    assert_eq!(
+        parse_group_by("group by partition max size top 3"),
+        Ok((
+            "",
+            GroupBy::new(Symbol::Partition, Aggregate::Max(Symbol::Size), Some(3))
+        ))
+    );
+    assert_eq!(
+        parse_group_by("min value.price by headers.source"),
+        Ok((
+            "",
+            GroupBy::new(
+                Symbol::Header("source".to_string()),
+                Aggregate::Min(Symbol::Value(Some(".price".to_string()))),
+                None
+            )
+        ))
+    );
+    assert!(parse_group_by("count(\"foo\")").is_err());
+}
+
+#[test]
+fn test_display_group_by() {
+    for input in [
+        "group by value.type count",
+        "group by key count top 20",
+        "group by headers.source min value.price",
+    ] {
+        assert_eq!(parse_group_by(input).unwrap().1.to_string(), input);
+    }
+}
@@ -3,7 +3,7 @@
 //!
 //! ```bnf
 //! search-query      ::= clause+
-//! clause            ::= or-expression | limit-clause | from-clause | order-clause
+//! clause            ::= or-expression | limit-clause | from-clause | order-clause | group-clause | distinct-clause | sample-clause
 //! or-expression     ::= And-expression | and-expression 'or' and-expression
 //! and-expression    ::= atom | atom 'and' atom
 //! term              ::= atom | '!' atom
@@ -11,20 +11,36 @@
 //! number-symbol     ::= 'offset' | 'partition' | 'size'
 //! string-symbol     ::= 'topic' | 'key' | 'timestamp' | 'value'
 //! symbol            ::= number-symbol | string-symbol
-//! comparison        ::= number-comparison | string-comparison | time-comparison
+//! comparison        ::= number-comparison | string-comparison | time-comparison | value-time-comparison | function-comparison | committed-comparison
 //! number-comparison ::= number-symbol number-operator number
+//! committed-comparison ::= 'offset' number-operator 'committed' '(' string ')'
 //! string-comparison ::= string-symbol string-operator string
-//! time-comparison   ::= 'between' string 'and' string
+//! time-comparison   ::= 'timestamp' number-operator time | 'timestamp between' time 'and' time
+//! value-time-comparison ::= 'value' json-path? number-operator time
+//! function-comparison ::= function '(' function-argument ')' (string-operator string | number-operator number)
+//! function          ::= 'lower' | 'length' | 'json_type' | 'b64decode'
+//! function-argument ::= 'topic' | 'key' | 'value' json-path? | 'headers.' name
+//! time              ::= (string | 'now') (('+' | '-') duration)*
+//! duration          ::= (number ('ms' | 's' | 'm' | 'h' | 'd' | 'w'))+
 //! number-operator   ::=  '==' | '!=' | '>' | '<' | '>=' | '<='
 //! string-operator   ::= 'starts with' | '==' | '!=' | '=~' | 'contains' | 'contain' | 'includes' | 'include'
 //! filter            ::= .+ '('filter-parameters')'
 //! filter-parameter  ::= string | number
 //! filter-parameters ::= filter-parameter  (',' filter-parameter)*
 //! limit-clause      ::= 'limit' number
-//! order-clause      ::= 'order by' symbol order-keyword
+//! order-clause      ::= 'order by' sort-key (',' sort-key)*
+//! sort-key          ::= sort-symbol order-keyword?
+//! sort-symbol       ::= symbol | 'value.' json-path | 'headers.' name
 //! order-keyword     ::= 'asc' | 'desc'
+//! group-clause      ::= 'group by' group-symbol aggregate? top? | aggregate 'by' group-symbol top?
+//! group-symbol      ::= 'topic' | 'partition' | 'key' | 'value' | 'headers.' name
+//! aggregate         ::= 'count' | 'min' numeric-symbol | 'max' numeric-symbol
+//! numeric-symbol    ::= 'offset' | 'partition' | 'size' | 'timestamp' | 'value'
+//! top               ::= 'top' number
+//! distinct-clause   ::= 'distinct key' | 'latest by key'
+//! sample-clause     ::= 'sample' number ('.' [0-9]+)? '%' | 'sample' number 'per partition'
 //! from-clause       ::= 'from' offset
-//! offset            ::= 'beginning' | 'begin' | 'end' | 'end' '-' number | string | number
+//! offset            ::= 'beginning' | 'begin' | 'end' | 'end' '-' number | time | number | 'group' string
 //! number            ::= [0-9_]+
 //! string            ::= '"' [^"]+ '"' | "'" [^']+ "'"
 //! ```
@@ -35,16 +51,24 @@ pub mod atom;
 #[cfg(feature = "native")]
 pub mod clause;
 #[cfg(feature = "native")]
+pub mod diagnostic;
+#[cfg(feature = "native")]
 pub mod expression;
 #[cfg(feature = "native")]
 pub mod filter;
 #[cfg(feature = "native")]
+pub mod function;
+#[cfg(feature = "native")]
+pub mod group_by;
+#[cfg(feature = "native")]
 pub mod number;
 #[cfg(feature = "native")]
 pub mod offset;
 #[cfg(feature = "native")]
 pub mod order;
 #[cfg(feature = "native")]
+pub mod sample;
+#[cfg(feature = "native")]
 pub mod search_query;
 #[cfg(feature = "native")]
 pub mod string;
@@ -64,6 +88,8 @@ pub use order::Order;
 #[cfg(feature = "native")]
 pub use order::OrderBy;
 #[cfg(feature = "native")]
+pub use order::SortKey;
+#[cfg(feature = "native")]
 pub use search_query::SearchQuery;
 #[cfg(feature = "native")]
 pub use search_query::parse_search_query;
@@ -93,3 +119,59 @@ impl From<bool> for FilterResult {
         Self { r#match }
     }
 }
+
+/// What the optional `transform` function of a search filter returns: the record to display instead.
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+pub struct TransformResult {
+    pub record: crate::KafkaRecord,
+}
+
+impl From<crate::KafkaRecord> for TransformResult {
+    fn from(record: crate::KafkaRecord) -> Self {
+        Self { record }
+    }
+}
+
+/// What the optional `describe` function of a search filter returns.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Deserialize, Serialize)]
+pub struct FilterDescription {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub parameters: Vec<ParameterDescription>,
+}
+
+/// A parameter of a search filter.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Deserialize, Serialize)]
+pub struct ParameterDescription {
+    pub name: String,
+    /// `string` or `number`
+    #[serde(default, rename = "type")]
+    pub r#type: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
+}
+
+impl FilterDescription {
+    /// Returns how the search filter is called, `key-ends-with(suffix: string)`.
+    pub fn signature(&self, name: &str) -> String {
+        let parameters = self
+            .parameters
+            .iter()
+            .map(|p| {
+                let optional = if p.optional { "?" } else { "" };
+                match p.r#type.is_empty() {
+                    true => format!("{}{}", p.name, optional),
+                    false => format!("{}{}: {}", p.name, optional, p.r#type),
+                }
+            })
+            .collect::<Vec<_>>();
+        format!("{}({})", name, parameters.join(", "))
+    }
+}
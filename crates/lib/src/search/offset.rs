@@ -1,5 +1,8 @@
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
+use chrono::{DateTime, SecondsFormat};
+
 use nom::{
     IResult, Parser,
     branch::alt,
@@ -11,13 +14,14 @@ use nom::{
 use super::{
     compare::parse_equal,
     number::parse_number,
+    string::{parse_string, quote},
     symbol::{parse_end_keyword, parse_offset},
     timestamp::parse_timestamp,
     wsi::wsi,
 };
 
 /// A kafka offset.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FromOffset {
     /// Start consuming from the beginning of the partition.
     Beginning,
@@ -29,6 +33,8 @@ pub enum FromOffset {
     OffsetTail(i64),
     /// Start consuming from a specific timestamp end of the partition.
     Timestamp(i64),
+    /// Start consuming from the offsets committed by a consumer group.
+    Group(String),
 }
 
 impl Display for FromOffset {
@@ -38,7 +44,14 @@ impl Display for FromOffset {
             FromOffset::End => write!(f, "end"),
             FromOffset::Offset(o) => write!(f, "{}", o),
             FromOffset::OffsetTail(o) => write!(f, "end - {}", o),
-            FromOffset::Timestamp(_) => write!(f, ""),
+            FromOffset::Timestamp(t) => write!(
+                f,
+                r#""{}""#,
+                DateTime::from_timestamp_millis(*t)
+                    .unwrap_or_default()
+                    .to_rfc3339_opts(SecondsFormat::Millis, true)
+            ),
+            FromOffset::Group(group) => write!(f, "group {}", quote(group)),
         }
     }
 }
@@ -48,15 +61,21 @@ impl Display for FromOffset {
 /// from begin
 /// from end
 /// from "3 hours ago"
+/// from now - 2h
 /// from 34895
 /// from -10
+/// from group "billing-service"
 /// ```
 pub(crate) fn parse_from_offset(input: &str) -> IResult<&str, FromOffset> {
     preceded(
         wsi(tag_no_case("from")),
         alt((
+            map(
+                preceded(wsi(tag_no_case("group")), wsi(parse_string)),
+                FromOffset::Group,
+            ),
             map(wsi(parse_timestamp), |t| {
-                FromOffset::Timestamp(t.to_utc().timestamp_millis())
+                FromOffset::Timestamp(t.evaluate().to_utc().timestamp_millis())
             }),
             value(
                 FromOffset::Beginning,
@@ -1,4 +1,4 @@
-use crate::search::offset::parse_from_offset;
+use crate::search::offset::{FromOffset, parse_from_offset};
 
 #[test]
 fn test_parse_from_offset() {
@@ -9,3 +9,23 @@ fn test_parse_from_offset() {
 fn test_parse_from_end_minus_number() {
     assert!(parse_from_offset(r#"from end - 10"#).is_ok());
 }
+
+#[test]
+fn test_parse_from_relative_time() {
+    assert!(matches!(
+        parse_from_offset(r#"from now - 2h"#),
+        Ok(("", FromOffset::Timestamp(_)))
+    ));
+}
+
+#[test]
+fn test_parse_from_group() {
+    assert_eq!(
+        parse_from_offset(r#"from group "billing-service""#),
+        Ok(("", FromOffset::Group("billing-service".to_string())))
+    );
+    assert_eq!(
+        FromOffset::Group("billing-service".to_string()).to_string(),
+        r#"group "billing-service""#
+    );
+}
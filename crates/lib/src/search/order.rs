@@ -1,49 +1,59 @@
-use nom::Parser;
-use nom::{IResult, branch::alt, bytes::complete::tag, combinator::value};
+use nom::{
+    IResult, Parser,
+    branch::alt,
+    bytes::complete::tag,
+    combinator::{map, opt, value},
+    multi::separated_list1,
+};
+use serde::{Deserialize, Serialize};
 
 use super::symbol::{
-    Symbol, parse_key, parse_offset, parse_partition, parse_size, parse_timestamp_symbol,
-    parse_topic, parse_value,
+    Symbol, parse_header_symbol, parse_key, parse_offset, parse_partition, parse_size,
+    parse_timestamp_symbol, parse_topic, parse_value_symbol,
 };
 use super::wsi::wsi;
 
-/// This struct is only used when you start the TUI.
 /// You can order kafka records in the terminal as you could do with SQL.
+/// In headless mode, records are buffered and printed once sorted when the consumer stops.
 ///
 /// ```sql
 /// order by key desc
 /// sort by partition asc
+/// order by value.priority desc, timestamp asc
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+///
+/// Sort keys are compared in the order they are written, the next key only breaks ties.
+/// An empty `OrderBy` means the query has no `order by` clause.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct OrderBy {
+    pub keys: Vec<SortKey>,
+}
+
+/// A sort key of the `order by` clause.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct SortKey {
     pub order: Order,
     pub keyword: OrderKeyword,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum OrderKeyword {
     Desc,
+    #[default]
     Asc,
 }
 
-impl Default for Order {
-    fn default() -> Self {
-        Self::Timestamp
-    }
-}
-
-impl Default for OrderKeyword {
-    fn default() -> Self {
-        Self::Asc
-    }
-}
-
 /// You can order kafka records by the following fields.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum Order {
+    #[default]
     Timestamp,
     Key,
-    Value,
+    /// The whole value, or the property at the given JSON path.
+    /// Properties are compared as numbers or dates when possible.
+    Value(Option<String>),
+    /// The value of a header, compared as a number or a date when possible.
+    Header(String),
     Partition,
     Offset,
     Size,
@@ -51,6 +61,24 @@ pub enum Order {
 }
 
 impl OrderBy {
+    pub fn new(order: Order, keyword: OrderKeyword) -> Self {
+        Self {
+            keys: vec![SortKey::new(order, keyword)],
+        }
+    }
+
+    /// Adds a sort key used to break ties of the previous ones.
+    pub fn then(mut self, order: Order, keyword: OrderKeyword) -> Self {
+        self.keys.push(SortKey::new(order, keyword));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+impl SortKey {
     pub fn new(order: Order, keyword: OrderKeyword) -> Self {
         Self { order, keyword }
     }
@@ -66,7 +94,8 @@ pub(crate) fn parse_order(input: &str) -> IResult<&str, Order> {
         parse_timestamp_symbol,
         parse_offset,
         parse_key,
-        parse_value,
+        map(parse_value_symbol, |s| s.0),
+        map(parse_header_symbol, |s| s.0),
         parse_topic,
         parse_partition,
     )))
@@ -76,12 +105,12 @@ pub(crate) fn parse_order(input: &str) -> IResult<&str, Order> {
         Symbol::Offset => Order::Offset,
         Symbol::Key => Order::Key,
         Symbol::Topic => Order::Topic,
-        Symbol::Value(_) => Order::Value,
+        Symbol::Value(path) => Order::Value(path),
         Symbol::Partition => Order::Partition,
         Symbol::OffsetTail => unreachable!("nope"),
         Symbol::Size => Order::Size,
         Symbol::Timestamp => Order::Timestamp,
-        Symbol::Header(_) => unreachable!("nope"),
+        Symbol::Header(name) => Order::Header(name),
     };
     Ok((t.0, o))
 }
@@ -94,24 +123,42 @@ pub(crate) fn parse_order_keyword(input: &str) -> IResult<&str, OrderKeyword> {
     .parse(input)
 }
 
+pub(crate) fn parse_sort_keys(input: &str) -> IResult<&str, OrderBy> {
+    map(
+        separated_list1(wsi(tag(",")), (parse_order, opt(parse_order_keyword))),
+        |keys| OrderBy {
+            keys: keys
+                .into_iter()
+                .map(|(order, keyword)| SortKey::new(order, keyword.unwrap_or_default()))
+                .collect(),
+        },
+    )
+    .parse(input)
+}
+
 impl std::fmt::Display for OrderBy {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "order by {} {}", self.order, self.keyword)
+        let keys = self
+            .keys
+            .iter()
+            .map(|k| format!("{} {}", k.order, k.keyword))
+            .collect::<Vec<_>>();
+        write!(f, "order by {}", keys.join(", "))
     }
 }
 
 impl std::fmt::Display for Order {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let order = match self {
-            Order::Timestamp => "timestamp",
-            Order::Key => "key",
-            Order::Value => "value",
-            Order::Partition => "partition",
-            Order::Offset => "offset",
-            Order::Size => "size",
-            Order::Topic => "topic",
-        };
-        write!(f, "{}", order)
+        match self {
+            Order::Timestamp => write!(f, "timestamp"),
+            Order::Key => write!(f, "key"),
+            Order::Value(path) => write!(f, "value{}", path.as_deref().unwrap_or_default()),
+            Order::Header(name) => write!(f, "headers.{}", name),
+            Order::Partition => write!(f, "partition"),
+            Order::Offset => write!(f, "offset"),
+            Order::Size => write!(f, "size"),
+            Order::Topic => write!(f, "topic"),
+        }
     }
 }
 
@@ -136,3 +183,36 @@ fn test_parse_order_keyword() {
     assert_eq!(parse_order_keyword(r#"asc"#), Ok(("", OrderKeyword::Asc)));
     assert_eq!(parse_order_keyword(r#"desc"#), Ok(("", OrderKeyword::Desc)));
 }
+
+#[test]
+fn test_parse_sort_keys() {
+    assert_eq!(
+        parse_sort_keys(r#"value.priority desc, timestamp"#),
+        Ok((
+            "",
+            OrderBy::new(
+                Order::Value(Some(".priority".to_string())),
+                OrderKeyword::Desc
+            )
+            .then(Order::Timestamp, OrderKeyword::Asc)
+        ))
+    );
+    assert_eq!(
+        parse_sort_keys(r#"headers.source asc"#),
+        Ok((
+            "",
+            OrderBy::new(Order::Header("source".to_string()), OrderKeyword::Asc)
+        ))
+    );
+}
+
+#[test]
+fn test_display_order_by() {
+    for input in [
+        "order by value.priority desc, timestamp asc",
+        "order by headers.source asc, key desc",
+    ] {
+        let (_, order_by) = parse_sort_keys(input.trim_start_matches("order by")).unwrap();
+        assert_eq!(order_by.to_string(), input);
+    }
+}
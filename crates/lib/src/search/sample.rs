@@ -0,0 +1,123 @@
+//! The `sample` clause keeps a representative slice of the records.
+//!
+//! ```sql
+//! sample 1%
+//! sample 0.05%
+//! sample 1000 per partition
+//! ```
+use std::fmt::Display;
+
+use nom::{
+    IResult, Parser,
+    branch::alt,
+    bytes::complete::{tag, tag_no_case},
+    character::complete::digit1,
+    combinator::{map, map_res, opt},
+    sequence::preceded,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{number::parse_unsigned_number, wsi::wsi};
+
+/// One percent, in parts per million.
+const PERCENT: u32 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Sample {
+    /// Each record is kept with this probability, in parts per million.
+    Rate(u32),
+    /// Records at evenly spaced offsets between the watermarks of each partition.
+    PerPartition(usize),
+}
+
+impl Display for Sample {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Sample::Rate(rate) => {
+                let decimals = format!("{:04}", rate % PERCENT);
+                let decimals = decimals.trim_end_matches('0');
+                match decimals.is_empty() {
+                    true => write!(f, "sample {}%", rate / PERCENT),
+                    false => write!(f, "sample {}.{}%", rate / PERCENT, decimals),
+                }
+            }
+            Sample::PerPartition(count) => write!(f, "sample {} per partition", count),
+        }
+    }
+}
+
+/// Parses a percentage with up to 4 decimals, in parts per million.
+fn parse_percentage(input: &str) -> IResult<&str, u32> {
+    map_res(
+        (
+            parse_unsigned_number,
+            opt(preceded(tag("."), digit1)),
+            wsi(tag("%")),
+        ),
+        |(units, decimals, _): (usize, Option<&str>, _)| {
+            let decimals = decimals.unwrap_or_default();
+            if decimals.len() > 4 {
+                return Err("a percentage has at most 4 decimals");
+            }
+            let decimals = format!("{:0<4}", decimals).parse::<u32>().unwrap_or(0);
+            match units {
+                0..100 => Ok(units as u32 * PERCENT + decimals),
+                100 if decimals == 0 => Ok(100 * PERCENT),
+                _ => Err("a percentage cannot exceed 100%"),
+            }
+        },
+    )
+    .parse(input)
+}
+
+/// Parses the sample clause.
+/// ```text
+/// sample 1%
+/// sample 1000 per partition
+/// ```
+pub(crate) fn parse_sample(input: &str) -> IResult<&str, Sample> {
+    preceded(
+        wsi(tag_no_case("sample")),
+        alt((
+            map(wsi(parse_percentage), Sample::Rate),
+            map(
+                (
+                    wsi(parse_unsigned_number),
+                    wsi(tag_no_case("per")),
+                    wsi(tag_no_case("partition")),
+                ),
+                |(count, _, _)| Sample::PerPartition(count),
+            ),
+        )),
+    )
+    .parse(input)
+}
+
+#[test]
+fn test_parse_sample() {
+    assert_eq!(parse_sample("sample 1%"), Ok(("", Sample::Rate(10_000))));
+    assert_eq!(parse_sample("sample 0.05 %"), Ok(("", Sample::Rate(500))));
+    assert_eq!(
+        parse_sample("sample 100%"),
+        Ok(("", Sample::Rate(1_000_000)))
+    );
+    assert_eq!(
+        parse_sample("sample 1_000 per partition"),
+        Ok(("", Sample::PerPartition(1000)))
+    );
+    assert!(parse_sample("sample 100.5%").is_err());
+    assert!(parse_sample("sample 0.00001%").is_err());
+    assert!(parse_sample("sample 10").is_err());
+}
+
+#[test]
+fn test_display_sample() {
+    for input in [
+        "sample 1%",
+        "sample 0.05%",
+        "sample 12.3456%",
+        "sample 1000 per partition",
+    ] {
+        assert_eq!(parse_sample(input).unwrap().1.to_string(), input);
+    }
+}
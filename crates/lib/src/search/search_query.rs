@@ -1,37 +1,70 @@
 use itertools::Itertools;
 use nom::{
-    Parser,
+    IResult, Parser,
     branch::alt,
     combinator::{eof, map},
-    multi::many_till,
+    multi::{many_till, many0},
 };
+use serde::{Deserialize, Serialize};
 
 use crate::error::SearchError;
 
 use super::{
     clause::{
-        SearchClause, parse_expression, parse_from_offset_clause, parse_limit, parse_order_by,
+        SearchClause, parse_distinct_key, parse_expression, parse_from_offset_clause,
+        parse_group_by_clause, parse_limit, parse_order_by, parse_sample_clause,
     },
+    diagnostic::Diagnostic,
     expression::Expression,
+    group_by::GroupBy,
     offset::FromOffset,
-    order::{Order, OrderBy, OrderKeyword},
+    order::OrderBy,
+    sample::Sample,
     wsi::wsi,
 };
 
 /// A `SearchQuery` is a combination of a expression, a limit, an offset and an order by clause.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SearchQuery {
     pub expression: Expression,
     pub limit: Option<usize>,
     pub from: Option<FromOffset>,
     pub order_by: OrderBy,
-    //pub group_by_key: bool,
+    pub group_by: Option<GroupBy>,
+    /// Only keep the latest record of each key, tombstones delete the key.
+    pub distinct_key: bool,
+    /// Only keep a representative slice of the records.
+    pub sample: Option<Sample>,
 }
 
 impl SearchQuery {
     pub fn is_empty(&self) -> bool {
         self.limit.is_none() && self.from.is_none() && self.expression.is_empty()
     }
+
+    /// Returns the clauses of the query, except the expression.
+    pub fn clauses(&self) -> Vec<String> {
+        let mut clauses = vec![];
+        if let Some(from) = &self.from {
+            clauses.push(format!("from {}", from));
+        }
+        if !self.order_by.is_empty() {
+            clauses.push(self.order_by.to_string());
+        }
+        if let Some(group_by) = &self.group_by {
+            clauses.push(group_by.to_string());
+        }
+        if self.distinct_key {
+            clauses.push("distinct key".to_string());
+        }
+        if let Some(sample) = &self.sample {
+            clauses.push(sample.to_string());
+        }
+        if let Some(limit) = self.limit {
+            clauses.push(format!("limit {}", limit));
+        }
+        clauses
+    }
 }
 
 impl std::fmt::Display for SearchQuery {
@@ -48,7 +81,18 @@ impl std::fmt::Display for SearchQuery {
         };
         clauses.push(from.to_string());
         clauses.push(format!("{}", self.expression));
-        clauses.push(format!("{}", self.order_by));
+        if !self.order_by.is_empty() {
+            clauses.push(format!("{}", self.order_by));
+        }
+        if let Some(group_by) = &self.group_by {
+            clauses.push(group_by.to_string());
+        }
+        if self.distinct_key {
+            clauses.push("distinct key".to_string());
+        }
+        if let Some(sample) = &self.sample {
+            clauses.push(sample.to_string());
+        }
         clauses.push(limit.to_string());
         let clauses = clauses.into_iter().filter(|e| !e.is_empty()).collect_vec();
         write!(f, "{}", clauses.join(" "))
@@ -61,46 +105,52 @@ impl Default for SearchQuery {
             expression: Expression::OrExpression(vec![]),
             limit: None,
             from: None,
-            order_by: OrderBy::new(Order::Timestamp, OrderKeyword::Asc),
-            //group_by_key: false,
+            order_by: OrderBy::default(),
+            group_by: None,
+            distinct_key: false,
+            sample: None,
         }
     }
 }
 
+fn parse_clause(input: &str) -> IResult<&str, SearchClause> {
+    alt((
+        parse_from_offset_clause,
+        parse_limit,
+        parse_group_by_clause,
+        parse_distinct_key,
+        parse_sample_clause,
+        parse_expression,
+        parse_order_by,
+    ))
+    .parse(input)
+}
+
 pub fn parse_search_query(input: &str) -> Result<(&str, SearchQuery), SearchError> {
-    map(
-        many_till(
-            alt((
-                parse_from_offset_clause,
-                parse_limit,
-                parse_expression,
-                parse_order_by,
-            )),
-            wsi(eof),
-        ),
-        |clauses| {
-            let mut s = SearchQuery::default();
-            for c in clauses.0 {
-                match c {
-                    SearchClause::Limit(i) => s.limit = Some(i),
-                    SearchClause::From(f) => s.from = Some(f),
-                    SearchClause::Expression(u) => s.expression = u,
-                    SearchClause::OrderBy(order, k) => {
-                        s.order_by = OrderBy::new(order, k.unwrap_or(OrderKeyword::Asc))
-                    } //SearchClause::GroupByKey => s.group_by_key = true,
-                }
+    map(many_till(parse_clause, wsi(eof)), |clauses| {
+        let mut s = SearchQuery::default();
+        for c in clauses.0 {
+            match c {
+                SearchClause::Limit(i) => s.limit = Some(i),
+                SearchClause::From(f) => s.from = Some(f),
+                SearchClause::Expression(u) => s.expression = u,
+                SearchClause::OrderBy(o) => s.order_by = o,
+                SearchClause::GroupBy(g) => s.group_by = Some(g),
+                SearchClause::DistinctKey => s.distinct_key = true,
+                SearchClause::Sample(sample) => s.sample = Some(sample),
             }
-            s
-        },
-    )
+        }
+        s
+    })
     .parse(input)
-    .map_err(|e| {
-        let remaining = match e {
-            nom::Err::Incomplete(_) => input.to_string(),
-            nom::Err::Error(s) => s.input.to_string(),
-            nom::Err::Failure(s) => s.input.to_string(),
-        };
-        SearchError::Parse(remaining)
+    .map_err(|_| {
+        // The error of the parser points to where the last alternative failed,
+        // the diagnostic starts from the beginning of the first invalid clause.
+        let remaining = many0(parse_clause)
+            .parse(input)
+            .map(|(remaining, _)| remaining)
+            .unwrap_or(input);
+        SearchError::Parse(Diagnostic::from_remaining(input, remaining))
     })
 }
 
@@ -108,3 +158,14 @@ pub fn parse_search_query(input: &str) -> Result<(&str, SearchQuery), SearchErro
 fn test_parse_search_query() {
     assert!(parse_search_query(r#"   from end - 10"#).is_ok());
 }
+
+#[test]
+fn test_clauses() {
+    let (_, query) = parse_search_query(r#"key == "a" limit 3 from begin distinct key"#).unwrap();
+    assert_eq!(
+        query.clauses(),
+        vec!["from beginning", "distinct key", "limit 3"]
+    );
+    let (_, query) = parse_search_query(r#"key == "a" || offset > 3"#).unwrap();
+    assert!(query.clauses().is_empty());
+}
@@ -22,8 +22,24 @@ pub(crate) fn parse_string(input: &str) -> IResult<&str, String> {
     .parse(input)
 }
 
+/// Quotes a string so [`parse_string`] reads it back.
+/// Double quotes are preferred, single quotes are used when the string contains a double quote.
+pub fn quote(string: &str) -> String {
+    match string.contains('"') {
+        true => format!("'{}'", string),
+        false => format!(r#""{}""#, string),
+    }
+}
+
 #[test]
 fn test_parse_string() {
     assert_eq!(parse_string(r#"'halo'"#), Ok(("", "halo".to_string())));
     assert_eq!(parse_string(r#""hola""#), Ok(("", "hola".to_string())));
 }
+
+#[test]
+fn test_quote() {
+    for input in ["halo", r#"say "hola""#, "it's", ""] {
+        assert_eq!(parse_string(&quote(input)), Ok(("", input.to_string())));
+    }
+}
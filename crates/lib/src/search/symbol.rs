@@ -8,11 +8,12 @@ use nom::{
     error::ErrorKind,
     sequence::preceded,
 };
+use serde::{Deserialize, Serialize};
 use strum::Display;
 
 use super::wsi::wsi;
 
-#[derive(Debug, Display, PartialEq, Eq, Clone)]
+#[derive(Debug, Display, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum Symbol {
     Offset,
     Topic,
@@ -52,10 +53,6 @@ pub(crate) fn parse_partition(input: &str) -> IResult<&str, Symbol> {
     value(Symbol::Partition, wsi(alt((tag("partition"), tag("p"))))).parse(input)
 }
 
-pub(crate) fn parse_value(input: &str) -> IResult<&str, Symbol> {
-    value(Symbol::Value(None), wsi(alt((tag("value"), tag("v"))))).parse(input)
-}
-
 pub(crate) fn parse_topic(input: &str) -> IResult<&str, Symbol> {
     value(Symbol::Topic, wsi(alt((tag("topic"), tag("t"))))).parse(input)
 }
@@ -88,8 +85,11 @@ pub(crate) fn parse_header_symbol(input: &str) -> IResult<&str, (Symbol, String)
 }
 
 /// Parse a JSON Pointer, producing a list of decoded segments.
+/// A comma ends the path so sort keys can be listed: `order by value.a, value.b`.
+/// A closing parenthesis ends the path of a function argument: `lower(value.email)`.
 pub(crate) fn parse_json_path(input: &str) -> IResult<&str, String> {
-    let (remaining, json_path) = recognize(take_while(|ch| ch != ' ')).parse(input)?;
+    let (remaining, json_path) =
+        recognize(take_while(|ch| ch != ' ' && ch != ',' && ch != ')')).parse(input)?;
     match json_path.is_empty() {
         true => Err(nom::Err::Error(nom::error::Error::new(
             remaining,
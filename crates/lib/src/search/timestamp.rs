@@ -1,15 +1,115 @@
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, TimeDelta, Utc};
 use fuzzydate::parse;
 use nom::{
     IResult, Parser,
     branch::alt,
-    bytes::complete::tag_no_case,
-    combinator::{map_res, value},
+    bytes::complete::{tag, tag_no_case},
+    combinator::{map_opt, map_res, value},
+    multi::{many0, many1},
 };
+use serde::{Deserialize, Serialize};
 
-use super::{string::parse_string, wsi::wsi};
+use super::{number::parse_unsigned_number, string::parse_string, wsi::wsi};
 
-/// Parses a timestamp.
+/// A point in time of a search query.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeExpression {
+    /// A fixed date. Fuzzy dates (`"3 hours ago"`) are evaluated when the query is parsed.
+    Date(DateTime<Local>),
+    /// `now`, shifted by a number of milliseconds.
+    /// It is evaluated every time it is compared, so long-running searches don't drift.
+    Now(i64),
+}
+
+impl TimeExpression {
+    /// Returns the date time the expression refers to at this instant.
+    /// A shift beyond the supported dates returns the earliest or the latest supported date.
+    pub fn evaluate(&self) -> DateTime<Local> {
+        match self {
+            TimeExpression::Date(date) => *date,
+            TimeExpression::Now(shift) => shift_date(Local::now(), *shift).unwrap_or_else(|| {
+                match *shift < 0 {
+                    true => DateTime::<Utc>::MIN_UTC,
+                    false => DateTime::<Utc>::MAX_UTC,
+                }
+                .with_timezone(&Local)
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for TimeExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeExpression::Date(date) => write!(
+                f,
+                r#""{}""#,
+                date.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, false)
+            ),
+            TimeExpression::Now(0) => write!(f, "now"),
+            TimeExpression::Now(shift) if *shift < 0 => {
+                write!(f, "now - {}", format_duration(shift.unsigned_abs()))
+            }
+            TimeExpression::Now(shift) => write!(f, "now + {}", format_duration(*shift as u64)),
+        }
+    }
+}
+
+const DURATION_UNITS: [(&str, u64); 6] = [
+    ("w", 7 * 24 * 60 * 60 * 1000),
+    ("d", 24 * 60 * 60 * 1000),
+    ("h", 60 * 60 * 1000),
+    ("m", 60 * 1000),
+    ("s", 1000),
+    ("ms", 1),
+];
+
+/// Formats milliseconds as a duration literal, `5400000` becomes `1h30m`.
+fn format_duration(mut millis: u64) -> String {
+    if millis == 0 {
+        return "0s".to_string();
+    }
+    let mut duration = String::new();
+    for (unit, size) in DURATION_UNITS {
+        if millis >= size {
+            duration.push_str(&format!("{}{}", millis / size, unit));
+            millis %= size;
+        }
+    }
+    duration
+}
+
+/// Parses a duration literal, in milliseconds.
+///
+/// ```text
+/// 15m
+/// 1h30m
+/// 2d
+/// 500ms
+/// ```
+pub(crate) fn parse_duration(input: &str) -> IResult<&str, i64> {
+    map_opt(
+        many1((
+            parse_unsigned_number,
+            alt((
+                value(1, tag("ms")),
+                value(1000, tag("s")),
+                value(60 * 1000, tag("m")),
+                value(60 * 60 * 1000, tag("h")),
+                value(24 * 60 * 60 * 1000, tag("d")),
+                value(7 * 24 * 60 * 60 * 1000, tag("w")),
+            )),
+        )),
+        |parts| {
+            parts.into_iter().try_fold(0i64, |total, (n, unit)| {
+                total.checked_add(i64::try_from(n).ok()?.checked_mul(unit)?)
+            })
+        },
+    )
+    .parse(input)
+}
+
+/// Parses a date.
 /// It can be a RFC3339 date time
 /// or a fuzzy date (`3 hours ago`) or the 'now' keyword.
 ///
@@ -18,22 +118,110 @@ use super::{string::parse_string, wsi::wsi};
 /// '2024-09-19T17:59:25.815+02:00'
 /// now
 /// ```
-pub(crate) fn parse_timestamp(input: &str) -> IResult<&str, DateTime<Local>> {
+pub(crate) fn parse_date(input: &str) -> IResult<&str, TimeExpression> {
     alt((
         map_res(parse_string, |s| {
-            DateTime::parse_from_rfc3339(&s).map(|d| d.with_timezone(&Local))
+            DateTime::parse_from_rfc3339(&s).map(|d| TimeExpression::Date(d.with_timezone(&Local)))
         }),
         map_res(parse_string, |s| {
-            parse(s).map(|d| d.and_local_timezone(Local).unwrap())
+            parse(s).map(|d| TimeExpression::Date(d.and_local_timezone(Local).unwrap()))
         }),
-        value(Local::now(), wsi(tag_no_case("now"))),
+        value(TimeExpression::Now(0), wsi(tag_no_case("now"))),
     ))
     .parse(input)
 }
 
+/// Parses a timestamp, optionally shifted by durations.
+///
+/// ```text
+/// now - 15m
+/// "2024-09-19T17:59:25.815+02:00" + 1d
+/// now - 1h + 30s
+/// ```
+pub(crate) fn parse_timestamp(input: &str) -> IResult<&str, TimeExpression> {
+    map_opt(
+        (
+            parse_date,
+            many0((
+                wsi(alt((value(1, tag("+")), value(-1, tag("-"))))),
+                wsi(parse_duration),
+            )),
+        ),
+        |(date, shifts)| {
+            shifts
+                .into_iter()
+                .try_fold(date, |date, (sign, millis)| match date {
+                    TimeExpression::Date(d) => {
+                        shift_date(d, sign * millis).map(TimeExpression::Date)
+                    }
+                    TimeExpression::Now(shift) => {
+                        let shift = shift.checked_add(sign * millis)?;
+                        // The shift must lead to a supported date
+                        shift_date(Local::now(), shift)?;
+                        Some(TimeExpression::Now(shift))
+                    }
+                })
+        },
+    )
+    .parse(input)
+}
+
+/// Shifts the date by a number of milliseconds, `None` when the result is not a supported date.
+fn shift_date(date: DateTime<Local>, millis: i64) -> Option<DateTime<Local>> {
+    date.checked_add_signed(TimeDelta::try_milliseconds(millis)?)
+}
+
 #[test]
 fn test_parse_timestamp() {
     assert!(parse_timestamp(r#"'3 hours ago'"#).is_ok());
     assert!(parse_timestamp(r#"now"#).is_ok());
     assert!(parse_timestamp(r#""2024-09-17T06:44:59Z""#).is_ok());
 }
+
+#[test]
+fn test_parse_relative_timestamp() {
+    assert_eq!(
+        parse_timestamp(r#"now - 15m"#),
+        Ok(("", TimeExpression::Now(-15 * 60 * 1000)))
+    );
+    assert_eq!(
+        parse_timestamp(r#"now - 1h + 30s and"#),
+        Ok((" and", TimeExpression::Now(-60 * 60 * 1000 + 30 * 1000)))
+    );
+    assert_eq!(
+        parse_timestamp(r#""2024-09-17T06:44:59Z" + 1d"#).map(|(_, t)| t.evaluate().to_utc()),
+        Ok(DateTime::parse_from_rfc3339("2024-09-18T06:44:59Z")
+            .unwrap()
+            .to_utc())
+    );
+    assert_eq!(
+        parse_timestamp(r#"now - 10"#),
+        Ok((" - 10", TimeExpression::Now(0)))
+    );
+}
+
+#[test]
+fn test_display_time_expression() {
+    for input in ["now", "now - 1h30m", "now + 2d", "now - 1w500ms"] {
+        assert_eq!(parse_timestamp(input).unwrap().1.to_string(), input);
+    }
+}
+
+#[test]
+fn test_time_overflow() {
+    assert!(parse_duration("99999999999999999w").is_err());
+    assert!(parse_duration("18446744073709551615ms").is_err());
+    assert!(parse_timestamp("now - 100000000w").is_err());
+    assert!(parse_timestamp(r#""2024-01-01T00:00:00Z" + 100000000w"#).is_err());
+    for input in [
+        "timestamp > now - 99999999999999999w",
+        r#"timestamp > "2024-01-01T00:00:00Z" + 100000000w"#,
+        "from now - 100000000w",
+    ] {
+        assert!(crate::parse_search_query(input).is_err(), "{}", input);
+    }
+    assert_eq!(
+        TimeExpression::Now(i64::MIN).evaluate(),
+        DateTime::<Utc>::MIN_UTC
+    );
+}
@@ -0,0 +1,3 @@
+from end - 10_000
+where topic == "purchases"
+count by value.type top 20
@@ -0,0 +1,3 @@
+from begin
+where value.status == "open"
+order by value.priority desc, headers.source, timestamp asc
@@ -0,0 +1,2 @@
+where timestamp > now - 15m
+  and value.createdAt >= now - 1d12h
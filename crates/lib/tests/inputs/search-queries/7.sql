@@ -0,0 +1,4 @@
+where lower(value.email) == "jane@example.com"
+  and length(value.items) > 3
+  and json_type(value.id) == "string"
+   or b64decode(headers.payload) contains "error"
@@ -0,0 +1,3 @@
+from group "billing-service"
+where partition == 0 and offset >= committed("billing-service")
+limit 100
@@ -0,0 +1,4 @@
+from beginning
+where value contains "error"
+sample 0.5%
+limit 1000
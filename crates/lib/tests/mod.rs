@@ -25,6 +25,21 @@ fn test_inputs() {
     });
 }
 
+#[test]
+fn test_formatted_inputs_are_parsed_back() {
+    glob!("inputs/search-queries/*.sql", |path| {
+        let input = fs::read_to_string(path).unwrap();
+        let (_, query) = parse_search_query(input.trim()).unwrap();
+        let formatted = query.to_string();
+        assert_eq!(
+            parse_search_query(&formatted).map(|(_, q)| q).ok(),
+            Some(query),
+            "{}",
+            formatted
+        );
+    });
+}
+
 #[test]
 fn test_exported_record() {
     glob!("inputs/parsed-records/record*.json", |path| {
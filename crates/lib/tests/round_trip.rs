@@ -0,0 +1,289 @@
+//! Property tests checking that a formatted search query is parsed back to the same AST.
+//!
+//! Strategies only generate the shapes the parser produces:
+//! a single term is an `AndTerm`, not an `AndExpression` of one term.
+use chrono::{DateTime, Local};
+use proptest::prelude::*;
+use yozefu_lib::{
+    StringOperator,
+    search::{
+        Order, OrderBy, SearchQuery, SortKey,
+        atom::Atom,
+        compare::{CompareExpression, NumberOperator},
+        expression::{AndExpression, OrExpression},
+        filter::{Filter, Parameter},
+        function::{Function, FunctionCall},
+        group_by::{Aggregate, GroupBy},
+        offset::FromOffset,
+        order::OrderKeyword,
+        parse_search_query,
+        sample::Sample,
+        symbol::Symbol,
+        term::Term,
+        timestamp::TimeExpression,
+    },
+};
+
+fn string() -> impl Strategy<Value = String> + Clone {
+    prop_oneof!["[a-zA-Z0-9 _.'-]{0,10}", r#"[a-zA-Z0-9 _."-]{0,10}"#]
+}
+
+fn json_path() -> impl Strategy<Value = String> + Clone {
+    "(\\.[a-zA-Z_][a-zA-Z0-9_]{0,6}){1,3}"
+}
+
+fn header_name() -> impl Strategy<Value = String> + Clone {
+    "[a-zA-Z][a-zA-Z0-9_-]{0,8}"
+}
+
+fn number_operator() -> impl Strategy<Value = NumberOperator> + Clone {
+    prop_oneof![
+        Just(NumberOperator::GreaterThan),
+        Just(NumberOperator::GreaterOrEqual),
+        Just(NumberOperator::LowerThan),
+        Just(NumberOperator::LowerOrEqual),
+        Just(NumberOperator::Equal),
+        Just(NumberOperator::NotEqual),
+    ]
+}
+
+fn string_operator() -> impl Strategy<Value = StringOperator> + Clone {
+    prop_oneof![
+        Just(StringOperator::Contain),
+        Just(StringOperator::Equal),
+        Just(StringOperator::NotEqual),
+        Just(StringOperator::StartWith),
+    ]
+}
+
+fn date() -> impl Strategy<Value = DateTime<Local>> + Clone {
+    (0i64..4_102_444_800, 0u32..1_000_000_000).prop_map(|(seconds, nanos)| {
+        DateTime::from_timestamp(seconds, nanos)
+            .unwrap()
+            .with_timezone(&Local)
+    })
+}
+
+fn time_expression() -> impl Strategy<Value = TimeExpression> + Clone {
+    prop_oneof![
+        date().prop_map(TimeExpression::Date),
+        (-100_000_000_000i64..100_000_000_000).prop_map(TimeExpression::Now),
+    ]
+}
+
+fn compare() -> impl Strategy<Value = CompareExpression> + Clone {
+    prop_oneof![
+        (number_operator(), -1000i32..1000).prop_map(|(op, r)| CompareExpression::Partition(op, r)),
+        (0i64..1_000_000).prop_map(CompareExpression::OffsetTail),
+        (number_operator(), -1_000_000i64..1_000_000)
+            .prop_map(|(op, r)| CompareExpression::Offset(op, r)),
+        (number_operator(), string())
+            .prop_map(|(op, group)| CompareExpression::OffsetCommitted(op, group)),
+        (string_operator(), string()).prop_map(|(op, r)| CompareExpression::Topic(op, r)),
+        (string_operator(), string()).prop_map(|(op, r)| CompareExpression::Key(op, r)),
+        (
+            proptest::option::of(json_path()),
+            string_operator(),
+            string()
+        )
+            .prop_map(|(path, op, r)| CompareExpression::Value(path, op, r)),
+        (header_name(), string_operator(), string())
+            .prop_map(|(name, op, r)| CompareExpression::Header(name, op, r)),
+        // `value == "..."` is a string comparison, equality is only a date comparison with `now`.
+        (
+            proptest::option::of(json_path()),
+            number_operator(),
+            time_expression()
+        )
+            .prop_filter("string comparison", |(_, op, time)| {
+                !matches!(op, NumberOperator::Equal | NumberOperator::NotEqual)
+                    || matches!(time, TimeExpression::Now(_))
+            })
+            .prop_map(|(path, op, time)| CompareExpression::ValueTimestamp(path, op, time)),
+        (number_operator(), 0i64..1_000_000).prop_map(|(op, r)| CompareExpression::Size(op, r)),
+        (number_operator(), time_expression())
+            .prop_map(|(op, time)| CompareExpression::Timestamp(op, time)),
+        (time_expression(), time_expression())
+            .prop_map(|(from, to)| CompareExpression::TimestampBetween(from, to)),
+        (function_call(), string_operator(), string())
+            .prop_map(|(call, op, r)| CompareExpression::FunctionString(call, op, r)),
+        (function_call(), number_operator(), -1000i64..1000)
+            .prop_map(|(call, op, r)| CompareExpression::FunctionNumber(call, op, r)),
+    ]
+}
+
+fn function_call() -> impl Strategy<Value = FunctionCall> + Clone {
+    let function = prop_oneof![
+        Just(Function::Lower),
+        Just(Function::Length),
+        Just(Function::JsonType),
+        Just(Function::Base64Decode),
+    ];
+    let argument = prop_oneof![
+        Just(Symbol::Topic),
+        Just(Symbol::Key),
+        proptest::option::of(json_path()).prop_map(Symbol::Value),
+        header_name().prop_map(Symbol::Header),
+    ];
+    (function, argument).prop_map(|(function, argument)| FunctionCall::new(function, argument))
+}
+
+fn filter() -> impl Strategy<Value = Filter> + Clone {
+    let parameter = prop_oneof![
+        (-1000i64..1000).prop_map(Parameter::Number),
+        string().prop_map(Parameter::String),
+    ];
+    (
+        prop_oneof![
+            Just("contains"),
+            Just("key_ends_with"),
+            Just("inRange"),
+            Just("my-filter")
+        ],
+        proptest::collection::vec(parameter, 0..3),
+    )
+        .prop_map(|(name, parameters)| Filter {
+            name: name.to_string(),
+            parameters,
+        })
+}
+
+fn or_expression(
+    term: impl Strategy<Value = Term> + Clone,
+) -> impl Strategy<Value = OrExpression> + Clone {
+    let and = proptest::collection::vec(term, 1..4).prop_map(|mut terms| match terms.len() {
+        1 => AndExpression::AndTerm(terms.remove(0)),
+        _ => AndExpression::AndExpression(terms),
+    });
+    proptest::collection::vec(and, 1..3).prop_map(|mut ands| match ands.len() {
+        1 => OrExpression::OrTerm(ands.remove(0)),
+        _ => OrExpression::OrExpression(ands),
+    })
+}
+
+fn term() -> impl Strategy<Value = Term> + Clone {
+    let leaf = prop_oneof![
+        compare().prop_map(Atom::Compare),
+        filter().prop_map(Atom::Filter)
+    ];
+    let atom = leaf.prop_recursive(3, 16, 4, |atom| {
+        or_expression(atom_to_term(atom)).prop_map(|e| Atom::Parenthesis(Box::new(e)))
+    });
+    atom_to_term(atom)
+}
+
+fn atom_to_term(atom: impl Strategy<Value = Atom> + Clone) -> impl Strategy<Value = Term> + Clone {
+    (any::<bool>(), atom).prop_map(|(not, atom)| match not {
+        true => Term::Not(atom),
+        false => Term::Atom(atom),
+    })
+}
+
+fn from_offset() -> impl Strategy<Value = FromOffset> + Clone {
+    prop_oneof![
+        Just(FromOffset::Beginning),
+        Just(FromOffset::End),
+        (-1000i64..1_000_000).prop_map(FromOffset::Offset),
+        (0i64..1_000_000).prop_map(FromOffset::OffsetTail),
+        (0i64..4_102_444_800_000).prop_map(FromOffset::Timestamp),
+        string().prop_map(FromOffset::Group),
+    ]
+}
+
+fn order_by() -> impl Strategy<Value = OrderBy> + Clone {
+    let order = prop_oneof![
+        Just(Order::Timestamp),
+        Just(Order::Key),
+        proptest::option::of(json_path()).prop_map(Order::Value),
+        header_name().prop_map(Order::Header),
+        Just(Order::Partition),
+        Just(Order::Offset),
+        Just(Order::Size),
+        Just(Order::Topic),
+    ];
+    let keyword = prop_oneof![Just(OrderKeyword::Asc), Just(OrderKeyword::Desc)];
+    proptest::collection::vec(
+        (order, keyword).prop_map(|(order, keyword)| SortKey::new(order, keyword)),
+        0..3,
+    )
+    .prop_map(|keys| OrderBy { keys })
+}
+
+fn group_by() -> impl Strategy<Value = GroupBy> + Clone {
+    let key = prop_oneof![
+        Just(Symbol::Topic),
+        Just(Symbol::Partition),
+        Just(Symbol::Key),
+        proptest::option::of(json_path()).prop_map(Symbol::Value),
+        header_name().prop_map(Symbol::Header),
+    ];
+    let numeric = prop_oneof![
+        proptest::option::of(json_path()).prop_map(Symbol::Value),
+        Just(Symbol::Offset),
+        Just(Symbol::Size),
+        Just(Symbol::Timestamp),
+        Just(Symbol::Partition),
+    ];
+    let aggregate = prop_oneof![
+        Just(Aggregate::Count),
+        numeric.clone().prop_map(Aggregate::Min),
+        numeric.prop_map(Aggregate::Max),
+    ];
+    (key, aggregate, proptest::option::of(0usize..100))
+        .prop_map(|(key, aggregate, top)| GroupBy::new(key, aggregate, top))
+}
+
+fn search_query() -> impl Strategy<Value = SearchQuery> + Clone {
+    (
+        prop_oneof![
+            Just(OrExpression::OrExpression(vec![])),
+            or_expression(term())
+        ],
+        proptest::option::of(0usize..10_000),
+        proptest::option::of(from_offset()),
+        order_by(),
+        proptest::option::of(group_by()),
+        any::<bool>(),
+        proptest::option::of(sample()),
+    )
+        .prop_map(
+            |(expression, limit, from, order_by, group_by, distinct_key, sample)| SearchQuery {
+                expression,
+                limit,
+                from,
+                order_by,
+                group_by,
+                distinct_key,
+                sample,
+            },
+        )
+}
+
+fn sample() -> impl Strategy<Value = Sample> + Clone {
+    prop_oneof![
+        (0u32..=1_000_000).prop_map(Sample::Rate),
+        (0usize..100_000).prop_map(Sample::PerPartition),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn test_formatted_query_is_parsed_back(query in search_query()) {
+        let formatted = query.to_string();
+        let parsed = parse_search_query(&formatted).map(|(_, q)| q).map_err(|e| e.to_string());
+        prop_assert_eq!(parsed, Ok(query), "{}", formatted);
+    }
+
+    #[test]
+    fn test_format_is_stable(query in search_query()) {
+        let formatted = query.to_string();
+        let (_, parsed) = parse_search_query(&formatted).unwrap();
+        prop_assert_eq!(parsed.to_string(), formatted);
+    }
+
+    #[test]
+    fn test_json_ast_is_deserialized_back(query in search_query()) {
+        let json = serde_json::to_string(&query).unwrap();
+        prop_assert_eq!(serde_json::from_str::<SearchQuery>(&json).unwrap(), query);
+    }
+}
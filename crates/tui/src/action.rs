@@ -1,4 +1,4 @@
-use app::search::ValidSearchQuery;
+use app::search::{ValidSearchQuery, highlight::Highlight};
 use std::collections::HashSet;
 
 use lib::{KafkaRecord, TopicDetail, kafka::SchemaId, search::OrderBy};
@@ -25,8 +25,12 @@ pub(crate) enum Action {
     Notification(Notification),
     /// Request the UI to start searching for kafka records
     Search(ValidSearchQuery),
-    ///  notification to the UI
-    ShowRecord(KafkaRecord),
+    /// Request the UI to only keep the records already loaded that match the search query
+    Refine(String, ValidSearchQuery),
+    /// Request the UI to restore the records as they were before the last refinement
+    UndoRefine,
+    /// Request the UI to show the record, with the locations where it matches the search query
+    ShowRecord(KafkaRecord, Vec<Highlight>),
     /// Request the app to setup a new kafka consumer
     NewConsumer(),
     /// Request the app to start consuming
@@ -59,8 +63,12 @@ pub(crate) enum Action {
     TopicDetails(Vec<TopicDetail>),
     /// Notify the UI that the user typed a new search query
     NewSearchPrompt(String),
+    /// Request the search bar to be filled with the given input
+    FillSearchPrompt(String),
     /// Notify the progress bar an estimate of the kafka records to consume in total according to the search query
     RecordsToRead(usize),
+    /// Notify the app that the wasm module of the given search filter has been rebuilt or removed
+    FilterChanged(String),
 }
 
 /// A notification is a message displayed at the bottom-right corner of the TUI.
@@ -1,4 +1,5 @@
 //! The footer component displays contextual information: the current cluster, shortcuts and the last notifications
+use app::search::filter::filter_errors;
 use crossterm::event::KeyEvent;
 
 use ratatui::{
@@ -25,6 +26,8 @@ pub struct FooterComponent {
     action_tx: Option<UnboundedSender<Action>>,
     ticks: u64,
     show_shortcuts: bool,
+    /// Number of errors of each search filter since the search started
+    filter_errors: Vec<(String, usize)>,
 }
 
 impl FooterComponent {
@@ -85,6 +88,7 @@ impl Component for FooterComponent {
                 self.notification = Some(notification)
             }
             Action::Tick => {
+                self.filter_errors = filter_errors();
                 self.ticks += 1;
                 if self.ticks > 20 {
                     self.notification = None;
@@ -135,6 +139,15 @@ impl Component for FooterComponent {
         }
 
         help.push(Span::from("  "));
+        for (filter, count) in &self.filter_errors {
+            help.push(
+                format!(" {} failed {} times ", filter, count)
+                    .bg(state.theme.red)
+                    .fg(state.theme.white)
+                    .bold(),
+            );
+            help.push(Span::from("  "));
+        }
         if self.show_shortcuts {
             help.extend(self.generate_shortcuts(state));
         }
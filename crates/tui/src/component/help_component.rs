@@ -16,7 +16,7 @@ use super::{
     scroll_state::ScrollState,
 };
 
-const HELP_HEIGHT: usize = 42;
+const HELP_HEIGHT: usize = 46;
 const TEN_MINUTES_FRAME: usize = 30 * 60 * 10;
 const REPOSITORY_URL: &str = concat!(
     "      https://github.com/MAIF/yozefu/tree/v",
@@ -77,6 +77,9 @@ impl Component for HelpComponent {
             Line::from("                                                           ESC      Close the window/app"),
             Line::from("                                                           TAB      Focus next window"),
             Line::from("                                                   SHIFT + TAB      Focus previous window"),
+            Line::from("                                                      CTRL + S      Browse saved queries, invoked with @name(parameter=value)"),
+            Line::from("                                                  | expression      Refine the records already loaded, without consuming the topics again"),
+            Line::from("                                                      CTRL + Z      Undo the last refinement"),
             Line::from(""),
 
             Line::from("                                                      Variable      Type                        Alias       Description").bold(),
@@ -101,6 +104,7 @@ impl Component for HelpComponent {
             Line::from("                                                         limit      limit <number>                          Limit the number of kafka records to receive"),
             Line::from("                                                          from      from <begin|end|date|offset>            Start consuming records from the beginning, the end or a date"),
             Line::from("                                                      order by      order by <var> <asc|desc>               Sort kafka records"),
+            Line::from("                                                        sample      sample <n>% | sample <n> per partition  Keep a representative slice of the records"),
             Line::from(""),
 
             Line::from("                                                         Input      Description").bold(),
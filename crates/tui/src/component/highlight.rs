@@ -0,0 +1,34 @@
+//! Renders the matches of the search query, see [`app::search::highlight`].
+
+use std::ops::Range;
+
+use ratatui::{
+    style::Style,
+    text::{Line, Span, Text},
+};
+
+/// Splits the text into lines, the byte ranges of `spans` are rendered with the given style.
+/// `spans` must be sorted and must not overlap.
+pub(crate) fn highlighted_text(text: &str, spans: &[Range<usize>], style: Style) -> Text<'static> {
+    let mut lines = vec![];
+    let mut start = 0;
+    for line in text.split('\n') {
+        let end = start + line.len();
+        let mut spans_of_line = vec![];
+        let mut position = start;
+        for span in spans.iter().filter(|s| s.start < end && s.end > start) {
+            let (from, to) = (span.start.max(start), span.end.min(end));
+            if from > position {
+                spans_of_line.push(Span::raw(text[position..from].to_string()));
+            }
+            spans_of_line.push(Span::styled(text[from..to].to_string(), style));
+            position = to;
+        }
+        if position < end {
+            spans_of_line.push(Span::raw(text[position..end].to_string()));
+        }
+        lines.push(Line::from(spans_of_line));
+        start = end + 1;
+    }
+    Text::from(lines)
+}
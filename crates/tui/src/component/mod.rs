@@ -1,10 +1,12 @@
 mod footer_component;
 mod help_component;
+mod highlight;
 mod issue_component;
 mod progress_bar_component;
 mod record_details_component;
 mod records_component;
 mod root_component;
+mod saved_queries_component;
 mod schemas_component;
 mod scroll_state;
 mod search_component;
@@ -41,6 +43,7 @@ pub(crate) type ConcurrentRecordsBuffer = LazyLock<Arc<Mutex<RecordsBuffer>>>;
 static BUFFER: ConcurrentRecordsBuffer =
     LazyLock::new(|| Arc::new(Mutex::new(RecordsBuffer::new())));
 
+#[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
 pub(crate) enum FocusDirection {
     Top,
@@ -49,9 +52,10 @@ pub(crate) enum FocusDirection {
     Bottom,
 }
 
-#[derive(Debug, Clone, Display, Hash, PartialEq, Eq, Deserialize, PartialOrd, Ord)]
+#[derive(Debug, Clone, Display, Hash, PartialEq, Eq, Deserialize, PartialOrd, Ord, Default)]
 pub(crate) enum ComponentName {
     Records,
+    #[default]
     Topics,
     Footer,
     RecordDetails,
@@ -63,6 +67,7 @@ pub(crate) enum ComponentName {
     Dialog,
     Help,
     Schemas,
+    SavedQueries,
 }
 
 impl ComponentName {
@@ -70,17 +75,12 @@ impl ComponentName {
         match &self {
             ComponentName::RecordDetails => "Record".to_string(),
             ComponentName::TopicDetails => "Topic".to_string(),
+            ComponentName::SavedQueries => "Queries".to_string(),
             _ => self.to_string(),
         }
     }
 }
 
-impl Default for ComponentName {
-    fn default() -> Self {
-        Self::Topics
-    }
-}
-
 pub(crate) trait WithHeight: Component {
     fn content_height(&self) -> usize {
         0
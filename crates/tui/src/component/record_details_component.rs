@@ -1,4 +1,5 @@
 //! Component showing all the details of a given kafka record.
+use app::search::highlight::{Field, Highlight, spans_of};
 use bytesize::ByteSize;
 use crossterm::event::{KeyCode, KeyEvent};
 
@@ -8,18 +9,24 @@ use ratatui::{
     Frame,
     layout::Rect,
     style::{Style, Stylize},
-    text::{Line, Span, Text},
+    text::{Line, Span},
     widgets::{Block, Borders, Clear, Padding, Paragraph, Wrap},
 };
 use tokio::sync::mpsc::UnboundedSender;
 
 use crate::{Action, error::TuiError};
 
-use super::{Component, ComponentName, Shortcut, State, scroll_state::ScrollState};
+use super::{
+    Component, ComponentName, Shortcut, State, highlight::highlighted_text,
+    scroll_state::ScrollState,
+};
 
 #[derive(Default)]
 pub(crate) struct RecordDetailsComponent<'a> {
     record: Option<KafkaRecord>,
+    /// Where the record matches the search query
+    highlights: Vec<Highlight>,
+    highlight_style: Style,
     lines: Vec<Line<'a>>,
     search_query: String,
     scroll: ScrollState,
@@ -27,8 +34,11 @@ pub(crate) struct RecordDetailsComponent<'a> {
 }
 
 impl RecordDetailsComponent<'_> {
-    pub fn new(_state: &State) -> Self {
+    pub fn new(state: &State) -> Self {
         Self {
+            highlight_style: Style::default()
+                .bg(state.theme.bg_highlight)
+                .fg(state.theme.fg_highlight),
             ..Default::default()
         }
     }
@@ -43,6 +53,25 @@ impl RecordDetailsComponent<'_> {
         ])
     }
 
+    /// Same as [`Self::generate_span`], the matches of the search query are highlighted.
+    fn generate_highlighted_span<'b>(
+        &self,
+        key: &str,
+        value: &str,
+        field: &Field,
+        style: Style,
+    ) -> Line<'b> {
+        let spans = spans_of(&self.highlights, field, value);
+        let mut line = Line::from(Span::styled(
+            format!("{:>12}: ", key),
+            Style::default().bold(),
+        ));
+        for l in highlighted_text(value, &spans, style).lines {
+            line.spans.extend(l.spans);
+        }
+        line
+    }
+
     fn show_schema(&mut self) -> Result<(), TuiError> {
         if self.record.as_ref().map(|r| r.has_schemas()) == Some(false) {
             return Ok(());
@@ -70,10 +99,11 @@ impl RecordDetailsComponent<'_> {
             self.record = Some(KafkaRecord::default());
         }
 
+        let style = self.highlight_style;
         let record = self.record.as_ref().unwrap();
         let mut to_render = vec![
             Line::default(),
-            Self::generate_span("Topic", record.topic.clone()),
+            self.generate_highlighted_span("Topic", &record.topic, &Field::Topic, style),
             Self::generate_span(
                 "Timestamp",
                 self.record
@@ -119,31 +149,33 @@ impl RecordDetailsComponent<'_> {
             .enumerate()
         {
             let e = entry.1;
-            match entry.0 {
-                0 => formatted_headers.push(Span::styled(
+            let mut header = vec![match entry.0 {
+                0 => Span::styled(
                     format!("{: <width$}", e.0, width = longest_header_key),
                     Style::default().italic(),
-                )),
-                _ => formatted_headers.push(Span::styled(
+                ),
+                _ => Span::styled(
                     format!("              {: <width$}", e.0, width = longest_header_key),
                     Style::default().italic(),
-                )),
-            };
-            formatted_headers.push(Span::styled(" : ", Style::default()));
-            formatted_headers.push(Span::styled(e.1.to_string(), Style::default()));
+                ),
+            }];
+            header.push(Span::styled(" : ", Style::default()));
+            let spans = spans_of(&self.highlights, &Field::Header(e.0.clone()), e.1);
+            for line in highlighted_text(e.1, &spans, style).lines {
+                header.extend(line.spans);
+            }
+            formatted_headers.push(header);
         }
 
-        if !formatted_headers.is_empty() {
-            let first = formatted_headers.iter().take(3).collect_vec();
+        let mut formatted_headers = formatted_headers.into_iter();
+        if let Some(first) = formatted_headers.next() {
             let line: &mut Line<'_> = to_render.last_mut().unwrap();
             line.spans.remove(1);
-            for span in first {
-                line.push_span(span.clone());
-            }
+            line.spans.extend(first);
         }
 
-        for ppp in &formatted_headers.into_iter().skip(3).chunks(3) {
-            to_render.push(Line::from(ppp.collect_vec()));
+        for header in formatted_headers {
+            to_render.push(Line::from(header));
         }
 
         if let Some(s) = &record.key_schema {
@@ -166,7 +198,7 @@ impl RecordDetailsComponent<'_> {
         }
 
         to_render.extend(vec![
-            Self::generate_span("Key", record.key_as_string.clone()),
+            self.generate_highlighted_span("Key", &record.key_as_string, &Field::Key, style),
             Self::generate_span("Value", "".to_string()),
         ]);
 
@@ -181,8 +213,9 @@ impl RecordDetailsComponent<'_> {
         //    let output = escaped.into_text().unwrap();
         //    payload_lines.extend(output.lines);
         //}
-        let text = Text::from(record.value.to_string_pretty());
-        to_render.extend(text.lines);
+        let value = record.value.to_string_pretty();
+        let spans = spans_of(&self.highlights, &Field::Value, &value);
+        to_render.extend(highlighted_text(&value, &spans, style).lines);
 
         self.lines = to_render;
         self.scroll.reset();
@@ -249,8 +282,9 @@ impl Component for RecordDetailsComponent<'_> {
 
     fn update(&mut self, action: Action) -> Result<Option<Action>, TuiError> {
         match action {
-            Action::ShowRecord(record) => {
+            Action::ShowRecord(record, highlights) => {
                 self.record = Some(record);
+                self.highlights = highlights;
                 self.compute_record_rendering();
             }
             Action::Search(e) => self.search_query = e.query().to_string(),
@@ -1,5 +1,9 @@
 //! Component showing in real time incoming kafka records.
-use app::search::ValidSearchQuery;
+use app::search::{
+    ValidSearchQuery,
+    aggregation::Aggregator,
+    highlight::{Field, spans_of},
+};
 use copypasta::{ClipboardContext, ClipboardProvider};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use itertools::Itertools;
@@ -18,7 +22,9 @@ use tokio::sync::watch::Receiver;
 
 use crate::{Action, action::Notification, error::TuiError, records_buffer::BufferAction};
 
-use super::{Component, ComponentName, ConcurrentRecordsBuffer, Shortcut, State};
+use super::{
+    Component, ComponentName, ConcurrentRecordsBuffer, Shortcut, State, highlight::highlighted_text,
+};
 
 pub(crate) struct RecordsComponent<'a> {
     records: &'a ConcurrentRecordsBuffer,
@@ -82,8 +88,7 @@ impl<'a> RecordsComponent<'a> {
     fn shorten_topic(topic: &str) -> String {
         let t = topic.replace('_', "");
         let parts = t.split('.');
-        let e = parts.map(|e| e.chars().next().unwrap_or('_')).join(".");
-        e
+        parts.map(|e| e.chars().next().unwrap_or('_')).join(".")
     }
 
     fn show_details(&mut self) -> Result<(), TuiError> {
@@ -99,11 +104,16 @@ impl<'a> RecordsComponent<'a> {
 
     fn set_event_dialog(&mut self) -> Result<(), TuiError> {
         if let Some(s) = self.state.selected() {
-            let record = self.records.lock().unwrap().get(s).unwrap().clone();
+            let (record, highlights) = {
+                let records = self.records.lock().unwrap();
+                let record = records.get(s).unwrap().clone();
+                let highlights = records.highlights(&record).to_vec();
+                (record, highlights)
+            };
             self.action_tx
                 .as_ref()
                 .unwrap()
-                .send(Action::ShowRecord(record))?;
+                .send(Action::ShowRecord(record, highlights))?;
         }
         Ok(())
     }
@@ -200,6 +210,42 @@ impl<'a> RecordsComponent<'a> {
         Ok(())
     }
 
+    /// Table of the groups when the search query has a `group by` clause.
+    fn aggregation_table(aggregator: &Aggregator) -> Table<'static> {
+        let header = Row::new(
+            aggregator
+                .columns()
+                .into_iter()
+                .enumerate()
+                .map(|(i, c)| match i {
+                    0 => Cell::new(Text::from(c)),
+                    _ => Cell::new(Text::from(c).alignment(Alignment::Right)),
+                }),
+        )
+        .height(1)
+        .bottom_margin(1);
+        let rows = aggregator.rows().iter().map(|row| {
+            Row::new(
+                row.cells(aggregator.group_by())
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, c)| match i {
+                        0 => Cell::new(Text::from(c)),
+                        _ => Cell::new(Text::from(c).alignment(Alignment::Right)),
+                    }),
+            )
+        });
+        Table::new(
+            rows,
+            [
+                Constraint::Percentage(100),
+                Constraint::Min(12),
+                Constraint::Min(24),
+            ],
+        )
+        .header(header)
+    }
+
     fn truncate_value(value: &str, rect: &Rect) -> String {
         let split_at = (rect.width.checked_sub(70)).unwrap_or(3) as usize;
         match value.len() > split_at {
@@ -309,6 +355,7 @@ impl Component for RecordsComponent<'_> {
                 self.state.select(None);
                 self.search_query = search_query;
             }
+            Action::Refine(_, _) | Action::UndoRefine => self.state.select(None),
             _ => (),
         };
         Ok(None)
@@ -316,10 +363,20 @@ impl Component for RecordsComponent<'_> {
 
     fn draw(&mut self, f: &mut Frame<'_>, rect: Rect, state: &State) -> Result<(), TuiError> {
         let focused = state.is_focused(self.id());
+        let (aggregation, refinements) = {
+            let mut records = self.records.lock().unwrap();
+            (
+                records.aggregator().map(Self::aggregation_table),
+                records.refinements().map(|r| format!("| {} ", r)).join(""),
+            )
+        };
         let block = Block::default()
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)
-            .title(" Records ");
+            .title(match aggregation.is_some() {
+                true => " Aggregation ".to_string(),
+                false => format!(" Records {}", refinements),
+            });
 
         let block = self.make_block_focused_with_state(state, block);
 
@@ -336,17 +393,24 @@ impl Component for RecordsComponent<'_> {
             .style(normal_style)
             .height(1)
             .bottom_margin(1);
-        let r = self.records.lock().unwrap();
+        let records = self.records;
+        let r = records.lock().unwrap();
+        let highlight_style = Style::default()
+            .bg(state.theme.bg_highlight)
+            .fg(state.theme.fg_highlight);
+        let selected = self.state.selected();
 
         // TODO render only records in the viewport
         let rows = r.iter().enumerate().map(|(index, item)| {
-            if let Some(s) = self.state.selected() {
+            if let Some(s) = selected {
                 let is_visible = (s + rect.height as usize) > index
                     && s.saturating_sub(rect.height as usize) <= index;
                 if !is_visible {
                     return Row::new(Vec::<Cell>::new()).height(1_u16);
                 }
             }
+            let highlights = r.highlights(item);
+            let value = Self::truncate_value(&item.value_as_string, &rect);
             let cells = vec![
                 Cell::new(Text::from(
                     item.timestamp_as_local_date_time()
@@ -356,11 +420,23 @@ impl Component for RecordsComponent<'_> {
                 Cell::new(Text::from(item.offset.to_string()).alignment(Alignment::Right)),
                 Cell::new(Text::from(item.partition.to_string()).alignment(Alignment::Right)),
                 Cell::new(Text::from(Self::shorten_topic(&item.topic)).alignment(Alignment::Right)),
-                Cell::new(Text::from(item.key_as_string.to_string()).alignment(Alignment::Right)),
-                Cell::new(Text::from(Self::truncate_value(
-                    &item.value_as_string,
-                    &rect,
-                ))),
+                Cell::new(
+                    highlighted_text(
+                        &item.key_as_string,
+                        &spans_of(highlights, &Field::Key, &item.key_as_string),
+                        highlight_style,
+                    )
+                    .alignment(Alignment::Right),
+                ),
+                Cell::new(highlighted_text(
+                    &value,
+                    &spans_of(highlights, &Field::Value, &item.value_as_string)
+                        .into_iter()
+                        .filter(|s| s.start < value.len())
+                        .map(|s| s.start..s.end.min(value.len()))
+                        .collect_vec(),
+                    highlight_style,
+                )),
             ];
             Row::new(cells).height(1_u16)
         });
@@ -397,7 +473,10 @@ impl Component for RecordsComponent<'_> {
         let inner = block.inner(rect);
         f.render_widget(block, rect);
 
-        f.render_stateful_widget(table, inner, &mut self.state);
+        match aggregation {
+            Some(aggregation) => f.render_widget(aggregation, inner),
+            None => f.render_stateful_widget(table, inner, &mut self.state),
+        }
         let metrics_area = Rect::new(
             inner
                 .right()
@@ -436,7 +515,7 @@ impl Component for RecordsComponent<'_> {
     }
 
     fn shortcuts(&self) -> Vec<Shortcut> {
-        let shortcuts = vec![
+        let mut shortcuts = vec![
             Shortcut::new("C", "Copy"),
             Shortcut::new("O", "Open"),
             // Shortcut::new("[", "First record"),
@@ -451,6 +530,9 @@ impl Component for RecordsComponent<'_> {
             ),
             //Shortcut::new("↑↓", "Scroll"),
         ];
+        if self.records.lock().unwrap().refinements().next().is_some() {
+            shortcuts.push(Shortcut::new("CTRL + Z", "Undo refine"));
+        }
 
         shortcuts
     }
@@ -22,8 +22,8 @@ use super::{
     Component, ComponentName, ConcurrentRecordsBuffer, State, footer_component::FooterComponent,
     help_component::HelpComponent, progress_bar_component::ProgressBarComponent,
     record_details_component::RecordDetailsComponent, records_component::RecordsComponent,
-    schemas_component::SchemasComponent, search_component::SearchComponent,
-    topic_details_component::TopicDetailsComponent,
+    saved_queries_component::SavedQueriesComponent, schemas_component::SchemasComponent,
+    search_component::SearchComponent, topic_details_component::TopicDetailsComponent,
     topics_and_records_component::TopicsAndRecordsComponent, topics_component::TopicsComponent,
 };
 
@@ -51,7 +51,7 @@ impl RootComponent {
         let mut footer = FooterComponent::default();
         footer.show_shortcuts(config.show_shortcuts);
 
-        let mut components: [Arc<Mutex<dyn Component>>; 9] = [
+        let mut components: [Arc<Mutex<dyn Component>>; 10] = [
             Arc::new(Mutex::new(TopicsComponent::new(selected_topics))),
             Arc::new(Mutex::new(RecordsComponent::new(records))),
             Arc::new(Mutex::new(TopicDetailsComponent::default())),
@@ -59,11 +59,15 @@ impl RootComponent {
             Arc::new(Mutex::new(SearchComponent::new(
                 &query,
                 config.history.clone(),
+                config.queries.clone(),
                 config.filters_dir(),
             ))),
             Arc::new(Mutex::new(footer)),
             Arc::new(Mutex::new(HelpComponent::default())),
             Arc::new(Mutex::new(SchemasComponent::new())),
+            Arc::new(Mutex::new(SavedQueriesComponent::new(
+                config.queries.clone(),
+            ))),
             Arc::new(Mutex::new(FooterComponent::default())),
         ];
 
@@ -271,6 +275,14 @@ impl Component for RootComponent {
                 self.toggle_view(ComponentName::Help)?;
                 return Ok(None);
             }
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.toggle_view(ComponentName::SavedQueries)?;
+                return Ok(None);
+            }
+            KeyCode::Char('z') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.action_tx.as_ref().unwrap().send(Action::UndoRefine)?;
+                return Ok(None);
+            }
             KeyCode::Esc => self.close(),
             _ => (),
         };
@@ -326,6 +338,12 @@ impl Component for RootComponent {
                 }?;
                 self.notify_footer()?;
             }
+            Action::FillSearchPrompt(_) => {
+                if self.views.last() == Some(&ComponentName::SavedQueries) {
+                    self.close();
+                }
+                self.focus(ComponentName::Search)?;
+            }
             Action::RecordsToRead(length) => {
                 self.progress_bar.set_length(length);
             }
@@ -429,6 +447,7 @@ pub fn focus_order_of(component: &ComponentName) -> Vec<ComponentName> {
         ],
         ComponentName::TopicDetails => vec![ComponentName::TopicDetails, ComponentName::Search],
         ComponentName::Help => vec![ComponentName::Help, ComponentName::Search],
+        ComponentName::SavedQueries => vec![ComponentName::SavedQueries, ComponentName::Search],
         _ => vec![],
     }
 }
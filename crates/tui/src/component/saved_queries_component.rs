@@ -0,0 +1,148 @@
+//! Component listing the saved queries of the configuration.
+//! Pressing enter fills the search bar with the invocation of the selected query.
+
+use app::configuration::SavedQuery;
+use crossterm::event::{KeyCode, KeyEvent};
+use indexmap::IndexMap;
+use itertools::Itertools;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Style, Stylize},
+    text::{Line, Text},
+    widgets::{
+        Block, BorderType, Borders, Cell, Clear, Padding, Paragraph, Row, Table, TableState, Wrap,
+    },
+};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::{Action, error::TuiError};
+
+use super::{Component, ComponentName, Shortcut, State};
+
+#[derive(Default)]
+pub(crate) struct SavedQueriesComponent {
+    queries: IndexMap<String, SavedQuery>,
+    state: TableState,
+    action_tx: Option<UnboundedSender<Action>>,
+}
+
+impl SavedQueriesComponent {
+    pub fn new(queries: IndexMap<String, SavedQuery>) -> Self {
+        let mut state = TableState::default();
+        if !queries.is_empty() {
+            state.select(Some(0));
+        }
+        Self {
+            queries,
+            state,
+            ..Self::default()
+        }
+    }
+
+    fn select(&mut self, index: usize) {
+        match self.queries.is_empty() {
+            true => self.state.select(None),
+            false => self.state.select(Some(index.min(self.queries.len() - 1))),
+        }
+    }
+}
+
+impl Component for SavedQueriesComponent {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) {
+        self.action_tx = Some(tx);
+    }
+
+    fn id(&self) -> ComponentName {
+        ComponentName::SavedQueries
+    }
+
+    fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>, TuiError> {
+        let selected = self.state.selected().unwrap_or_default();
+        match key.code {
+            KeyCode::Char('k') | KeyCode::Down => self.select(selected + 1),
+            KeyCode::Char('j') | KeyCode::Up => self.select(selected.saturating_sub(1)),
+            KeyCode::Char('[') => self.select(0),
+            KeyCode::Char(']') => self.select(usize::MAX),
+            KeyCode::Enter => {
+                if let Some((name, query)) = self.queries.get_index(selected) {
+                    self.action_tx
+                        .as_ref()
+                        .unwrap()
+                        .send(Action::FillSearchPrompt(query.invocation(name)))?;
+                }
+            }
+            _ => (),
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect, state: &State) -> Result<(), TuiError> {
+        f.render_widget(Clear, rect);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .padding(Padding::proportional(1))
+            .border_type(BorderType::Rounded)
+            .title(" Saved queries ");
+        let block = self.make_block_focused_with_state(state, block);
+        let inner = block.inner(rect);
+        f.render_widget(block, rect);
+
+        if self.queries.is_empty() {
+            f.render_widget(
+                Paragraph::new(format!(
+                    "No saved queries. Add them under the 'queries' property of '{}'.",
+                    state.configuration_file.display()
+                ))
+                .wrap(Wrap { trim: false }),
+                inner,
+            );
+            return Ok(());
+        }
+
+        let [table_area, query_area] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Fill(1), Constraint::Length(5)])
+            .areas(inner);
+
+        let rows = self.queries.iter().map(|(name, query)| {
+            Row::new(vec![
+                Cell::new(name.clone()),
+                Cell::new(query.parameter_names().join(", ")),
+                Cell::new(query.description.clone().unwrap_or_default()),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(32),
+                Constraint::Length(32),
+                Constraint::Fill(1),
+            ],
+        )
+        .column_spacing(2)
+        .header(Row::new(vec!["Name", "Parameters", "Description"]).bold())
+        .row_highlight_style(
+            Style::default()
+                .bg(state.theme.bg_focused_selected)
+                .fg(state.theme.fg_focused_selected)
+                .bold(),
+        );
+        f.render_stateful_widget(table, table_area, &mut self.state);
+
+        if let Some((_, query)) = self
+            .state
+            .selected()
+            .and_then(|i| self.queries.get_index(i))
+        {
+            let mut text = Text::from(Line::from("Query").bold());
+            text.extend(query.query.lines().map(Line::from).collect_vec());
+            f.render_widget(Paragraph::new(text).wrap(Wrap { trim: false }), query_area);
+        }
+        Ok(())
+    }
+
+    fn shortcuts(&self) -> Vec<Shortcut> {
+        vec![Shortcut::new("ENTER", "Use query")]
+    }
+}
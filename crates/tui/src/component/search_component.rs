@@ -2,13 +2,28 @@
 //! It comes with the following features:
 //!  - all queries are stored into a history.
 //!  - The component suggests queries based on your history.
+//!  - Invalid queries are underlined, with a caret and the reason under the offending token.
+//!  - `@name(parameter=value)` invokes a saved query of the configuration.
+//!  - `| expression` refines the records already loaded instead of consuming the topics again.
+//!  - The names of the installed search filters are autocompleted, their parameters are shown under the search bar.
 
-use std::{path::PathBuf, time::Duration};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
-use app::search::ValidSearchQuery;
+use app::{
+    configuration::{SavedQuery, resolve_query},
+    search::{
+        ValidSearchQuery,
+        catalog::{FilterInfo, installed_filters},
+    },
+};
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use indexmap::IndexMap;
 use itertools::Itertools;
-use lib::{Error, error::SearchError};
+use lib::{Error, search::diagnostic::Diagnostic};
 use log::error;
 use ratatui::{
     Frame,
@@ -33,21 +48,33 @@ pub(crate) struct SearchComponent {
     input: Input,
     index_history: usize,
     history: Vec<String>,
+    queries: IndexMap<String, SavedQuery>,
     compiler_worker: CancellationToken,
     filters_directory: PathBuf,
-    remaining_input: Option<String>,
+    /// Why the last submitted query is invalid.
+    diagnostic: Option<Diagnostic>,
     action_tx: Option<UnboundedSender<Action>>,
     autocomplete: Option<String>,
+    /// Installed search filters, loaded in the background
+    filters: Arc<Mutex<Vec<FilterInfo>>>,
+    /// Signature of the search filter whose parameters are being typed, and the column of its name
+    filter_hint: Option<(usize, String)>,
     // A hack to detect copy-paste events and replace \n with a space
     entered: Option<Instant>,
 }
 
 impl SearchComponent {
-    pub fn new(input: &str, history: Vec<String>, filters_directory: PathBuf) -> Self {
+    pub fn new(
+        input: &str,
+        history: Vec<String>,
+        queries: IndexMap<String, SavedQuery>,
+        filters_directory: PathBuf,
+    ) -> Self {
         Self {
             input: Input::from(input),
             index_history: history.len() - 1,
             history,
+            queries,
             filters_directory,
             ..Self::default()
         }
@@ -58,6 +85,7 @@ impl SearchComponent {
         let tt = self.action_tx.clone();
 
         let filters_dir = self.filters_directory.clone();
+        let queries = self.queries.clone();
         self.compiler_worker.cancel();
         self.compiler_worker = CancellationToken::new();
         let token = self.compiler_worker.clone();
@@ -66,9 +94,10 @@ impl SearchComponent {
                 _ = token.cancelled() => {  },
                 _ = tokio::time::sleep(Duration::from_millis(700)) => {
                     if input.len() > 5 {
-                        if let Err(e) = ValidSearchQuery::from(&input, &filters_dir) {
+                        let input = input.trim_start().strip_prefix('|').unwrap_or(&input);
+                        if let Err(e) = resolve_query(input, &queries).and_then(|q| ValidSearchQuery::from(&q, &filters_dir)) {
                             error!("{}", e);
-                            tt.as_ref().unwrap().send(Action::Notification(Notification::new(log::Level::Error, e.to_string()))).unwrap();
+                            tt.as_ref().unwrap().send(Action::Notification(Notification::new(log::Level::Error, Self::error_message(&e)))).unwrap();
                         }
                     }
                  }
@@ -76,6 +105,19 @@ impl SearchComponent {
         });
     }
 
+    /// Loads the installed search filters in the background, for autocompletion and hints.
+    fn load_filters(&self) {
+        let filters = self.filters.clone();
+        let directory = self.filters_directory.clone();
+        tokio::task::spawn_blocking(move || {
+            let loaded = installed_filters(&directory)
+                .iter()
+                .filter_map(|name| FilterInfo::load(&directory, name).ok())
+                .collect();
+            *filters.lock().unwrap() = loaded;
+        });
+    }
+
     fn autocomplete(&mut self, keycode: KeyCode) {
         let prompt = self.input.value();
 
@@ -102,7 +144,59 @@ impl SearchComponent {
         };
         self.autocomplete = possibilities
             .get(self.index_history)
-            .map(|e| e.split_at(prompt.len()).1.to_string());
+            .map(|e| e.split_at(prompt.len()).1.to_string())
+            .or_else(|| self.complete_filter_name());
+        self.filter_hint = self.filter_hint();
+    }
+
+    /// Completes the name of a search filter at the end of the prompt.
+    fn complete_filter_name(&self) -> Option<String> {
+        let prompt = self.input.value();
+        let word = prompt
+            .rsplit(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+            .next()
+            .filter(|w| !w.is_empty())?;
+        let filters = self.filters.lock().unwrap();
+        filters
+            .iter()
+            .find(|f| f.name.starts_with(word) && f.name != word)
+            .map(|f| format!("{}(", &f.name[word.len()..]))
+    }
+
+    /// Returns the signature of the search filter whose parentheses are open at the end of the prompt.
+    fn filter_hint(&self) -> Option<(usize, String)> {
+        let prompt = self.input.value();
+        let mut open = vec![];
+        let mut quote = None;
+        for (i, c) in prompt.char_indices() {
+            match (quote, c) {
+                (Some(q), c) if c == q => quote = None,
+                (Some(_), _) => (),
+                (None, '"' | '\'') => quote = Some(c),
+                (None, '(') => open.push(i),
+                (None, ')') => {
+                    open.pop();
+                }
+                _ => (),
+            }
+        }
+        let before = prompt[..*open.last()?].trim_end();
+        let start = before
+            .rfind(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let name = &before[start..];
+        let filters = self.filters.lock().unwrap();
+        let filter = filters.iter().find(|f| f.name == name)?;
+        let mut hint = filter.signature();
+        if let Some(description) = filter
+            .description
+            .as_ref()
+            .filter(|d| !d.description.is_empty())
+        {
+            hint.push_str(&format!(" · {}", description.description));
+        }
+        Some((prompt[..start].chars().count(), hint))
     }
 
     fn update_history(&mut self, prompt: &str) -> Result<(), TuiError> {
@@ -124,19 +218,30 @@ impl SearchComponent {
 
     fn search(&mut self) -> Result<(), TuiError> {
         let o = self.input.value().to_string();
+        let refinement = o.trim_start().strip_prefix('|').map(str::trim);
 
-        match ValidSearchQuery::from(o.as_str(), &self.filters_directory) {
+        match resolve_query(refinement.unwrap_or(&o), &self.queries)
+            .and_then(|query| ValidSearchQuery::from(&query, &self.filters_directory))
+            .and_then(|search_query| match search_query.query().clauses() {
+                clauses if refinement.is_some() && !clauses.is_empty() => {
+                    Err(Error::Error(format!(
+                        "A refinement only filters the records already loaded, remove '{}'",
+                        clauses.join(" ")
+                    )))
+                }
+                _ => Ok(search_query),
+            }) {
             Ok(search_query) => {
                 self.update_history(&o)?;
-                self.action_tx
-                    .clone()
-                    .unwrap()
-                    .send(Action::Search(search_query))?;
+                self.action_tx.clone().unwrap().send(match refinement {
+                    Some(refinement) => Action::Refine(refinement.to_string(), search_query),
+                    None => Action::Search(search_query),
+                })?;
             }
 
             Err(e) => {
-                if let Error::Search(SearchError::Parse(ee)) = &e {
-                    self.remaining_input = Some(ee.to_string());
+                if let Error::Search(ee) = &e {
+                    self.diagnostic = Some(ee.diagnostic().clone());
                 }
 
                 self.action_tx
@@ -144,19 +249,18 @@ impl SearchComponent {
                     .unwrap()
                     .send(Action::Notification(Notification::new(
                         log::Level::Error,
-                        e.to_string(),
+                        Self::error_message(&e),
                     )))?;
             }
         };
         Ok(())
     }
 
-    #[allow(dead_code)]
-    fn pretty_error_message(error: &nom::Err<nom::error::Error<&str>>) -> String {
+    /// Notifications are displayed on a single line, the caret is drawn under the search bar.
+    fn error_message(error: &Error) -> String {
         match error {
-            nom::Err::Incomplete(_) => "Cannot parse query".to_string(),
-            nom::Err::Error(s) => format!("unexpected token '{}'", s.input),
-            nom::Err::Failure(s) => format!("unexpected token '{}'", s.input),
+            Error::Search(e) => e.diagnostic().summary(),
+            e => e.to_string(),
         }
     }
 }
@@ -164,14 +268,35 @@ impl SearchComponent {
 impl Component for SearchComponent {
     fn register_action_handler(&mut self, tx: UnboundedSender<Action>) {
         self.action_tx = Some(tx);
+        self.load_filters();
     }
 
     fn id(&self) -> ComponentName {
         ComponentName::Search
     }
 
+    fn update(&mut self, action: Action) -> Result<Option<Action>, TuiError> {
+        if let Action::FilterChanged(_) = action {
+            self.load_filters();
+        }
+        if let Action::FillSearchPrompt(input) = action {
+            // The cursor is placed on the first parameter without value
+            let cursor = ["=,", "=)"]
+                .iter()
+                .filter_map(|empty| input.find(empty))
+                .min()
+                .map(|i| i + 1)
+                .unwrap_or(input.len());
+            self.input = Input::from(input).with_cursor(cursor);
+            self.diagnostic = None;
+            self.autocomplete = None;
+            self.filter_hint = None;
+        }
+        Ok(None)
+    }
+
     fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>, TuiError> {
-        self.remaining_input = None;
+        self.diagnostic = None;
         match key.code {
             KeyCode::Right => {
                 if self.input.value().len() == self.input.cursor() {
@@ -220,20 +345,19 @@ impl Component for SearchComponent {
     fn draw(&mut self, f: &mut Frame<'_>, rect: Rect, state: &State) -> Result<(), TuiError> {
         let padding = 1;
         let input: &str = self.input.value();
-        let mut line: Line = match &self.remaining_input {
-            Some(e) => {
-                let parts = input.split_at(input.len() - e.len());
-                Line::from(vec![
-                    Span::raw(parts.0),
-                    Span::styled(
-                        parts.1,
-                        Style::default()
-                            .fg(state.theme.orange)
-                            .not_bold()
-                            .underlined(),
-                    ),
-                ])
-            }
+        let diagnostic = self.diagnostic.as_ref().filter(|d| d.query == input);
+        let mut line: Line = match diagnostic {
+            Some(d) => Line::from(vec![
+                Span::raw(&input[..d.span.start]),
+                Span::styled(
+                    d.token(),
+                    Style::default()
+                        .fg(state.theme.orange)
+                        .not_bold()
+                        .underlined(),
+                ),
+                Span::raw(&input[d.span.end..]),
+            ]),
             None => Line::from(vec![input.into()]),
         };
 
@@ -278,6 +402,47 @@ impl Component for SearchComponent {
         }
         //f.render_widget(Clear, rect);
         f.render_widget(paragraph, rect);
+
+        // The caret and the reason are drawn over the bottom border, under the offending token.
+        if let Some(d) = diagnostic {
+            let column = rect.x + 2 + input[..d.span.start].chars().count() as u16;
+            let mut caret = format!(
+                "{} {}",
+                "^".repeat(d.token().chars().count().max(1)),
+                d.message
+            );
+            if let Some(hint) = &d.hint {
+                caret.push_str(&format!(", did you mean '{}'?", hint));
+            }
+            let caret_area = Rect::new(
+                column,
+                rect.y + rect.height.saturating_sub(1),
+                (rect.x + rect.width)
+                    .saturating_sub(column + 1)
+                    .min(caret.chars().count() as u16 + 1),
+                1,
+            );
+            f.render_widget(
+                Paragraph::new(format!("{} ", caret))
+                    .style(Style::default().fg(state.theme.orange)),
+                caret_area,
+            );
+        } else if let Some((column, hint)) = &self.filter_hint {
+            let column = rect.x + 2 + *column as u16;
+            let hint_area = Rect::new(
+                column,
+                rect.y + rect.height.saturating_sub(1),
+                (rect.x + rect.width)
+                    .saturating_sub(column + 1)
+                    .min(hint.chars().count() as u16 + 2),
+                1,
+            );
+            f.render_widget(
+                Paragraph::new(format!(" {} ", hint))
+                    .style(Style::default().fg(state.theme.autocomplete)),
+                hint_area,
+            );
+        }
         Ok(())
     }
 
@@ -285,6 +450,7 @@ impl Component for SearchComponent {
         vec![
             Shortcut::new("↑↓", "History"),
             Shortcut::new("ENTER", "Search"),
+            Shortcut::new("|", "Refine"),
         ]
     }
 }
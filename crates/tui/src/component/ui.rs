@@ -1,7 +1,15 @@
 //! Module gathering the code to run the terminal user interface.
 
 use app::App;
-use app::search::{Search, SearchContext};
+use app::search::{
+    Search, SearchContext, ValidSearchQuery,
+    evaluation::{Evaluation, Evaluator},
+    filter::{
+        aborted_by_filter, is_filter_loaded, load_filter, replace_filter, reset_filter_errors,
+        take_disabled_filters,
+    },
+    watcher::FilterWatcher,
+};
 use chrono::DateTime;
 use crossterm::event::KeyEvent;
 use futures::{StreamExt, TryStreamExt};
@@ -14,6 +22,7 @@ use rdkafka::consumer::{Consumer, StreamConsumer};
 use rdkafka::message::OwnedMessage;
 use std::collections::HashSet;
 use std::fs;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::sync::mpsc::{self, UnboundedSender};
 use tokio::time::Instant;
@@ -37,6 +46,43 @@ pub struct Ui {
     last_tick_key_events: Vec<KeyEvent>,
     records_sender: Option<UnboundedSender<KafkaRecord>>,
     records: &'static ConcurrentRecordsBuffer,
+    /// Reloads the search filters when their wasm module is rebuilt
+    filter_watcher: Option<FilterWatcher>,
+    /// The search whose search filters have been started
+    running_search: Option<RunningSearch>,
+}
+
+/// A search whose search filters have been started and must be ended once,
+/// by the worker when the search completes or before the next search starts.
+#[derive(Clone)]
+struct RunningSearch {
+    search_query: ValidSearchQuery,
+    ended: Arc<Mutex<bool>>,
+}
+
+impl RunningSearch {
+    fn start(search_query: &ValidSearchQuery) -> Self {
+        search_query.start_search();
+        Self {
+            search_query: search_query.clone(),
+            ended: Arc::new(Mutex::new(false)),
+        }
+    }
+
+    /// Ends the search filters and notifies their reports, unless it's already done.
+    fn end(&self, tx: &UnboundedSender<Action>) {
+        let mut ended = self.ended.lock().unwrap();
+        if *ended {
+            return;
+        }
+        *ended = true;
+        for report in self.search_query.end_search() {
+            let _ = tx.send(Action::Notification(Notification::new(
+                log::Level::Info,
+                report,
+            )));
+        }
+    }
 }
 
 impl Ui {
@@ -56,6 +102,8 @@ impl Ui {
             root: RootComponent::new(query, selected_topics, &config.global, &BUFFER, state),
             records_sender: None,
             last_tick_key_events: Vec::new(),
+            filter_watcher: None,
+            running_search: None,
         })
     }
 
@@ -94,7 +142,19 @@ impl Ui {
         tx: UnboundedSender<Action>,
     ) -> Result<(), TuiError> {
         self.worker.cancel();
-        self.records.lock().unwrap().reset();
+        // The previous search must be ended before the search filters are started again
+        if let Some(running_search) = self.running_search.take() {
+            running_search.end(&tx);
+        }
+        reset_filter_errors();
+        let running_search = RunningSearch::start(&self.app.search_query);
+        self.running_search = Some(running_search.clone());
+        {
+            let mut records = self.records.lock().unwrap();
+            records.aggregate(self.app.search_query.query().group_by.clone());
+            records.distinct_key(self.app.search_query.query().distinct_key);
+            records.reset();
+        }
         if self.topics.is_empty() {
             tx.send(Action::StopConsuming())?;
             return Ok(());
@@ -127,7 +187,8 @@ impl Ui {
         });
         let r = self.records;
         let token = self.worker.clone();
-        let search_query = self.app.search_query.query().clone();
+        let search_query = self.app.search_query.clone();
+        let distinct_key = search_query.query().distinct_key;
         let app = self.app.clone();
         let txx = tx.clone();
         let topics = self.topics.clone();
@@ -137,6 +198,10 @@ impl Ui {
         let token_cloned = token.clone();
 
         let filters_directory = self.app.config.global.filters_dir();
+        let evaluator = Evaluator::new(&search_query, &filters_directory);
+        // When the query uses search filters, several records are evaluated at the same time, the results are read in order.
+        let (tx_evaluations, mut rx_evaluations) =
+            mpsc::channel::<Evaluation>(evaluator.concurrency());
         tokio::spawn(async move {
             loop {
                 select! {
@@ -144,25 +209,82 @@ impl Ui {
                         info!("Consumer is about to be cancelled");
                         return;
                      },
-                    Some(message) = rx_dd.recv() => {
-                        let record = KafkaRecord::parse(message, &mut schema_registry).await;
-                        let context = SearchContext::new(&record, &filters_directory);
-                        let mut ll = r.lock().unwrap();
-                        ll.new_record_read();
-                        if search_query.matches(&context) {
-                            ll.push(record);
+                    message = rx_dd.recv() => {
+                        // The consumer stopped, every record it read has been processed
+                        let Some(message) = message else {
+                            return;
+                        };
+                        let prefilter = search_query.matches_message(&message);
+                        // The compacted view needs the key of records that don't match to evict them.
+                        if prefilter == Some(false) && !distinct_key {
+                            let mut ll = r.lock().unwrap();
+                            ll.new_record_read();
+                            ll.dispatch_metrics();
+                            continue;
                         }
-                        ll.dispatch_metrics();
-                        if let Some(limit) = query.limit {
-                            if Some(ll.matched_and_read().0) >= Some(limit) {
-                                token_cloned.cancel();
-                            }
+                        let record = KafkaRecord::parse(message, &mut schema_registry).await;
+                        if tx_evaluations.send(evaluator.evaluate(record, prefilter)).await.is_err() {
+                            return;
                         }
                     }
                 }
             }
         });
 
+        let r = self.records;
+        let token_cloned = token.clone();
+        let search_query = self.app.search_query.clone();
+        let tx_notification = tx.clone();
+        tokio::spawn(async move {
+            while let Some(evaluation) = rx_evaluations.recv().await {
+                let (record, matches) = evaluation.result().await;
+                // Computed once, the records are drawn many times
+                let highlights = match matches {
+                    true => search_query
+                        .locate(&SearchContext::new(&record, &filters_directory))
+                        .unwrap_or_default(),
+                    false => vec![],
+                };
+                let mut ll = r.lock().unwrap();
+                // The records may have been refined while this one was evaluated
+                if token_cloned.is_cancelled() {
+                    break;
+                }
+                ll.new_record_read();
+                if matches {
+                    ll.push(record, highlights);
+                } else {
+                    ll.evict(&record);
+                }
+                ll.dispatch_metrics();
+                for message in take_disabled_filters() {
+                    let _ = tx_notification.send(Action::Notification(Notification::new(
+                        log::Level::Warn,
+                        message,
+                    )));
+                }
+                if let Some(reason) = aborted_by_filter() {
+                    let _ = tx_notification.send(Action::Notification(Notification::new(
+                        log::Level::Error,
+                        reason,
+                    )));
+                    token_cloned.cancel();
+                    break;
+                }
+                if let Some(limit) = query.limit {
+                    if Some(ll.matched_and_read().0) >= Some(limit) {
+                        token_cloned.cancel();
+                        break;
+                    }
+                }
+            }
+            running_search.end(&tx_notification);
+            // Every record read by the consumer has been evaluated
+            token_cloned.cancel();
+            r.lock().unwrap().sort(&query.order_by);
+            let _ = tx_notification.send(Action::StopConsuming());
+        });
+
         tokio::spawn(async move {
             let _ = tx.send(Action::Consuming);
             let consumer = match Self::create_consumer(&app, topics.clone(), txx.clone()).await {
@@ -174,19 +296,50 @@ impl Ui {
                 }
             };
             let _ = tx.send(Action::Consuming);
+            let mut sampler = match app.create_sampler(&consumer) {
+                Ok(sampler) => sampler,
+                Err(e) => {
+                    warn!("I was not able to sample the partitions: {}", e);
+                    let _ = tx.send(Action::StopConsuming());
+                    return Err("I was not able to sample the partitions");
+                }
+            };
+            // Reading the last position of every partition stops the consumer, not the processing of the records read so far
+            let sampling = token.child_token();
+            if sampler.as_ref().is_some_and(|s| s.is_empty()) {
+                sampling.cancel();
+            }
             let assignments = consumer.assignment().unwrap();
             let txx = tx.clone();
+            let sampled = sampler.as_ref().map(|s| s.len());
             tokio::spawn(async move {
-                let count = app
-                    .estimate_number_of_records_to_read(assignments)
-                    .unwrap_or(0);
+                let count = match sampled {
+                    Some(count) => count as i64,
+                    None => app
+                        .estimate_number_of_records_to_read(assignments)
+                        .unwrap_or(0),
+                };
                 let _ = txx.send(Action::RecordsToRead(count as usize));
             });
             let mut current_time = Instant::now();
             let _ = consumer
                 .stream()
-                .take_until(token.cancelled())
+                .take_until(sampling.cancelled())
                 .try_for_each(|message| {
+                    if let Some(sampler) = sampler.as_mut() {
+                        let sampled = sampler.sample(
+                            &consumer,
+                            message.topic(),
+                            message.partition(),
+                            message.offset(),
+                        );
+                        if sampler.is_empty() {
+                            sampling.cancel();
+                        }
+                        if !sampled {
+                            return futures::future::ok(());
+                        }
+                    }
                     let message = message.detach();
                     let timestamp = message.timestamp().to_millis().unwrap_or_default();
                     tx_dd.send(message).unwrap();
@@ -207,14 +360,111 @@ impl Ui {
                 .await;
             consumer.unassign().unwrap();
             info!("Consumer is terminated");
-            token.cancel();
-            r.lock().unwrap().sort(&query.order_by);
-            let _ = tx.send(Action::StopConsuming());
             Ok(())
         });
         Ok(())
     }
 
+    /// Only keeps the records already loaded that match the search query, without consuming the topics again.
+    /// The consumer is stopped so the refined records don't get mixed with new ones.
+    /// The records are evaluated like the records of a search, outside of the UI thread.
+    pub(crate) fn refine(
+        &mut self,
+        refinement: String,
+        search_query: &ValidSearchQuery,
+        tx: UnboundedSender<Action>,
+    ) -> Result<(), TuiError> {
+        let live = !self.worker.is_cancelled();
+        self.worker.cancel();
+        if let Some(running_search) = self.running_search.take() {
+            running_search.end(&tx);
+        }
+        if live {
+            tx.send(Action::Notification(Notification::new(
+                log::Level::Info,
+                "The live search is stopped to refine the records".to_string(),
+            )))?;
+        }
+        // The search filters of the refinement see the loaded records as a new search
+        reset_filter_errors();
+        let refinement_search = RunningSearch::start(search_query);
+        self.running_search = Some(refinement_search.clone());
+        self.worker = CancellationToken::new();
+        let token = self.worker.clone();
+        let (records, refinements) = {
+            let buffer = self.records.lock().unwrap();
+            (
+                buffer.iter().cloned().collect_vec(),
+                buffer.refinements().count(),
+            )
+        };
+        let evaluator = Evaluator::new(search_query, &self.app.config.global.filters_dir());
+        let (tx_evaluations, mut rx_evaluations) =
+            mpsc::channel::<Evaluation>(evaluator.concurrency());
+        let token_cloned = token.clone();
+        tokio::spawn(async move {
+            for record in records {
+                if token_cloned.is_cancelled()
+                    || tx_evaluations
+                        .send(evaluator.evaluate(record, None))
+                        .await
+                        .is_err()
+                {
+                    return;
+                }
+            }
+        });
+        let r = self.records;
+        tokio::spawn(async move {
+            let mut left = vec![];
+            while let Some(evaluation) = rx_evaluations.recv().await {
+                let (record, matches) = evaluation.result().await;
+                if matches {
+                    left.push(record);
+                }
+                if token.is_cancelled() || aborted_by_filter().is_some() {
+                    break;
+                }
+            }
+            refinement_search.end(&tx);
+            let cancelled = token.is_cancelled();
+            token.cancel();
+            let message = match aborted_by_filter() {
+                Some(reason) => Notification::new(log::Level::Error, reason),
+                // A new search or an undo happened in the meantime
+                None if cancelled || r.lock().unwrap().refinements().count() != refinements => {
+                    return;
+                }
+                None => {
+                    let left = r.lock().unwrap().refine(refinement, left);
+                    let message = match live {
+                        true => format!(
+                            "{} records left, the live search is stopped, CTRL + Z to undo",
+                            left
+                        ),
+                        false => format!("{} records left, CTRL + Z to undo", left),
+                    };
+                    Notification::new(log::Level::Info, message)
+                }
+            };
+            let _ = tx.send(Action::Notification(message));
+        });
+        Ok(())
+    }
+
+    /// Restores the records as they were before the last refinement.
+    pub(crate) fn undo_refine(&mut self, tx: UnboundedSender<Action>) -> Result<(), TuiError> {
+        let message = match self.records.lock().unwrap().undo() {
+            Some(refinement) => format!("Undo '| {}'", refinement),
+            None => "Nothing to undo".to_string(),
+        };
+        tx.send(Action::Notification(Notification::new(
+            log::Level::Info,
+            message,
+        )))?;
+        Ok(())
+    }
+
     pub(crate) fn topics_details(
         &mut self,
         topics: HashSet<String>,
@@ -276,6 +526,83 @@ impl Ui {
         Ok(())
     }
 
+    /// Watches the filters directory and dispatches [`Action::FilterChanged`] when a wasm module changes.
+    pub(crate) fn watch_filters(&mut self, action_tx: UnboundedSender<Action>) {
+        let directory = self.app.config.global.filters_dir();
+        match FilterWatcher::watch(&directory, move |name| {
+            let _ = action_tx.send(Action::FilterChanged(name));
+        }) {
+            Ok(watcher) => self.filter_watcher = Some(watcher),
+            Err(e) => warn!("Search filters won't be reloaded when they change: {}", e),
+        }
+    }
+
+    /// Loads the new version of the search filter and searches again when the search query uses it.
+    /// The search using the previous version is stopped before the new version replaces it.
+    pub(crate) async fn reload_filter(
+        &mut self,
+        name: &str,
+        tx: UnboundedSender<Action>,
+    ) -> Result<(), TuiError> {
+        if !is_filter_loaded(name) {
+            return Ok(());
+        }
+        info!("Search filter '{}' has changed", name);
+        let filters_directory = self.app.config.global.filters_dir();
+        let pool = match load_filter(name, &filters_directory) {
+            Ok(pool) => pool,
+            Err(e) => {
+                error!("Cannot reload search filter '{}': {}", name, e);
+                tx.send(Action::Notification(Notification::new(
+                    log::Level::Error,
+                    format!("Cannot reload search filter '{}': {}", name, e),
+                )))?;
+                return Ok(());
+            }
+        };
+        if !self
+            .app
+            .search_query
+            .filters()
+            .iter()
+            .any(|filter| filter.name == name)
+        {
+            replace_filter(name, pool);
+            tx.send(Action::Notification(Notification::new(
+                log::Level::Info,
+                format!("Search filter '{}' has been reloaded", name),
+            )))?;
+            return Ok(());
+        }
+        self.worker.cancel();
+        if let Some(running_search) = self.running_search.take() {
+            running_search.end(&tx);
+        }
+        replace_filter(name, pool);
+        let input = self.app.search_query.query().to_string();
+        match ValidSearchQuery::from(&input, &filters_directory) {
+            Ok(search_query) => {
+                self.app.search_query = search_query;
+                self.consume_topics(tx.clone()).await?;
+                tx.send(Action::Notification(Notification::new(
+                    log::Level::Info,
+                    format!(
+                        "Search filter '{}' has been reloaded, searching again",
+                        name
+                    ),
+                )))?;
+            }
+            Err(e) => {
+                error!("Cannot reload search filter '{}': {}", name, e);
+                tx.send(Action::Notification(Notification::new(
+                    log::Level::Error,
+                    format!("Cannot reload search filter '{}': {}", name, e),
+                )))?;
+            }
+        }
+        Ok(())
+    }
+
     pub async fn run(&mut self, topics: Vec<String>, state: State) -> Result<(), TuiError> {
         let (action_tx, mut action_rx) = mpsc::unbounded_channel();
         let records_channel = mpsc::unbounded_channel::<KafkaRecord>();
@@ -285,6 +612,7 @@ impl Ui {
         tui.enter()?;
         self.root.register_action_handler(action_tx.clone());
         self.root.init()?;
+        self.watch_filters(action_tx.clone());
         if !topics.is_empty() {
             action_tx.send(Action::SelectedTopics(topics))?;
         }
@@ -379,6 +707,15 @@ impl Ui {
                         self.app.search_query = search.clone();
                         self.consume_topics(action_tx.clone()).await?;
                     }
+                    Action::Refine(ref refinement, ref search) => {
+                        self.refine(refinement.clone(), search, action_tx.clone())?;
+                    }
+                    Action::UndoRefine => {
+                        self.undo_refine(action_tx.clone())?;
+                    }
+                    Action::FilterChanged(ref name) => {
+                        self.reload_filter(name, action_tx.clone()).await?;
+                    }
                     _ => {}
                 }
 
@@ -4,10 +4,13 @@
 //! This should be possible to increase the size but the more you display events,
 //! the more the tool gets laggy. I need to work on it.
 
+use std::collections::HashMap;
+
+use app::search::{aggregation::Aggregator, highlight::Highlight, order::compare_records};
 use circular_buffer::{CircularBuffer, Iter};
 use lib::{
     KafkaRecord,
-    search::{Order, OrderBy},
+    search::{Order, OrderBy, group_by::GroupBy, order::OrderKeyword},
 };
 use rayon::prelude::*;
 use tokio::sync::watch::{self, Receiver, Sender};
@@ -27,18 +30,20 @@ pub(crate) struct RecordsBuffer {
     pub channels: (Sender<BufferAction>, Receiver<BufferAction>),
     last_time_sorted: usize,
     matched: usize,
-}
-
-macro_rules! sort_records {
-    ($array:ident, $field: ident, $reverse: expr) => {
-        $array.par_sort_by(|a, b| {
-            let mut ordering = a.$field.cmp(&b.$field);
-            if $reverse {
-                ordering = ordering.reverse();
-            }
-            ordering
-        })
-    };
+    /// Aggregates all the matching records when the search query has a `group by` clause.
+    aggregator: Option<Aggregator>,
+    /// `true` when the aggregation must be computed again from the records of the buffer
+    aggregation_outdated: bool,
+    /// Only keeps the latest record of each key when the search query has a `distinct key` clause.
+    distinct_key: bool,
+    /// With `distinct key`, position of the record of each topic and key.
+    /// Positions are counted from the first record pushed, the record at index `i` is at position `first_position + i`.
+    positions: HashMap<(String, String), usize>,
+    first_position: usize,
+    /// Where the records match the search query, by topic, partition and offset.
+    highlights: HashMap<(String, i32, i64), Vec<Highlight>>,
+    /// Refinements applied to the records, with the records and the aggregation before each of them.
+    refinements: Vec<(String, Vec<KafkaRecord>, Option<Aggregator>)>,
 }
 
 impl Default for RecordsBuffer {
@@ -55,17 +60,89 @@ impl RecordsBuffer {
             channels: watch::channel(BufferAction::Count((0, 0, 0))),
             matched: 0,
             last_time_sorted: 0,
+            aggregator: None,
+            aggregation_outdated: false,
+            distinct_key: false,
+            positions: HashMap::new(),
+            first_position: 0,
+            highlights: HashMap::new(),
+            refinements: vec![],
         }
     }
 
     /// Empty the buffer and reset metrics
     pub fn reset(&mut self) {
         self.buffer.clear();
+        self.refinements.clear();
+        self.highlights.clear();
+        self.index_positions();
         self.read = 0;
         self.matched = 0;
+        if let Some(aggregator) = self.aggregator.as_mut() {
+            aggregator.reset();
+        }
         self.dispatch_metrics();
     }
 
+    /// Aggregates the next matching records according to the `group by` clause.
+    pub fn aggregate(&mut self, group_by: Option<GroupBy>) {
+        self.aggregator = group_by.map(Aggregator::new);
+    }
+
+    /// Returns the aggregation of the matching records.
+    /// With `distinct key`, only the latest record of each key, the records of the buffer, are aggregated.
+    pub fn aggregator(&mut self) -> Option<&Aggregator> {
+        if self.aggregation_outdated {
+            self.aggregation_outdated = false;
+            if let Some(aggregator) = self.aggregator.as_mut() {
+                aggregator.reset();
+                for record in self.buffer.iter() {
+                    aggregator.push(record);
+                }
+            }
+        }
+        self.aggregator.as_ref()
+    }
+
+    /// Only keeps the latest record of each key.
+    pub fn distinct_key(&mut self, distinct_key: bool) {
+        self.distinct_key = distinct_key;
+    }
+
+    /// Removes the previous version of the key of this record, when the buffer is a compacted view.
+    /// This is called for records that don't match the search query anymore.
+    /// The last record takes the place of the removed one until the buffer is sorted again.
+    pub fn evict(&mut self, kafka_record: &KafkaRecord) {
+        if !self.distinct_key {
+            return;
+        }
+        let Some(position) = self.positions.remove(&key_of(kafka_record)) else {
+            return;
+        };
+        let index = position - self.first_position;
+        if let Some(removed) = self.buffer.swap_remove_back(index) {
+            self.highlights.remove(&id_of(&removed));
+        }
+        if let Some(moved) = self.buffer.get(index) {
+            self.positions.insert(key_of(moved), position);
+        }
+        self.aggregation_outdated = true;
+    }
+
+    /// Computes the position of the record of each key, after the records have been moved.
+    fn index_positions(&mut self) {
+        self.positions.clear();
+        self.first_position = 0;
+        if self.distinct_key {
+            self.positions = self
+                .buffer
+                .iter()
+                .enumerate()
+                .map(|(index, record)| (key_of(record), index))
+                .collect();
+        }
+    }
+
     /// Returns the metrics of the number of records matched and read.
     pub fn matched_and_read(&self) -> (usize, usize, usize) {
         (self.matched, self.read, self.buffer.len())
@@ -80,16 +157,80 @@ impl RecordsBuffer {
         self.buffer.get(index)
     }
 
-    pub fn iter(&self) -> Iter<KafkaRecord> {
+    pub fn iter(&self) -> Iter<'_, KafkaRecord> {
         self.buffer.iter()
     }
 
-    pub fn push(&mut self, kafka_record: KafkaRecord) -> usize {
-        self.buffer.push_back(kafka_record);
+    /// Returns where the record matches the search query.
+    pub fn highlights(&self, record: &KafkaRecord) -> &[Highlight] {
+        self.highlights
+            .get(&id_of(record))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Adds a matching record, with where it matches the search query.
+    pub fn push(&mut self, kafka_record: KafkaRecord, highlights: Vec<Highlight>) -> usize {
+        self.evict(&kafka_record);
         self.matched += 1;
+        if self.distinct_key && kafka_record.tombstone {
+            return self.matched;
+        }
+        if let Some(aggregator) = self.aggregator.as_mut() {
+            aggregator.push(&kafka_record);
+        }
+        // With `distinct key`, the oldest key leaving the buffer leaves the aggregation
+        self.aggregation_outdated |= self.distinct_key && self.buffer.is_full();
+        if self.distinct_key {
+            let position = self.first_position + self.buffer.len();
+            self.positions.insert(key_of(&kafka_record), position);
+        }
+        if !highlights.is_empty() {
+            self.highlights.insert(id_of(&kafka_record), highlights);
+        }
+        if let Some(dropped) = self.buffer.push_back(kafka_record) {
+            self.first_position += 1;
+            self.highlights.remove(&id_of(&dropped));
+            if self.distinct_key {
+                self.positions.remove(&key_of(&dropped));
+            }
+        }
         self.matched
     }
 
+    /// Only keeps the records left by the refinement. The previous records are kept for [`RecordsBuffer::undo`].
+    /// The aggregation is computed again from the records left.
+    /// Returns the number of records left.
+    pub fn refine(&mut self, refinement: String, left: Vec<KafkaRecord>) -> usize {
+        let aggregator = self.aggregator().cloned();
+        let records = self.buffer.to_vec();
+        self.buffer.clear();
+        self.buffer.extend(left);
+        self.refinements.push((refinement, records, aggregator));
+        self.index_positions();
+        self.aggregation_outdated = true;
+        self.dispatch_metrics();
+        self.buffer.len()
+    }
+
+    /// Restores the records as they were before the last refinement.
+    /// Returns the refinement that was undone.
+    pub fn undo(&mut self) -> Option<String> {
+        let (refinement, records, aggregator) = self.refinements.pop()?;
+        self.buffer.clear();
+        self.buffer.extend(records);
+        self.aggregator = aggregator;
+        self.aggregation_outdated = false;
+        self.index_positions();
+        self.dispatch_metrics();
+        Some(refinement)
+    }
+
+    /// The refinements applied to the records, from the first to the last.
+    pub fn refinements(&self) -> impl Iterator<Item = &str> {
+        self.refinements.iter().map(|(r, _, _)| r.as_str())
+    }
+
     /// Dispatches a new events about the metrics of the buffer
     pub fn dispatch_metrics(&mut self) {
         self.channels
@@ -98,43 +239,34 @@ impl RecordsBuffer {
             .unwrap();
     }
 
-    /// Sort the buffer by the given order
+    /// Sort the buffer by the given order, records are sorted by timestamp when the query has no `order by` clause.
     pub fn sort(&mut self, order_by: &OrderBy) {
         let mut unsorted = self.buffer.to_vec();
         if self.read == self.last_time_sorted {
             return;
         }
-        let reverse = order_by.is_descending();
-        match order_by.order {
-            Order::Timestamp => {
-                sort_records!(unsorted, timestamp, reverse)
-            }
-            Order::Key => {
-                sort_records!(unsorted, key_as_string, reverse)
-            }
-            Order::Value => sort_records!(unsorted, value_as_string, reverse),
-            Order::Partition => {
-                sort_records!(unsorted, partition, reverse)
-            }
-            Order::Offset => {
-                sort_records!(unsorted, offset, reverse)
-            }
-            Order::Size => unsorted.sort_by(|a, b| {
-                let mut ordering = a.size.cmp(&b.size);
-                if order_by.is_descending() {
-                    ordering = ordering.reverse();
-                }
-                ordering
-            }),
-            Order::Topic => {
-                sort_records!(unsorted, topic, reverse)
-            }
-        }
+        let default_order = OrderBy::new(Order::Timestamp, OrderKeyword::Asc);
+        let order_by = match order_by.is_empty() {
+            true => &default_order,
+            false => order_by,
+        };
+        unsorted.par_sort_by(|a, b| compare_records(order_by, a, b));
         self.buffer.clear();
-        self.buffer.extend(unsorted)
+        self.buffer.extend(unsorted);
+        self.index_positions();
     }
 }
 
+/// Identifies a record by its topic, partition and offset.
+fn id_of(record: &KafkaRecord) -> (String, i32, i64) {
+    (record.topic.clone(), record.partition, record.offset)
+}
+
+/// Identifies the records of a key in the compacted view.
+fn key_of(record: &KafkaRecord) -> (String, String) {
+    (record.topic.clone(), record.key_as_string.clone())
+}
+
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum BufferAction {
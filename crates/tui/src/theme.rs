@@ -30,6 +30,12 @@ pub struct Theme {
     pub fg_active: Color,
     pub dialog_border: Color,
     pub autocomplete: Color,
+    /// Background of the matches of the search query
+    #[serde(default = "Theme::default_bg_highlight")]
+    pub bg_highlight: Color,
+    /// Foreground of the matches of the search query
+    #[serde(default = "Theme::default_fg_highlight")]
+    pub fg_highlight: Color,
 }
 
 impl Theme {
@@ -58,6 +64,16 @@ impl Theme {
             fg_disabled: Color::Black,
             bg_active: Color::Green,
             fg_active: Color::Black,
+            bg_highlight: Self::default_bg_highlight(),
+            fg_highlight: Self::default_fg_highlight(),
         }
     }
+
+    fn default_bg_highlight() -> Color {
+        Color::Yellow
+    }
+
+    fn default_fg_highlight() -> Color {
+        Color::Black
+    }
 }
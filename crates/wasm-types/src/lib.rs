@@ -1,6 +1,9 @@
 //! Crate containing the types used to define search filters.
+pub use lib::FilterDescription;
 pub use lib::FilterResult;
 use lib::KafkaRecord;
+pub use lib::ParameterDescription;
+pub use lib::TransformResult;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 